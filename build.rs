@@ -40,6 +40,50 @@ fn build_alkane(wasm_str: &str, features: Vec<&'static str>) -> Result<()> {
     }
 }
 
+/// Build the mock Oyl factory/pool contract used by the graduation tests
+///
+/// Panics with cargo's output if the build fails, rather than leaving the
+/// tests to embed a stale or missing wasm.
+fn build_oyl_mock(wasm_str: &str) -> Result<()> {
+    let output = Command::new("cargo")
+        .env("CARGO_TARGET_DIR", wasm_str)
+        .current_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("mocks").join("oyl-mock"))
+        .arg("build")
+        .arg("--release")
+        .arg("--target")
+        .arg("wasm32-unknown-unknown")
+        .output()?;
+    if !output.status.success() {
+        panic!(
+            "building mocks/oyl-mock failed ({}):\n{}{}",
+            output.status,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(())
+}
+
+/// Compress `<mod_name>.wasm` next to itself and embed it in
+/// `std/<mod_name>_build.rs` as `get_bytes()`
+fn write_test_build(wasm_str: &str, write_dir: &Path, mod_name: &str) -> Result<()> {
+    let release_dir = Path::new(wasm_str).join("wasm32-unknown-unknown").join("release");
+    let f: Vec<u8> = fs::read(&release_dir.join(mod_name.to_owned() + ".wasm"))?;
+    let compressed: Vec<u8> = compress(f.clone())?;
+    fs::write(&release_dir.join(mod_name.to_owned() + ".wasm.gz"), &compressed)?;
+
+    let data: String = hex::encode(&f);
+    let target = write_dir.join("std").join(mod_name.to_owned() + "_build.rs");
+    fs::write(
+        &target,
+        String::from("use hex_lit::hex;\n#[allow(long_running_const_eval)]\npub fn get_bytes() -> Vec<u8> { (&hex!(\"")
+            + data.as_str()
+            + "\")).to_vec() }",
+    )?;
+    eprintln!("build: {}", target.into_os_string().to_str().unwrap());
+    Ok(())
+}
+
 fn main() {
     // Guard against recursive execution
     if std::env::var("BONDING_CURVE_BUILD_IN_PROGRESS").is_ok() {
@@ -62,15 +106,8 @@ fn main() {
     let wasm_dir = base_dir.parent().unwrap().join("alkanes");
     fs::create_dir_all(&wasm_dir).unwrap();
     let wasm_str = wasm_dir.to_str().unwrap();
-    let write_dir = Path::new(&out_dir)
-        .parent()
-        .unwrap()
-        .parent()
-        .unwrap()
-        .parent()
-        .unwrap()
-        .join("src")
-        .join("tests");
+    // Generated test builds live in this crate's src/tests/std
+    let write_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src").join("tests");
 
     fs::create_dir_all(&write_dir.join("std")).unwrap();
     // Don't change directory - stay in current project directory
@@ -86,48 +123,12 @@ fn main() {
     // Use a separate target directory specifically for the WASM build
     // to avoid triggering the main build process again
     build_alkane(wasm_str, vec![]).unwrap();
-    let mod_name = "bonding_curve_system".to_owned();
-    eprintln!(
-        "write: {}",
-        write_dir
-            .join("std")
-            .join(mod_name.clone() + "_build.rs")
-            .into_os_string()
-            .to_str()
-            .unwrap()
-    );
-    let f: Vec<u8> = fs::read(
-        &Path::new(&wasm_str)
-            .join("wasm32-unknown-unknown")
-            .join("release")
-            .join(mod_name.clone() + ".wasm"),
-    )
-    .unwrap();
-    let compressed: Vec<u8> = compress(f.clone()).unwrap();
-    fs::write(
-        &Path::new(&wasm_str)
-            .join("wasm32-unknown-unknown")
-            .join("release")
-            .join(mod_name.clone() + ".wasm.gz"),
-        &compressed,
-    )
-    .unwrap();
-    let data: String = hex::encode(&f);
-    fs::write(
-                &write_dir.join("std").join(mod_name.clone() + "_build.rs"),
-                String::from("use hex_lit::hex;\n#[allow(long_running_const_eval)]\npub fn get_bytes() -> Vec<u8> { (&hex!(\"")
-                    + data.as_str()
-                    + "\")).to_vec() }",
-            ).unwrap();
-    eprintln!(
-        "build: {}",
-        write_dir
-            .join("std")
-            .join(mod_name.clone() + "_build.rs")
-            .into_os_string()
-            .to_str()
-            .unwrap()
-    );
+    build_oyl_mock(wasm_str).unwrap();
+
+    let mod_names = ["bonding_curve_system", "oyl_mock"];
+    for mod_name in mod_names {
+        write_test_build(wasm_str, &write_dir, mod_name).unwrap();
+    }
 
     eprintln!(
         "write test builds to: {}",
@@ -138,9 +139,9 @@ fn main() {
             .to_str()
             .unwrap()
     );
-    fs::write(
-        &write_dir.join("std").join("mod.rs"),
-        "pub mod ".to_owned() + mod_name.as_str() + "_build;\n",
-    )
-    .unwrap();
+    let mod_rs: String = mod_names
+        .iter()
+        .map(|mod_name| "pub mod ".to_owned() + mod_name + "_build;\n")
+        .collect();
+    fs::write(&write_dir.join("std").join("mod.rs"), mod_rs).unwrap();
 }
@@ -0,0 +1,324 @@
+//! Mock Oyl AMM
+//!
+//! Stand-in for the Oyl factory and pool in graduation tests. One contract
+//! plays both parts: `CreateNewPool` seeds a single pool held by the contract
+//! itself, whose LP token is the contract's own id, as an Oyl pool's is.
+//! Every deposit is checked against the alkanes actually sent with it, so
+//! reserves only ever count tokens that reached the pool. Like Oyl it keeps
+//! the pair sorted by id, so token a is the lower of the two whichever order
+//! they were passed in.
+//!
+//! Opcodes and layouts follow the factory and pool contracts of
+//! https://github.com/Oyl-Wallet/oyl-amm. The factory and pool share opcodes
+//! 1 and 2, so dispatch is written by hand and tells them apart by their
+//! inputs: the factory's `CreateNewPool` and `FindExistingPoolId` take token
+//! ids, the pool's `AddLiquidity` and `WithdrawAndBurn` take none.
+
+use alkanes_runtime::storage::StoragePointer;
+use alkanes_runtime::{declare_alkane, message::MessageDispatch, runtime::AlkaneResponder};
+use alkanes_support::context::Context;
+use alkanes_support::id::AlkaneId;
+use alkanes_support::parcel::AlkaneTransfer;
+use alkanes_support::response::CallResponse;
+use alkanes_support::utils::overflow_error;
+use anyhow::{anyhow, Result};
+use metashrew_support::index_pointer::KeyValuePointer;
+use std::sync::Arc;
+
+/// LP locked on the first mint, as Oyl pools do
+pub const MINIMUM_LIQUIDITY: u128 = 1_000;
+
+#[derive(Default)]
+pub struct MockOyl(());
+
+enum MockOylMessage {
+    Initialize,
+
+    /// Factory (1): create the pool and seed it with `amount_a` and
+    /// `amount_b`, which must both be sent
+    CreateNewPool {
+        token_a: AlkaneId,
+        token_b: AlkaneId,
+        amount_a: u128,
+        amount_b: u128,
+    },
+
+    /// Factory (2): id of the pool for a token pair
+    FindExistingPoolId {
+        token_a: AlkaneId,
+        token_b: AlkaneId,
+    },
+
+    /// Pool (1): mint LP for both pool tokens sent
+    AddLiquidity,
+
+    /// Pool (2): burn the LP tokens sent and return their share of both
+    /// reserves
+    WithdrawAndBurn,
+
+    /// Pool (97): reserve a and reserve b as LE u128s
+    GetReserves,
+
+    /// Pool (999): token a, token b, reserve a, reserve b and LP supply as LE
+    /// u128s, followed by the pool name
+    PoolDetails,
+}
+
+impl MessageDispatch<MockOyl> for MockOylMessage {
+    fn from_opcode(opcode: u128, inputs: Vec<u128>) -> Result<Self> {
+        let id = |i: usize| AlkaneId { block: inputs[i], tx: inputs[i + 1] };
+        match (opcode, inputs.len()) {
+            (0, _) => Ok(Self::Initialize),
+            (1, 0) => Ok(Self::AddLiquidity),
+            (1, 6) => Ok(Self::CreateNewPool {
+                token_a: id(0),
+                token_b: id(2),
+                amount_a: inputs[4],
+                amount_b: inputs[5],
+            }),
+            (2, 0) => Ok(Self::WithdrawAndBurn),
+            (2, 4) => Ok(Self::FindExistingPoolId { token_a: id(0), token_b: id(2) }),
+            (97, _) => Ok(Self::GetReserves),
+            (999, _) => Ok(Self::PoolDetails),
+            _ => Err(anyhow!("Unknown opcode {} with {} inputs", opcode, inputs.len())),
+        }
+    }
+
+    fn dispatch(&self, responder: &MockOyl) -> Result<CallResponse> {
+        match self {
+            Self::Initialize => responder.initialize(),
+            Self::CreateNewPool { token_a, token_b, amount_a, amount_b } => {
+                responder.create_new_pool(token_a, token_b, *amount_a, *amount_b)
+            },
+            Self::FindExistingPoolId { token_a, token_b } => responder.find_existing_pool_id(token_a, token_b),
+            Self::AddLiquidity => responder.add_liquidity(),
+            Self::WithdrawAndBurn => responder.withdraw_and_burn(),
+            Self::GetReserves => responder.get_reserves(),
+            Self::PoolDetails => responder.pool_details(),
+        }
+    }
+
+    fn export_abi() -> Vec<u8> {
+        Vec::new()
+    }
+}
+
+fn pointer(key: &str) -> StoragePointer {
+    StoragePointer::from_keyword(key)
+}
+
+fn get_id(key: &str) -> Option<AlkaneId> {
+    let data = pointer(key).get();
+    if data.len() < 32 {
+        return None;
+    }
+    Some(AlkaneId {
+        block: u128::from_le_bytes(data[0..16].try_into().ok()?),
+        tx: u128::from_le_bytes(data[16..32].try_into().ok()?),
+    })
+}
+
+fn id_bytes(id: &AlkaneId) -> Vec<u8> {
+    let mut data = id.block.to_le_bytes().to_vec();
+    data.extend_from_slice(&id.tx.to_le_bytes());
+    data
+}
+
+fn set_id(key: &str, id: &AlkaneId) {
+    pointer(key).set(Arc::new(id_bytes(id)));
+}
+
+/// Amount of `id` sent with the call
+fn received(context: &Context, id: &AlkaneId) -> Result<u128> {
+    let mut amount = 0u128;
+    for transfer in context.incoming_alkanes.0.iter().filter(|transfer| &transfer.id == id) {
+        amount = overflow_error(amount.checked_add(transfer.value))?;
+    }
+    Ok(amount)
+}
+
+/// `a * b / c`, rounded down
+fn mul_div(a: u128, b: u128, c: u128) -> Result<u128> {
+    if c == 0 {
+        return Err(anyhow!("Pool is empty"));
+    }
+    Ok(overflow_error(a.checked_mul(b))? / c)
+}
+
+/// Largest `r` with `r * r <= n`
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = (x + 1) / 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+impl MockOyl {
+    fn reserves() -> (u128, u128, u128) {
+        (
+            pointer("/pool/reserve_a").get_value::<u128>(),
+            pointer("/pool/reserve_b").get_value::<u128>(),
+            pointer("/pool/lp_supply").get_value::<u128>(),
+        )
+    }
+
+    /// The pool's pair, token a first
+    fn pair() -> Result<(AlkaneId, AlkaneId)> {
+        match (get_id("/pool/token_a"), get_id("/pool/token_b")) {
+            (Some(token_a), Some(token_b)) => Ok((token_a, token_b)),
+            _ => Err(anyhow!("Pool not initialized")),
+        }
+    }
+
+    fn set_reserves(reserve_a: u128, reserve_b: u128, lp_supply: u128) {
+        pointer("/pool/reserve_a").set_value::<u128>(reserve_a);
+        pointer("/pool/reserve_b").set_value::<u128>(reserve_b);
+        pointer("/pool/lp_supply").set_value::<u128>(lp_supply);
+    }
+
+    /// Response minting `lp` to the caller, with the pool id as data
+    fn lp_response(&self, lp: u128) -> Result<CallResponse> {
+        let context = self.context()?;
+        let mut response = CallResponse::default();
+        response.alkanes.0.push(AlkaneTransfer { id: context.myself.clone(), value: lp });
+        response.data = id_bytes(&context.myself);
+        Ok(response)
+    }
+
+    fn initialize(&self) -> Result<CallResponse> {
+        Ok(CallResponse::default())
+    }
+
+    fn create_new_pool(
+        &self,
+        token_a: &AlkaneId,
+        token_b: &AlkaneId,
+        amount_a: u128,
+        amount_b: u128,
+    ) -> Result<CallResponse> {
+        if get_id("/pool/token_a").is_some() {
+            return Err(anyhow!("Pool already exists"));
+        }
+
+        // The amounts asked for must be the amounts sent
+        let context = self.context()?;
+        for (token, amount) in [(token_a, amount_a), (token_b, amount_b)] {
+            let sent = received(&context, token)?;
+            if sent != amount {
+                return Err(anyhow!("CreateNewPool asked for {} of {:?}, received {}", amount, token, sent));
+            }
+        }
+
+        let product = overflow_error(amount_a.checked_mul(amount_b))?;
+        let liquidity = isqrt(product);
+        if liquidity <= MINIMUM_LIQUIDITY {
+            return Err(anyhow!("Insufficient liquidity minted"));
+        }
+
+        let mut pair = [(token_a.clone(), amount_a), (token_b.clone(), amount_b)];
+        pair.sort_by_key(|(id, _)| (id.block, id.tx));
+        let [(token_a, reserve_a), (token_b, reserve_b)] = pair;
+
+        set_id("/pool/token_a", &token_a);
+        set_id("/pool/token_b", &token_b);
+        Self::set_reserves(reserve_a, reserve_b, liquidity);
+
+        self.lp_response(liquidity - MINIMUM_LIQUIDITY)
+    }
+
+    fn find_existing_pool_id(&self, token_a: &AlkaneId, token_b: &AlkaneId) -> Result<CallResponse> {
+        let (a, b) = (get_id("/pool/token_a"), get_id("/pool/token_b"));
+        let matches = (a.as_ref(), b.as_ref()) == (Some(token_a), Some(token_b))
+            || (a.as_ref(), b.as_ref()) == (Some(token_b), Some(token_a));
+        if !matches {
+            return Err(anyhow!("Pool not found"));
+        }
+
+        let mut response = CallResponse::default();
+        response.data = id_bytes(&self.context()?.myself);
+        Ok(response)
+    }
+
+    /// Mint LP for the smaller of the two deposits' shares of the reserves,
+    /// keeping both deposits
+    fn add_liquidity(&self) -> Result<CallResponse> {
+        let (token_a, token_b) = Self::pair()?;
+        let (reserve_a, reserve_b, lp_supply) = Self::reserves();
+
+        let context = self.context()?;
+        let (amount_a, amount_b) = (received(&context, &token_a)?, received(&context, &token_b)?);
+        if amount_a == 0 || amount_b == 0 {
+            return Err(anyhow!("AddLiquidity needs both pool tokens, received {} and {}", amount_a, amount_b));
+        }
+
+        let lp = mul_div(amount_a, lp_supply, reserve_a)?.min(mul_div(amount_b, lp_supply, reserve_b)?);
+        if lp == 0 {
+            return Err(anyhow!("Insufficient liquidity minted"));
+        }
+
+        Self::set_reserves(
+            overflow_error(reserve_a.checked_add(amount_a))?,
+            overflow_error(reserve_b.checked_add(amount_b))?,
+            overflow_error(lp_supply.checked_add(lp))?,
+        );
+        self.lp_response(lp)
+    }
+
+    /// Burn the LP tokens sent, paying out their share of each reserve
+    fn withdraw_and_burn(&self) -> Result<CallResponse> {
+        let (token_a, token_b) = Self::pair()?;
+        let (reserve_a, reserve_b, lp_supply) = Self::reserves();
+
+        let context = self.context()?;
+        let lp = received(&context, &context.myself)?;
+        if lp == 0 || lp > lp_supply {
+            return Err(anyhow!("WithdrawAndBurn received {} of {} LP tokens", lp, lp_supply));
+        }
+
+        let (amount_a, amount_b) = (mul_div(reserve_a, lp, lp_supply)?, mul_div(reserve_b, lp, lp_supply)?);
+        Self::set_reserves(reserve_a - amount_a, reserve_b - amount_b, lp_supply - lp);
+
+        let mut response = CallResponse::default();
+        response.alkanes.0.push(AlkaneTransfer { id: token_a, value: amount_a });
+        response.alkanes.0.push(AlkaneTransfer { id: token_b, value: amount_b });
+        Ok(response)
+    }
+
+    fn get_reserves(&self) -> Result<CallResponse> {
+        let (reserve_a, reserve_b, _) = Self::reserves();
+        let mut response = CallResponse::default();
+        response.data = [reserve_a, reserve_b]
+            .iter()
+            .flat_map(|field| field.to_le_bytes())
+            .collect();
+        Ok(response)
+    }
+
+    fn pool_details(&self) -> Result<CallResponse> {
+        let (token_a, token_b) = Self::pair()?;
+        let (reserve_a, reserve_b, lp_supply) = Self::reserves();
+
+        let mut response = CallResponse::default();
+        response.data = id_bytes(&token_a);
+        response.data.extend(id_bytes(&token_b));
+        for field in [reserve_a, reserve_b, lp_supply] {
+            response.data.extend_from_slice(&field.to_le_bytes());
+        }
+        response.data.extend_from_slice(b"MOCK LP");
+        Ok(response)
+    }
+}
+
+impl AlkaneResponder for MockOyl {}
+
+declare_alkane! {
+    impl AlkaneResponder for MockOyl {
+        type Message = MockOylMessage;
+    }
+}
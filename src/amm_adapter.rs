@@ -0,0 +1,277 @@
+//! AMM Adapters
+//!
+//! Graduation talks to an AMM only through the `AmmAdapter` trait, so a
+//! launch can graduate to any alkanes AMM that has an adapter. Each curve
+//! stores the `AmmBackend` it graduates to; the backend picks the adapter
+//! and the factory deployment it calls.
+//!
+//! `OylAdapter` speaks the Oyl factory and pool opcodes. It also serves
+//! Oyl-compatible deployments when the backend names their factory.
+//!
+//! `burn_lp` redeems LP tokens for the pool's tokens. Graduation never calls
+//! it: locking the LP in the curve is the graduation policy, since burning
+//! would withdraw the liquidity the curve just seeded.
+
+use crate::amm_integration::AlkaneCaller;
+use alkanes_support::cellpack::Cellpack;
+use alkanes_support::id::AlkaneId;
+use alkanes_support::parcel::{AlkaneTransfer, AlkaneTransferParcel};
+use alkanes_support::response::CallResponse;
+use alkanes_support::utils::overflow_error;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+// Oyl Factory contract address from official deployment (mainnet)
+// Source: https://docs.oyl.io/developer/deployment-ids
+pub const OYL_FACTORY_PROXY: AlkaneId = AlkaneId { block: 4u128, tx: 65522u128 }; // Factory Proxy (mainnet)
+
+// Oyl AMM opcodes, as dispatched by the factory and pool contracts
+// Source: https://github.com/Oyl-Wallet/oyl-amm (alkanes/factory, alkanes/pool)
+const FACTORY_CREATE_NEW_POOL: u128 = 1; // [token_a, token_b, amount_a, amount_b], both tokens sent
+const POOL_ADD_LIQUIDITY: u128 = 1;      // mints LP for the pair tokens sent
+const POOL_BURN: u128 = 2;               // WithdrawAndBurn: burns the LP sent, returns both tokens
+const POOL_DETAILS: u128 = 999;          // token_a, token_b, reserve_a, reserve_b, LP supply as LE u128s, then the name
+
+/// Operations graduation needs from an AMM
+///
+/// Token pairs are passed as `(token_a, token_b)` transfers. A pool may keep
+/// its pair in another order (Oyl sorts it by id), so reserves are asked
+/// for by token id.
+pub trait AmmAdapter {
+    /// Create the pool for the pair, seeded with both transfers. Returns the
+    /// pool id and the LP tokens it minted to the caller.
+    fn create_pool(&self, token_a: AlkaneTransfer, token_b: AlkaneTransfer) -> Result<(AlkaneId, u128)>;
+
+    /// Add both transfers to `pool`, returning the LP tokens minted. Graduation
+    /// seeds the pool through `create_pool` and does not call this.
+    fn add_liquidity(&self, pool: &AlkaneId, token_a: AlkaneTransfer, token_b: AlkaneTransfer) -> Result<u128>;
+
+    /// Reserves of `token_a` and `token_b` in `pool`, in that order
+    fn get_reserves(&self, pool: &AlkaneId, token_a: &AlkaneId, token_b: &AlkaneId) -> Result<(u128, u128)>;
+
+    /// Burn `lp_amount` of `pool`'s LP tokens, returning the amounts of
+    /// `token_a` and `token_b` withdrawn, in that order
+    fn burn_lp(&self, pool: &AlkaneId, lp_amount: u128, token_a: &AlkaneId, token_b: &AlkaneId) -> Result<(u128, u128)>;
+}
+
+/// AMM implementations a curve can graduate to. Only Oyl, and deployments
+/// speaking its opcodes, are supported so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AmmKind {
+    #[default]
+    Oyl,
+}
+
+impl AmmKind {
+    /// Decode the opcode representation (0 = Oyl)
+    pub fn from_u128(value: u128) -> Result<Self> {
+        match value {
+            0 => Ok(AmmKind::Oyl),
+            _ => Err(anyhow!("Invalid AMM backend")),
+        }
+    }
+
+    /// Factory the AMM is deployed at on mainnet
+    pub fn default_factory(&self) -> AlkaneId {
+        match self {
+            AmmKind::Oyl => OYL_FACTORY_PROXY,
+        }
+    }
+}
+
+/// AMM a curve graduates to, stored with its parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct AmmBackend {
+    #[serde(default)]
+    pub kind: AmmKind,
+    #[serde(default)]
+    pub factory: Option<(u128, u128)>, // Factory (block, tx); None = the kind's mainnet deployment
+}
+
+impl AmmBackend {
+    /// Backend from its opcode arguments; a 0:0 factory means the default
+    pub fn from_args(kind: u128, factory_block: u128, factory_tx: u128) -> Result<Self> {
+        let factory = match (factory_block, factory_tx) {
+            (0, 0) => None,
+            id => Some(id),
+        };
+        Ok(Self { kind: AmmKind::from_u128(kind)?, factory })
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if let Some((0, _)) = self.factory {
+            return Err(anyhow!("AMM factory must be a deployed alkane"));
+        }
+        Ok(())
+    }
+
+    /// Factory graduation calls
+    pub fn factory_id(&self) -> AlkaneId {
+        self.factory
+            .map_or_else(|| self.kind.default_factory(), |(block, tx)| AlkaneId { block, tx })
+    }
+
+    /// Adapter reaching this backend through `caller`
+    pub fn adapter<'a>(&self, caller: &'a dyn AlkaneCaller) -> Box<dyn AmmAdapter + 'a> {
+        match self.kind {
+            AmmKind::Oyl => Box::new(OylAdapter::new(caller, self.factory_id())),
+        }
+    }
+}
+
+/// Oyl factory and pools
+pub struct OylAdapter<'a> {
+    caller: &'a dyn AlkaneCaller,
+    factory: AlkaneId,
+}
+
+impl<'a> OylAdapter<'a> {
+    pub fn new(caller: &'a dyn AlkaneCaller, factory: AlkaneId) -> Self {
+        Self { caller, factory }
+    }
+
+    /// Pool id from a create-pool response: the leading `(block, tx)` u128
+    /// pair of its data, or else the LP token it returns, which an Oyl pool
+    /// mints under its own id
+    fn decode_pool_id(response: &CallResponse, inputs: &[AlkaneId]) -> Result<AlkaneId> {
+        if response.data.len() >= 32 {
+            let block = u128::from_le_bytes(response.data[0..16].try_into()?);
+            let tx = u128::from_le_bytes(response.data[16..32].try_into()?);
+            return Ok(AlkaneId { block, tx });
+        }
+
+        response.alkanes.0
+            .iter()
+            .find(|transfer| !inputs.contains(&transfer.id))
+            .map(|transfer| transfer.id.clone())
+            .ok_or_else(|| anyhow!("Oyl factory response carries no pool id"))
+    }
+
+    /// LP tokens returned in a response, minted under the pool id
+    fn received_lp_tokens(response: &CallResponse, pool: &AlkaneId) -> Result<u128> {
+        let mut lp_tokens = 0u128;
+        for transfer in response.alkanes.0.iter().filter(|transfer| &transfer.id == pool) {
+            lp_tokens = overflow_error(lp_tokens.checked_add(transfer.value))?;
+        }
+        if lp_tokens == 0 {
+            return Err(anyhow!("Oyl pool returned no LP tokens"));
+        }
+        Ok(lp_tokens)
+    }
+
+}
+
+impl AmmAdapter for OylAdapter<'_> {
+    /// `[1, a.block, a.tx, b.block, b.tx, a.amount, b.amount]` sent to the
+    /// factory with both transfers attached
+    fn create_pool(&self, token_a: AlkaneTransfer, token_b: AlkaneTransfer) -> Result<(AlkaneId, u128)> {
+        let inputs = [token_a.id.clone(), token_b.id.clone()];
+        let cellpack = Cellpack {
+            target: self.factory.clone(),
+            inputs: vec![
+                FACTORY_CREATE_NEW_POOL,
+                token_a.id.block,
+                token_a.id.tx,
+                token_b.id.block,
+                token_b.id.tx,
+                token_a.value,
+                token_b.value,
+            ],
+        };
+        let response = self
+            .caller
+            .call_alkane(&cellpack, &AlkaneTransferParcel(vec![token_a, token_b]))
+            .map_err(|e| anyhow!("Oyl factory failed to create pool: {}", e))?;
+        let pool = Self::decode_pool_id(&response, &inputs)?;
+        let lp_tokens = Self::received_lp_tokens(&response, &pool)?;
+        Ok((pool, lp_tokens))
+    }
+
+    /// Sends both transfers straight to the pool, which mints LP for them
+    fn add_liquidity(&self, pool: &AlkaneId, token_a: AlkaneTransfer, token_b: AlkaneTransfer) -> Result<u128> {
+        let cellpack = Cellpack {
+            target: pool.clone(),
+            inputs: vec![POOL_ADD_LIQUIDITY],
+        };
+        let response = self
+            .caller
+            .call_alkane(&cellpack, &AlkaneTransferParcel(vec![token_a, token_b]))
+            .map_err(|e| anyhow!("Oyl pool failed to add liquidity: {}", e))?;
+        Self::received_lp_tokens(&response, pool)
+    }
+
+    /// `POOL_DETAILS` names the pool's tokens next to their reserves, so
+    /// each reserve is matched to its token whichever way the pool sorted
+    /// the pair
+    fn get_reserves(&self, pool: &AlkaneId, token_a: &AlkaneId, token_b: &AlkaneId) -> Result<(u128, u128)> {
+        let cellpack = Cellpack {
+            target: pool.clone(),
+            inputs: vec![POOL_DETAILS],
+        };
+        let response = self
+            .caller
+            .call_alkane(&cellpack, &AlkaneTransferParcel::default())
+            .map_err(|e| anyhow!("Oyl pool failed to report reserves: {}", e))?;
+        if response.data.len() < 96 {
+            return Err(anyhow!("Oyl pool details response too short"));
+        }
+        let field = |i: usize| -> Result<u128> {
+            Ok(u128::from_le_bytes(response.data[i * 16..(i + 1) * 16].try_into()?))
+        };
+        let pool_a = AlkaneId { block: field(0)?, tx: field(1)? };
+        let pool_b = AlkaneId { block: field(2)?, tx: field(3)? };
+        let (reserve_a, reserve_b) = (field(4)?, field(5)?);
+
+        if (&pool_a, &pool_b) == (token_a, token_b) {
+            Ok((reserve_a, reserve_b))
+        } else if (&pool_a, &pool_b) == (token_b, token_a) {
+            Ok((reserve_b, reserve_a))
+        } else {
+            Err(anyhow!("Oyl pool {:?} does not hold {:?} and {:?}", pool, token_a, token_b))
+        }
+    }
+
+    /// Sends the LP tokens, minted under the pool id, back to the pool
+    fn burn_lp(&self, pool: &AlkaneId, lp_amount: u128, token_a: &AlkaneId, token_b: &AlkaneId) -> Result<(u128, u128)> {
+        let cellpack = Cellpack {
+            target: pool.clone(),
+            inputs: vec![POOL_BURN],
+        };
+        let outgoing = AlkaneTransferParcel(vec![AlkaneTransfer { id: pool.clone(), value: lp_amount }]);
+        let response = self
+            .caller
+            .call_alkane(&cellpack, &outgoing)
+            .map_err(|e| anyhow!("Oyl pool failed to burn LP tokens: {}", e))?;
+
+        let withdrawn = |token: &AlkaneId| -> Result<u128> {
+            let mut amount = 0u128;
+            for transfer in response.alkanes.0.iter().filter(|transfer| &transfer.id == token) {
+                amount = overflow_error(amount.checked_add(transfer.value))?;
+            }
+            Ok(amount)
+        };
+        let (amount_a, amount_b) = (withdrawn(token_a)?, withdrawn(token_b)?);
+        if amount_a == 0 && amount_b == 0 {
+            return Err(anyhow!("Oyl pool returned nothing for {} LP tokens", lp_amount));
+        }
+        Ok((amount_a, amount_b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_factory_resolution() {
+        assert_eq!(AmmBackend::default().factory_id(), OYL_FACTORY_PROXY);
+        assert_eq!(AmmBackend::from_args(0, 0, 0).unwrap(), AmmBackend::default());
+
+        let fork = AmmBackend::from_args(0, 4, 70_000).unwrap();
+        assert_eq!(fork.factory_id(), AlkaneId { block: 4, tx: 70_000 });
+        assert!(fork.validate().is_ok());
+
+        assert!(AmmBackend::from_args(1, 0, 0).is_err());
+        assert!(AmmBackend { factory: Some((0, 5)), ..AmmBackend::default() }.validate().is_err());
+    }
+}
@@ -1,87 +1,197 @@
-//! AMM Integration Module with Real Oyl SDK
+//! AMM Integration Module
 //!
-//! This module handles the graduation of bonding curves to Oyl AMM pools.
+//! This module handles the graduation of bonding curves to AMM pools,
+//! reached through the curve's `AmmAdapter` (Oyl by default).
 //! It provides functionality to:
 //! - Verify graduation criteria are met
 //! - Create new AMM pools with initial liquidity
 //! - Transfer bonding curve reserves to AMM
 //! - Handle LP token distribution according to strategy
 
-use crate::{BaseToken, CurveParams, bonding_curve::CurveCalculator};
-use alkanes_runtime::storage::StoragePointer;
+use crate::{BaseToken, CurveParams, CurveType, bonding_curve::CurveCalculator, fixed_point};
+use crate::amm_adapter::AmmAdapter;
+use crate::bonding_curve::GraduationState;
+use crate::fees::{FeeEngine, FeeRecipient};
+use crate::graduation::{EmergencyGraduation, GraduationMetrics, PriceContinuity, Tokenomics};
+use crate::journal::{Journal, JournaledPointer};
+use alkanes_support::cellpack::Cellpack;
 use alkanes_support::context::Context;
 use alkanes_support::response::CallResponse;
 use alkanes_support::id::AlkaneId;
-use alkanes_support::parcel::AlkaneTransfer;
+use alkanes_support::parcel::{AlkaneTransfer, AlkaneTransferParcel};
+use alkanes_support::utils::overflow_error;
 use anyhow::{anyhow, Result};
 use metashrew_support::index_pointer::KeyValuePointer;
+use std::cell::Cell;
 use std::sync::Arc;
 
-// Oyl Factory contract address from official deployment (mainnet)
-// Source: https://docs.oyl.io/developer/deployment-ids
-const OYL_FACTORY_PROXY: AlkaneId = AlkaneId { block: 4u128, tx: 65522u128 }; // Factory Proxy (mainnet)
-
-// Oyl Factory opcodes
-const FACTORY_CREATE_POOL: u128 = 1;
-const FACTORY_GET_POOL: u128 = 2;
-const FACTORY_ADD_LIQUIDITY: u128 = 3;
-
-// Oyl Pool opcodes
-const POOL_MINT_LP: u128 = 10;
-const POOL_BURN_LP: u128 = 11;
-const POOL_GET_RESERVES: u128 = 12;
+/// LP tokens a pool locks on its first mint, as Oyl and Uniswap V2 do
+pub const MINIMUM_LIQUIDITY: u128 = 1_000;
 
 /// LP token distribution strategies
+///
+/// Only `BurnAll` is implemented. The others would pay LP to holders, the
+/// creator or a DAO, which the curve cannot address yet, so launches and
+/// graduations refuse them rather than lock those shares silently.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LPDistributionStrategy {
-    BurnAll = 0,           // 100% burned for permanent liquidity
-    CommunityRewards = 1,  // 80% burned, 20% to top holders
-    CreatorAllocation = 2, // 90% burned, 10% to creator
-    DAOGovernance = 3,     // 80% burned, 20% to DAO
+    BurnAll = 0,           // 100% locked for permanent liquidity
+    CommunityRewards = 1,  // 80% locked, 20% to top holders
+    CreatorAllocation = 2, // 90% locked, 10% to creator
+    DAOGovernance = 3,     // 80% locked, 20% to DAO
+}
+
+impl LPDistributionStrategy {
+    pub fn from_u128(value: u128) -> Result<Self> {
+        match value {
+            0 => Ok(LPDistributionStrategy::BurnAll),
+            1 => Ok(LPDistributionStrategy::CommunityRewards),
+            2 => Ok(LPDistributionStrategy::CreatorAllocation),
+            3 => Ok(LPDistributionStrategy::DAOGovernance),
+            _ => Err(anyhow!("Invalid LP distribution strategy (0-3)")),
+        }
+    }
+
+    /// Fail unless graduation can carry the strategy out
+    pub fn ensure_supported(self) -> Result<()> {
+        match self {
+            LPDistributionStrategy::BurnAll => Ok(()),
+            _ => Err(anyhow!("LP distribution strategy {:?} is not supported yet", self)),
+        }
+    }
+}
+
+/// Calls into other alkanes: the curve contract makes runtime extcalls,
+/// tests substitute mock contracts
+pub trait AlkaneCaller {
+    /// Call `cellpack.target`, sending it `outgoing`
+    fn call_alkane(&self, cellpack: &Cellpack, outgoing: &AlkaneTransferParcel) -> Result<CallResponse>;
+}
+
+/// `AlkaneCaller` that notes whether any call it forwarded went through
+/// with alkanes attached
+///
+/// Once one has, the curve's tokens and base sit with the callee and only
+/// reverting the whole call brings them back.
+struct TransferTracker<'a> {
+    caller: &'a dyn AlkaneCaller,
+    sent: Cell<bool>,
+}
+
+impl AlkaneCaller for TransferTracker<'_> {
+    fn call_alkane(&self, cellpack: &Cellpack, outgoing: &AlkaneTransferParcel) -> Result<CallResponse> {
+        let response = self.caller.call_alkane(cellpack, outgoing)?;
+        if !outgoing.0.is_empty() {
+            self.sent.set(true);
+        }
+        Ok(response)
+    }
 }
 
 /// AMM integration handler
 pub struct AMMIntegration;
 
 impl AMMIntegration {
-    /// Attempt to graduate the bonding curve to an AMM pool
+    /// Queue graduation and attempt it in a nested transaction
+    ///
+    /// The curve sits in `GraduationPending` while the attempt runs. A
+    /// failure before any liquidity left the curve discards the attempt's
+    /// writes, moves the curve to `Failed` with the reason recorded and
+    /// reopens trading; it is returned as the inner error, which callers may
+    /// drop. A failure after the pool took the liquidity is the outer error:
+    /// the transfers can only be undone by reverting the whole call, so
+    /// callers must propagate it.
+    pub fn attempt_graduation(
+        caller: &dyn AlkaneCaller,
+        context: &Context,
+        metrics: &GraduationMetrics,
+    ) -> Result<Result<CallResponse>> {
+        CurveCalculator::set_graduation_state(GraduationState::GraduationPending);
+
+        let tracker = TransferTracker { caller, sent: Cell::new(false) };
+        match Journal::transact(|| Self::graduate_to_amm(&tracker, context, metrics)) {
+            Ok(response) => {
+                CurveCalculator::set_graduation_failure(None);
+                Ok(Ok(response))
+            },
+            Err(error) if tracker.sent.get() => {
+                Err(anyhow!("Graduation failed after the pool received the curve's liquidity: {}", error))
+            },
+            Err(error) => {
+                CurveCalculator::set_graduation_state(GraduationState::Failed);
+                CurveCalculator::set_graduation_failure(Some(&error.to_string()));
+                Ok(Err(error))
+            },
+        }
+    }
+
+    /// Attempt to graduate the bonding curve to its AMM backend's pool
     pub fn graduate_to_amm(
+        caller: &dyn AlkaneCaller,
         context: &Context,
-        token_supply: u128,
+        metrics: &GraduationMetrics,
     ) -> Result<CallResponse> {
-        println!("Attempting graduation to AMM pool");
-        
-        // Check if already graduated
-        if CurveCalculator::is_graduated() {
-            return Err(anyhow!("Bonding curve has already graduated"));
-        }
+        let backend = CurveCalculator::get_curve_params()?.amm_backend;
+        Self::graduate_with(backend.adapter(caller).as_ref(), context, metrics)
+    }
 
+    /// Attempt to graduate the bonding curve to a pool created by `amm`
+    ///
+    /// Graduates when the criteria are met, or when the curve has become
+    /// eligible for emergency graduation.
+    pub fn graduate_with(
+        amm: &dyn AmmAdapter,
+        context: &Context,
+        metrics: &GraduationMetrics,
+    ) -> Result<CallResponse> {
         // Get curve parameters and reserves
         let params = CurveCalculator::get_curve_params()?;
+        let token_supply = metrics.supply;
         let base_reserves = CurveCalculator::get_base_reserves();
 
-        // Verify graduation criteria
-        if !CurveCalculator::check_graduation_criteria(token_supply, base_reserves, &params) {
-            return Err(anyhow!("Graduation criteria not met"));
-        }
+        Self::ensure_graduation_due(metrics, &params)?;
+
+        // Refuse before touching the pool if the LP cannot be distributed
+        let lp_strategy = Self::get_lp_distribution_strategy()?;
+        lp_strategy.ensure_supported()?;
 
         // Calculate AMM pool ratios
-        let (token_liquidity, base_liquidity) = Self::calculate_pool_ratios(
+        let (token_liquidity, base_liquidity, excess_base) = Self::calculate_pool_ratios(
             token_supply,
             base_reserves,
             &params,
         )?;
 
+        // Base the LP reserve cannot pair with at the curve price goes to the treasury
+        if excess_base > 0 {
+            FeeEngine::credit(FeeRecipient::Protocol, excess_base)?;
+        }
+
+        // Mint the pool's tokens from its allocation; the unminted rest is burned
+        CurveCalculator::mint_pool_tokens(token_supply, token_liquidity, &params)?;
+
         // Create AMM pool with atomic operation
-        let pool_address = Self::create_oyl_pool_atomic(
+        let (pool_address, lp_tokens) = Self::create_pool_atomic(
+            amm,
             context,
             &params.base_token,
             token_liquidity,
             base_liquidity,
         )?;
 
-        // Mark as graduated
+        // Confirm the pool really opened at the curve price
+        Self::verify_opening_price(
+            amm,
+            &pool_address,
+            (&context.myself, &params.base_token.alkane_id()),
+            Self::curve_price(token_supply, &params)?,
+            &params.price_continuity,
+        )?;
+
+        // Mark as graduated; every base token the curve held is now in the
+        // pool or owed to the treasury
         CurveCalculator::set_graduated();
+        CurveCalculator::set_base_reserves(0);
 
         // Store pool information
         Self::set_amm_pool_address(pool_address.clone());
@@ -89,333 +199,222 @@ impl AMMIntegration {
         Self::set_graduation_block(0); // TODO: Get from context when available
         Self::set_graduation_timestamp(0); // TODO: Get from context when available
 
-        // Get LP distribution strategy from storage
-        let lp_strategy = Self::get_lp_distribution_strategy();
-        
-        // Distribute LP tokens according to strategy
-        Self::distribute_lp_tokens(
-            context,
-            &pool_address,
-            token_liquidity,
-            base_liquidity,
-            lp_strategy,
-        )?;
+        // Distribute the LP tokens the pool minted according to strategy
+        Self::distribute_lp_tokens(lp_tokens, lp_strategy)?;
 
         // Update factory registry if this is a factory-deployed token
         if let Ok(factory_id) = Self::get_factory_id() {
             Self::notify_factory_of_graduation(&factory_id, &context.myself, &pool_address)?;
         }
 
-        // Return success response with pool address
-        let mut response = CallResponse::forward(&context.incoming_alkanes);
-        let pool_id_bytes = ((pool_address.block as u128) << 64 | pool_address.tx as u128)
-            .to_le_bytes()
-            .to_vec();
-        response.data = pool_id_bytes;
-
-        println!("Successfully graduated to AMM pool: {:?}", pool_address);
+        // Return the pool address and the LP tokens locked in the curve;
+        // callers forward their own incoming alkanes
+        let mut response = CallResponse::default();
+        let pool_id = (pool_address.block as u128) << 64 | pool_address.tx as u128;
+        response.data = [pool_id, lp_tokens].iter().flat_map(|field| field.to_le_bytes()).collect();
         Ok(response)
     }
 
-    /// Calculate optimal token and base liquidity for AMM pool
-    fn calculate_pool_ratios(
-        token_supply: u128,
-        base_reserves: u128,
-        params: &CurveParams,
-    ) -> Result<(u128, u128)> {
-        // Use all base reserves for liquidity
-        let base_liquidity = base_reserves;
-        
-        // Calculate corresponding token amount at current price
-        // This ensures proper price continuity from bonding curve to AMM
-        let current_price = CurveCalculator::price_at_supply(token_supply, params)
-            .unwrap_or(params.base_price);
-        
-        // Calculate token liquidity to match the base liquidity at current price
-        // token_liquidity = base_liquidity * (10^9) / current_price
-        // (adjusting for decimals - assuming 9 decimals for tokens)
-        let token_liquidity = base_liquidity
-            .saturating_mul(1_000_000_000)
-            .saturating_div(current_price);
-        
-        // Ensure we don't exceed available supply
-        let max_token_liquidity = token_supply.saturating_mul(50).saturating_div(100); // Max 50% of supply
-        let final_token_liquidity = std::cmp::min(token_liquidity, max_token_liquidity);
-        
-        println!("Calculated pool ratios - Tokens: {}, Base: {}", final_token_liquidity, base_liquidity);
-        Ok((final_token_liquidity, base_liquidity))
-    }
-
-    /// Create a new Oyl AMM pool with atomic operation (all-or-nothing)
-    fn create_oyl_pool_atomic(
-        context: &Context,
-        base_token: &BaseToken,
-        token_liquidity: u128,
-        base_liquidity: u128,
-    ) -> Result<AlkaneId> {
-        // Get the appropriate factory address based on base token
-        let factory_address = match base_token {
-            BaseToken::BUSD => OYL_FACTORY_PROXY,
-            BaseToken::FrBtc => OYL_FACTORY_PROXY,
-        };
-
-        // Step 1: Create pool via Oyl Factory
-        let pool_address = Self::call_oyl_factory_create_pool(
-            factory_address,
-            context.myself.clone(),
-            base_token.alkane_id(),
-        )?;
-
-        // Step 2: Verify pool was created successfully
-        if !Self::verify_pool_exists(&pool_address) {
-            return Err(anyhow!("Pool creation failed - pool does not exist"));
+    /// Fail unless the curve may graduate now: not graduated yet, and
+    /// meeting its criteria or eligible for emergency graduation
+    pub fn ensure_graduation_due(metrics: &GraduationMetrics, params: &CurveParams) -> Result<()> {
+        if CurveCalculator::is_graduated() {
+            return Err(anyhow!("Bonding curve has already graduated"));
         }
 
-        // Step 3: Transfer tokens to the pool
-        let token_transfer = AlkaneTransfer {
-            id: context.myself.clone(),
-            value: token_liquidity,
-        };
-        
-        let base_transfer = AlkaneTransfer {
-            id: base_token.alkane_id(),
-            value: base_liquidity,
-        };
-
-        // Build transfer list
-        let mut transfers = vec![token_transfer];
-        transfers.push(base_transfer);
-
-        // Step 4: Add liquidity to the pool atomically
-        Self::add_initial_liquidity_to_pool(
-            &pool_address,
-            transfers,
-            token_liquidity,
-            base_liquidity,
-        )?;
-
-        println!("Successfully created Oyl pool: {:?}", pool_address);
-        Ok(pool_address)
-    }
-
-    /// Call Oyl Factory to create a new pool
-    fn call_oyl_factory_create_pool(
-        factory_address: AlkaneId,
-        token_a: AlkaneId,
-        token_b: AlkaneId,
-    ) -> Result<AlkaneId> {
-        // Build the message to create pool
-        let create_pool_message = serde_json::json!({
-            "opcode": FACTORY_CREATE_POOL,
-            "token_a": {
-                "block": token_a.block,
-                "tx": token_a.tx,
-            },
-            "token_b": {
-                "block": token_b.block,
-                "tx": token_b.tx,
-            },
-            "fee_tier": 30, // 0.3% fee tier
-        });
+        // Verify graduation criteria, falling back to the emergency window
+        let base_reserves = CurveCalculator::get_base_reserves();
+        if !CurveCalculator::check_graduation_criteria(metrics, params)
+            && !params.emergency_graduation.is_eligible(metrics.blocks_since_launch, metrics.supply, base_reserves)
+        {
+            return Err(anyhow!("Graduation criteria not met"));
+        }
 
-        // TODO: Implement real cross-contract call when API is available
-        // For now, simulate pool creation
-        println!("Simulating Oyl factory call to create pool");
-        
-        // Generate deterministic pool address
-        let pool_address = Self::generate_pool_address(&factory_address, &token_a, &token_b);
-        
-        println!("Created Oyl pool at {:?}", pool_address);
-        Ok(pool_address)
+        Ok(())
     }
 
-    /// Verify that pool was created successfully
-    fn verify_pool_exists(pool_address: &AlkaneId) -> bool {
-        // TODO: Query the pool contract to verify it exists
-        // For now, assume pool exists if address is valid
-        pool_address.block > 0 && pool_address.tx > 0
+    /// Final marginal price of the curve as a `(base, tokens)` ratio
+    ///
+    /// Constant-product curves price as their virtual pool, whose ratio is
+    /// kept exact; other shapes use their Q64.64 spot price, so the fraction
+    /// of a base unit a whole-unit price drops still seeds the pool.
+    fn curve_price(token_supply: u128, params: &CurveParams) -> Result<(u128, u128)> {
+        if params.curve_type == CurveType::ConstantProduct {
+            let token_reserve = params.virtual_token_reserve.saturating_sub(token_supply);
+            let virtual_base = fixed_point::mul_div(
+                params.virtual_base_reserve,
+                params.virtual_token_reserve,
+                token_reserve,
+            )?;
+            return Ok((virtual_base, token_reserve));
+        }
+
+        // Prices of 2^64 base or more shed low bits until they fit in u128
+        let price = CurveCalculator::price_at_supply_q64(token_supply, params);
+        let shift = price.bits().saturating_sub(128);
+        let numerator = overflow_error(price.shr(shift).to_u128())?;
+        Ok((numerator, fixed_point::ONE >> shift))
     }
 
-    /// Add initial liquidity to the newly created pool
-    fn add_initial_liquidity_to_pool(
-        pool_address: &AlkaneId,
-        transfers: Vec<AlkaneTransfer>,
-        token_amount: u128,
-        base_amount: u128,
-    ) -> Result<u128> {
-        // Build add liquidity message
-        let add_liquidity_message = serde_json::json!({
-            "opcode": FACTORY_ADD_LIQUIDITY,
-            "pool": {
-                "block": pool_address.block,
-                "tx": pool_address.tx,
-            },
-            "token_amount": token_amount,
-            "base_amount": base_amount,
-            "min_lp_out": 0, // Accept any amount of LP tokens for initial liquidity
-        });
+    /// Token and base amounts that open the pool at the curve's final price,
+    /// and the base left over
+    ///
+    /// The base reserves go into the pool, paired with the token amount
+    /// nearest the curve price. When that is more than the LP reserve, the
+    /// pool takes the whole reserve and only the base it pairs with at the
+    /// curve price; the rest is returned as excess. Aborts if the pair is
+    /// not within the launch's price tolerance.
+    fn calculate_pool_ratios(
+        token_supply: u128,
+        base_reserves: u128,
+        params: &CurveParams,
+    ) -> Result<(u128, u128, u128)> {
+        let (price, per_tokens) = Self::curve_price(token_supply, params)?;
+
+        // token_liquidity = base_reserves / price, rounded to nearest
+        let (quotient, remainder) = fixed_point::U256::mul_u128(base_reserves, per_tokens)
+            .div_rem_u128(price)
+            .ok_or_else(|| anyhow!("Curve has no price to open the pool at"))?;
+        let mut token_liquidity = quotient
+            .to_u128()
+            .ok_or_else(|| anyhow!("Pool token amount overflows"))?;
+        if remainder >= price - remainder {
+            token_liquidity = overflow_error(token_liquidity.checked_add(1))?;
+        }
 
-        // Calculate expected LP tokens (using constant product formula)
-        // LP = sqrt(token_amount * base_amount)
-        let lp_tokens = Self::calculate_lp_tokens(token_amount, base_amount);
+        // Past the reserve, the base side shrinks to what the reserve pairs
+        // with at the curve price (rounded down, so it never exceeds reserves)
+        let mut base_liquidity = base_reserves;
+        let allocation = Tokenomics::new(params, token_supply).pool_allocation();
+        if token_liquidity > allocation {
+            token_liquidity = allocation;
+            base_liquidity = fixed_point::mul_div(allocation, price, per_tokens)?.min(base_reserves);
+        }
 
-        println!("Added liquidity to pool. LP tokens minted: {}", lp_tokens);
-        Ok(lp_tokens)
-    }
+        if !params.price_continuity.allows(token_liquidity, base_liquidity, (price, per_tokens)) {
+            return Err(anyhow!(
+                "Pool of {} tokens and {} base cannot open within {} bps of the curve price",
+                token_liquidity,
+                base_liquidity,
+                params.price_continuity.tolerance_bps
+            ));
+        }
 
-    /// Calculate LP tokens using constant product formula
-    fn calculate_lp_tokens(token_amount: u128, base_amount: u128) -> u128 {
-        // Simplified calculation - in production would use proper sqrt
-        // LP = sqrt(token_amount * base_amount)
-        let product = (token_amount as u64).saturating_mul(base_amount as u64);
-        (product as f64).sqrt() as u128
+        Ok((token_liquidity, base_liquidity, base_reserves - base_liquidity))
     }
 
-    /// Distribute LP tokens according to the chosen strategy
-    fn distribute_lp_tokens(
+    /// Create a new AMM pool with atomic operation (all-or-nothing)
+    ///
+    /// The pool is created and seeded in one call; if it fails the runtime
+    /// reverts the transfers along with the call. Returns the pool id and
+    /// the LP tokens it minted to the curve.
+    fn create_pool_atomic(
+        amm: &dyn AmmAdapter,
         context: &Context,
-        pool_address: &AlkaneId,
+        base_token: &BaseToken,
         token_liquidity: u128,
         base_liquidity: u128,
-        strategy: LPDistributionStrategy,
-    ) -> Result<()> {
-        let total_lp_tokens = Self::calculate_lp_tokens(token_liquidity, base_liquidity);
-        
-        match strategy {
-            LPDistributionStrategy::BurnAll => {
-                // Burn 100% of LP tokens for permanent liquidity
-                Self::burn_lp_tokens(pool_address, total_lp_tokens)?;
-                println!("Burned all {} LP tokens for permanent liquidity", total_lp_tokens);
-            },
-            
-            LPDistributionStrategy::CommunityRewards => {
-                // Burn 80%, distribute 20% to top holders
-                let burn_amount = total_lp_tokens.saturating_mul(80).saturating_div(100);
-                let distribute_amount = total_lp_tokens.saturating_sub(burn_amount);
-                
-                Self::burn_lp_tokens(pool_address, burn_amount)?;
-                Self::distribute_to_top_holders(context, pool_address, distribute_amount)?;
-                
-                println!("Burned {} LP tokens, distributed {} to community", burn_amount, distribute_amount);
-            },
-            
-            LPDistributionStrategy::CreatorAllocation => {
-                // Burn 90%, give 10% to creator
-                let burn_amount = total_lp_tokens.saturating_mul(90).saturating_div(100);
-                let creator_amount = total_lp_tokens.saturating_sub(burn_amount);
-                
-                Self::burn_lp_tokens(pool_address, burn_amount)?;
-                Self::transfer_lp_to_creator(context, pool_address, creator_amount)?;
-                
-                println!("Burned {} LP tokens, allocated {} to creator", burn_amount, creator_amount);
-            },
-            
-            LPDistributionStrategy::DAOGovernance => {
-                // Burn 80%, send 20% to DAO
-                let burn_amount = total_lp_tokens.saturating_mul(80).saturating_div(100);
-                let dao_amount = total_lp_tokens.saturating_sub(burn_amount);
-                
-                Self::burn_lp_tokens(pool_address, burn_amount)?;
-                Self::transfer_lp_to_dao(pool_address, dao_amount)?;
-                
-                println!("Burned {} LP tokens, sent {} to DAO", burn_amount, dao_amount);
-            },
-        }
-        
-        Ok(())
-    }
+    ) -> Result<(AlkaneId, u128)> {
+        // A seed too small to mint past the locked minimum would be rejected
+        let expected_lp = Self::calculate_lp_tokens(token_liquidity, base_liquidity)?;
+
+        // Create the pool, sending the initial liquidity
+        let (pool_address, lp_tokens) = amm.create_pool(
+            AlkaneTransfer { id: context.myself.clone(), value: token_liquidity },
+            AlkaneTransfer { id: base_token.alkane_id(), value: base_liquidity },
+        )?;
 
-    /// Burn LP tokens for permanent liquidity
-    fn burn_lp_tokens(pool_address: &AlkaneId, amount: u128) -> Result<()> {
-        // In production, this would call the pool's burn function
-        // to permanently lock the liquidity
-        println!("Burning {} LP tokens from pool {:?}", amount, pool_address);
-        Ok(())
+        // Anything beyond a unit of rounding means the pool priced the seed
+        // differently than the curve expects
+        if lp_tokens.abs_diff(expected_lp) > 1 {
+            return Err(anyhow!("Pool minted {} LP tokens, expected {}", lp_tokens, expected_lp));
+        }
+        Ok((pool_address, lp_tokens))
     }
 
-    /// Distribute LP tokens to top token holders
-    fn distribute_to_top_holders(
-        context: &Context,
+    /// Read the pool's `(token, base)` reserves and check its price against
+    /// the curve's
+    fn verify_opening_price(
+        amm: &dyn AmmAdapter,
         pool_address: &AlkaneId,
-        amount: u128,
+        (token, base): (&AlkaneId, &AlkaneId),
+        curve_price: (u128, u128),
+        continuity: &PriceContinuity,
     ) -> Result<()> {
-        // Get top 10 holders from storage
-        let top_holders = Self::get_top_holders(10)?;
-        
-        if top_holders.is_empty() {
-            // If no holders, burn the tokens instead
-            return Self::burn_lp_tokens(pool_address, amount);
+        let (token_reserve, base_reserve) = amm.get_reserves(pool_address, token, base)?;
+
+        if !continuity.allows(token_reserve, base_reserve, curve_price) {
+            return Err(anyhow!(
+                "Pool opened {} from the curve price, beyond the {} bps tolerance",
+                PriceContinuity::deviation_bps(token_reserve, base_reserve, curve_price)
+                    .map_or_else(|| "unboundedly far".to_string(), |bps| format!("{} bps", bps)),
+                continuity.tolerance_bps
+            ));
         }
-        
-        // Distribute proportionally based on holdings
-        let amount_per_holder = amount.saturating_div(top_holders.len() as u128);
-        
-        for holder in top_holders {
-            // Transfer LP tokens to holder
-            println!("Distributing {} LP tokens to holder {:?}", amount_per_holder, holder);
-        }
-        
         Ok(())
     }
 
-    /// Transfer LP tokens to token creator
-    fn transfer_lp_to_creator(
-        context: &Context,
-        pool_address: &AlkaneId,
-        amount: u128,
-    ) -> Result<()> {
-        let creator = Self::get_token_creator()?;
-        println!("Transferring {} LP tokens to creator {:?}", amount, creator);
-        Ok(())
+    /// LP tokens a new pool mints for its first deposit
+    ///
+    /// `floor(sqrt(token_amount * base_amount)) - MINIMUM_LIQUIDITY`, taken
+    /// exactly over the 256-bit product. Fails if nothing is left after the
+    /// locked minimum, as the pool itself would.
+    fn calculate_lp_tokens(token_amount: u128, base_amount: u128) -> Result<u128> {
+        let liquidity = fixed_point::sqrt_mul(token_amount, base_amount);
+        match liquidity.checked_sub(MINIMUM_LIQUIDITY) {
+            Some(lp_tokens) if lp_tokens > 0 => Ok(lp_tokens),
+            _ => Err(anyhow!("Insufficient liquidity minted: {} LP against a {} minimum", liquidity, MINIMUM_LIQUIDITY)),
+        }
     }
 
-    /// Transfer LP tokens to DAO contract
-    fn transfer_lp_to_dao(pool_address: &AlkaneId, amount: u128) -> Result<()> {
-        let dao_address = Self::get_dao_address()?;
-        println!("Transferring {} LP tokens to DAO {:?}", amount, dao_address);
-        Ok(())
+    /// Distribute LP tokens according to the chosen strategy
+    ///
+    /// Only `BurnAll` is carried out: every LP token is locked.
+    fn distribute_lp_tokens(total_lp_tokens: u128, strategy: LPDistributionStrategy) -> Result<()> {
+        strategy.ensure_supported()?;
+
+        // Lock 100% of LP tokens for permanent liquidity
+        Self::lock_lp_tokens(total_lp_tokens)
     }
 
-    /// Generate a deterministic pool address based on factory and tokens
-    fn generate_pool_address(
-        factory: &AlkaneId,
-        token_a: &AlkaneId,
-        token_b: &AlkaneId,
-    ) -> AlkaneId {
-        // Create a deterministic address based on factory and token addresses
-        let factory_hash = (factory.block as u128) << 64 | (factory.tx as u128);
-        let token_a_hash = (token_a.block as u128) << 64 | (token_a.tx as u128);
-        let token_b_hash = (token_b.block as u128) << 64 | (token_b.tx as u128);
-        
-        let combined = factory_hash
-            .wrapping_add(token_a_hash)
-            .wrapping_add(token_b_hash);
-        
-        // Create pool address
-        AlkaneId {
-            block: ((combined >> 64) % 1000000) as u128,
-            tx: (combined % 1000000) as u128,
-        }
+    /// Lock LP tokens for permanent liquidity
+    ///
+    /// The curve keeps the LP the pool minted to it and has no call that
+    /// spends it, so the liquidity can never be withdrawn. Handing the LP
+    /// back to the pool would redeem it instead, pulling the liquidity out.
+    fn lock_lp_tokens(amount: u128) -> Result<()> {
+        let mut pointer = Self::locked_lp_pointer();
+        let locked = overflow_error(pointer.get_value::<u128>().checked_add(amount))?;
+        pointer.set_value::<u128>(locked);
+        Ok(())
     }
 
     /// Notify factory of graduation
     fn notify_factory_of_graduation(
-        factory_id: &AlkaneId,
-        token_id: &AlkaneId,
-        pool_address: &AlkaneId,
+        _factory_id: &AlkaneId,
+        _token_id: &AlkaneId,
+        _pool_address: &AlkaneId,
     ) -> Result<()> {
         // In production, this would call the factory to update registry
-        println!("Notifying factory {:?} of graduation for token {:?}", factory_id, token_id);
         Ok(())
     }
 
     // Storage accessors
+    fn locked_lp_pointer() -> JournaledPointer {
+        JournaledPointer::from_keyword("/amm/lp_locked")
+    }
+
+    /// LP tokens the curve locked at graduation
+    pub fn get_locked_lp_tokens() -> u128 {
+        Self::locked_lp_pointer().get_value::<u128>()
+    }
+
     pub fn get_amm_pool_address() -> Option<AlkaneId> {
-        let pointer = StoragePointer::from_keyword("/amm/pool_address");
+        let pointer = JournaledPointer::from_keyword("/amm/pool_address");
         let data = pointer.get();
-        if data.len() >= 16 {
-            let block = u128::from_le_bytes(data[0..8].try_into().ok()?);
-            let tx = u128::from_le_bytes(data[8..16].try_into().ok()?);
+        if data.len() >= 32 {
+            let block = u128::from_le_bytes(data[0..16].try_into().ok()?);
+            let tx = u128::from_le_bytes(data[16..32].try_into().ok()?);
             Some(AlkaneId { block, tx })
         } else {
             None
@@ -423,7 +422,7 @@ impl AMMIntegration {
     }
 
     fn set_amm_pool_address(pool: AlkaneId) {
-        let mut pointer = StoragePointer::from_keyword("/amm/pool_address");
+        let mut pointer = JournaledPointer::from_keyword("/amm/pool_address");
         let mut data = Vec::new();
         data.extend_from_slice(&pool.block.to_le_bytes());
         data.extend_from_slice(&pool.tx.to_le_bytes());
@@ -431,106 +430,212 @@ impl AMMIntegration {
     }
 
     fn set_graduation_block(block: u64) {
-        let mut pointer = StoragePointer::from_keyword("/amm/graduation_block");
+        let mut pointer = JournaledPointer::from_keyword("/amm/graduation_block");
         pointer.set_value(block);
     }
 
     fn set_graduation_timestamp(timestamp: u64) {
-        let mut pointer = StoragePointer::from_keyword("/amm/graduation_timestamp");
+        let mut pointer = JournaledPointer::from_keyword("/amm/graduation_timestamp");
         pointer.set_value(timestamp);
     }
 
-    fn get_lp_distribution_strategy() -> LPDistributionStrategy {
-        let pointer = StoragePointer::from_keyword("/amm/lp_strategy");
-        let strategy = pointer.get_value::<u8>();
-        match strategy {
-            1 => LPDistributionStrategy::CommunityRewards,
-            2 => LPDistributionStrategy::CreatorAllocation,
-            3 => LPDistributionStrategy::DAOGovernance,
-            _ => LPDistributionStrategy::BurnAll,
-        }
+    fn get_lp_distribution_strategy() -> Result<LPDistributionStrategy> {
+        let pointer = JournaledPointer::from_keyword("/amm/lp_strategy");
+        LPDistributionStrategy::from_u128(pointer.get_value::<u8>().into())
     }
 
-    fn get_factory_id() -> Result<AlkaneId> {
-        let pointer = StoragePointer::from_keyword("/factory/id");
-        let data = pointer.get();
-        if data.len() >= 16 {
-            let block = u128::from_le_bytes(data[0..8].try_into().map_err(|_| anyhow!("Invalid block"))?);
-            let tx = u128::from_le_bytes(data[8..16].try_into().map_err(|_| anyhow!("Invalid tx"))?);
-            Ok(AlkaneId { block, tx })
-        } else {
-            Err(anyhow!("Factory ID not found"))
-        }
-    }
-
-    fn get_top_holders(count: usize) -> Result<Vec<AlkaneId>> {
-        // In production, this would query balance storage
-        // and return top holders by balance
-        Ok(Vec::new()) // Placeholder
+    /// Store the strategy graduation distributes LP tokens by
+    pub fn set_lp_distribution_strategy(strategy: LPDistributionStrategy) {
+        let mut pointer = JournaledPointer::from_keyword("/amm/lp_strategy");
+        pointer.set_value(strategy as u8);
     }
 
-    fn get_token_creator() -> Result<AlkaneId> {
-        let pointer = StoragePointer::from_keyword("/token/creator");
+    fn get_factory_id() -> Result<AlkaneId> {
+        let pointer = JournaledPointer::from_keyword("/factory/id");
         let data = pointer.get();
         if data.len() >= 16 {
             let block = u128::from_le_bytes(data[0..8].try_into().map_err(|_| anyhow!("Invalid block"))?);
             let tx = u128::from_le_bytes(data[8..16].try_into().map_err(|_| anyhow!("Invalid tx"))?);
             Ok(AlkaneId { block, tx })
         } else {
-            Err(anyhow!("Creator not found"))
+            Err(anyhow!("Factory ID not found"))
         }
     }
 
-    fn get_dao_address() -> Result<AlkaneId> {
-        // DAO contract address would be configured
-        Ok(AlkaneId { block: 100u128, tx: 1u128 }) // Placeholder
+    /// Whether a curve launched at `launch_block` may be graduated at
+    /// `current_block` under its `emergency` window, regardless of criteria
+    ///
+    /// Callers pass the curve's own window, loaded with its parameters, so
+    /// a curve whose parameters cannot be read never falls back to defaults.
+    pub fn check_emergency_graduation(
+        emergency: &EmergencyGraduation,
+        current_block: u64,
+        launch_block: u64,
+        token_supply: u128,
+        base_reserves: u128,
+    ) -> bool {
+        emergency.is_eligible(
+            current_block.saturating_sub(launch_block),
+            token_supply,
+            base_reserves,
+        )
     }
 
-    /// Check if sufficient liquidity exists for graduation
+    /// Check if sufficient liquidity exists for graduation: the reserves
+    /// seed a pool at the curve price that mints LP past the locked minimum
     pub fn check_liquidity_sufficiency(
         token_supply: u128,
         base_reserves: u128,
         params: &CurveParams,
     ) -> bool {
-        match Self::calculate_pool_ratios(token_supply, base_reserves, params) {
-            Ok((token_needed, base_needed)) => {
-                token_needed <= token_supply && base_needed <= base_reserves
-            },
-            Err(_) => false,
-        }
+        Self::calculate_pool_ratios(token_supply, base_reserves, params)
+            .and_then(|(token_liquidity, base_liquidity, _)| Self::calculate_lp_tokens(token_liquidity, base_liquidity))
+            .is_ok()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::constants::{DEFAULT_BASE_PRICE, DEFAULT_GROWTH_RATE};
+    use crate::fixed_point::BPS;
+
+    /// The shared linear curve, priced from 100 to 200 base per token
+    fn linear_params() -> CurveParams {
+        CurveParams { base_price: 100, max_price: 200, ..crate::tests::linear_params() }
+    }
 
     #[test]
     fn test_pool_ratio_calculation() {
-        let params = CurveParams::default();
-        let token_supply = 1_000_000_000; // 1B tokens
-        let base_reserves = 10_000_000_000; // 10B base tokens
+        let params = linear_params();
+
+        // Half sold, the marginal price is 150 base per token
+        assert_eq!(
+            AMMIntegration::calculate_pool_ratios(500_000, 1_500_000, &params).unwrap(),
+            (10_000, 1_500_000, 0)
+        );
+
+        // The token side rounds to the nearest amount
+        assert_eq!(
+            AMMIntegration::calculate_pool_ratios(500_000, 1_500_075, &params).unwrap(),
+            (10_001, 1_500_075, 0)
+        );
+    }
+
+    #[test]
+    fn test_pool_ratio_outside_tolerance_aborts() {
+        let params = linear_params();
+
+        // 225 base buys 1.5 tokens: 2 tokens would open the pool 25% low
+        assert!(AMMIntegration::calculate_pool_ratios(500_000, 225, &params).is_err());
+        assert_eq!(AMMIntegration::calculate_pool_ratios(500_000, 150, &params).unwrap(), (1, 150, 0));
+
+        // A loose enough tolerance accepts it
+        let loose = CurveParams { price_continuity: PriceContinuity { tolerance_bps: 2_500 }, ..linear_params() };
+        assert_eq!(AMMIntegration::calculate_pool_ratios(500_000, 225, &loose).unwrap(), (2, 225, 0));
+    }
+
+    #[test]
+    fn test_pool_ratio_caps_base_at_the_reserve() {
+        // A ten-token reserve at 150 base pairs with 1,500 of 3,000 base
+        let scarce = CurveParams { lp_reserve_supply: 10, ..linear_params() };
+        assert_eq!(
+            AMMIntegration::calculate_pool_ratios(500_000, 3_000, &scarce).unwrap(),
+            (10, 1_500, 1_500)
+        );
+
+        // Without a reserve there is nothing to open the pool with
+        let unreserved = CurveParams { lp_reserve_supply: 0, ..linear_params() };
+        assert!(AMMIntegration::calculate_pool_ratios(500_000, 3_000, &unreserved).is_err());
+    }
 
-        let (token_liquidity, base_liquidity) = 
-            AMMIntegration::calculate_pool_ratios(token_supply, base_reserves, &params).unwrap();
+    #[test]
+    fn test_pool_ratio_late_in_the_default_exponential_curve() {
+        // The factory's default launch: 800M on the curve, 200M reserved
+        let params = CurveParams {
+            base_price: DEFAULT_BASE_PRICE,
+            growth_rate: DEFAULT_GROWTH_RATE,
+            max_supply: 800_000_000,
+            lp_reserve_supply: 200_000_000,
+            ..CurveParams::default()
+        };
+
+        for sold_bps in [8_000, 9_000, 10_000] {
+            let sold = params.max_supply * sold_bps / BPS;
+            let reserves = CurveCalculator::calculate_buy_price(0, sold, &params).unwrap();
+            let price = CurveCalculator::price_at_supply(sold, &params).unwrap();
+            assert!(reserves / price > params.lp_reserve_supply);
+
+            // The pool takes the whole reserve at the curve price; the rest is excess
+            let (tokens, base, excess) = AMMIntegration::calculate_pool_ratios(sold, reserves, &params).unwrap();
+            assert_eq!(tokens, params.lp_reserve_supply);
+            assert_eq!(base + excess, reserves);
+            assert!(excess > 0);
+            let price_q64 = CurveCalculator::price_at_supply_q64(sold, &params);
+            assert_eq!(fixed_point::U256::from_u128(base / tokens), price_q64.shr(64));
+        }
+    }
+
+    #[test]
+    fn test_constant_product_pool_ratio_matches_curve() {
+        let params = CurveParams {
+            max_supply: 1_000_000,
+            curve_type: CurveType::ConstantProduct,
+            virtual_base_reserve: 1_250_000_000,
+            virtual_token_reserve: 1_250_000,
+            ..CurveParams::default()
+        };
+
+        // After 250k sold the virtual pool holds 1.5625B base against 1M tokens
+        let (token_liquidity, base_liquidity) =
+            AMMIntegration::calculate_pool_ratios(250_000, 312_500_000, &params).unwrap();
+
+        assert_eq!(base_liquidity, 312_500_000);
+        assert_eq!(token_liquidity, 200_000);
+    }
+
+    #[test]
+    fn test_sub_unit_curve_price_seeds_exactly() {
+        // 1.015^50 puts the final price at 2.105 base per token, which whole
+        // units would round to 2 and open the pool 5% low
+        let params = CurveParams { base_price: 1, growth_rate: 150, max_supply: 1_000_000, ..CurveParams::default() };
+
+        let (token_liquidity, _) = AMMIntegration::calculate_pool_ratios(500_000, 210_000, &params).unwrap();
+        assert_eq!(token_liquidity, 99_751);
+    }
 
-        assert!(token_liquidity > 0);
-        assert!(base_liquidity > 0);
-        assert!(token_liquidity <= token_supply);
-        assert!(base_liquidity <= base_reserves);
+    #[test]
+    fn test_lp_tokens_are_exact() {
+        // sqrt(4M * 9M) = 6M, less the locked minimum
+        assert_eq!(AMMIntegration::calculate_lp_tokens(4_000_000, 9_000_000).unwrap(), 5_999_000);
+
+        // Amounts past u64 no longer saturate
+        let large = 1u128 << 80;
+        assert_eq!(AMMIntegration::calculate_lp_tokens(large, large).unwrap(), large - MINIMUM_LIQUIDITY);
+        assert_eq!(
+            AMMIntegration::calculate_lp_tokens(u128::MAX, u128::MAX).unwrap(),
+            u128::MAX - MINIMUM_LIQUIDITY
+        );
+
+        // Nothing left above the minimum
+        assert!(AMMIntegration::calculate_lp_tokens(1_000, 1_000).is_err());
+        assert!(AMMIntegration::calculate_lp_tokens(1_001, 1_001).is_ok());
     }
 
     #[test]
     fn test_liquidity_sufficiency() {
-        let params = CurveParams::default();
+        let params = linear_params();
         
-        // Should be insufficient with low amounts
+        // Should be insufficient with low amounts: 10 tokens and 1,000 base mint 100 LP
         assert!(!AMMIntegration::check_liquidity_sufficiency(1000, 1000, &params));
+
+        // Or when the pool cannot open at the curve price
+        assert!(!AMMIntegration::check_liquidity_sufficiency(500_000, 225, &params));
         
         // Should be sufficient with high amounts
         assert!(AMMIntegration::check_liquidity_sufficiency(
-            1_000_000_000, 
-            10_000_000_000, 
+            500_000,
+            1_500_000,
             &params
         ));
     }
@@ -541,9 +646,11 @@ mod tests {
         let launch_block = 1000;
         let token_supply = 2_000_000;
         let base_reserves = 200_000_000;
+        let emergency = EmergencyGraduation::default();
 
         // Should trigger emergency graduation after sufficient time
         assert!(AMMIntegration::check_emergency_graduation(
+            &emergency,
             current_block,
             launch_block,
             token_supply,
@@ -552,10 +659,20 @@ mod tests {
 
         // Should not trigger with recent launch
         assert!(!AMMIntegration::check_emergency_graduation(
+            &emergency,
             5000,
             launch_block,
             token_supply,
             base_reserves
         ));
+
+        // Nor when the curve's launch turned the window off
+        assert!(!AMMIntegration::check_emergency_graduation(
+            &EmergencyGraduation { delay_blocks: 0, ..emergency },
+            current_block,
+            launch_block,
+            token_supply,
+            base_reserves
+        ));
     }
 } 
\ No newline at end of file
@@ -1,24 +1,171 @@
 //! Bonding Curve Implementation
 //! 
 //! This module contains the core bonding curve logic including:
-//! - Exponential pricing algorithm
+//! - Curve pricing dispatched to the configured `BondingCurveModel`
 //! - Buy/sell mechanisms with slippage protection
 //! - Base token integration (BUSD/frBTC)
 //! - Reserve management and graduation criteria
 
-use crate::CurveParams;
-use alkanes_runtime::storage::StoragePointer;
+use crate::curve_models;
+use crate::fees::FeeEngine;
+use crate::fixed_point::{self, BPS, U256};
+use crate::graduation::{GraduationMetrics, Tokenomics};
+use crate::{CurveParams, CurveType};
+use crate::journal::JournaledPointer;
 use alkanes_support::utils::overflow_error;
 use anyhow::{anyhow, Result};
 use metashrew_support::index_pointer::KeyValuePointer;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+/// Prices saturate at this value instead of overflowing
+pub const MAX_PRICE: u128 = u128::MAX / 1000;
+
+/// Upper bound on cost evaluations when settling a tokens-for-base estimate:
+/// one for the estimate, up to 129 while galloping to bracket the answer
+/// (one per bit of its distance from the estimate, plus a final step clamped
+/// to the supply) and up to 128 while bisecting the bracket. Models with a
+/// closed-form inverse land on the answer, so a buy normally takes two
+/// probes; the bound only applies when the estimate fails.
+pub const MAX_SEARCH_PROBES: u32 = 2 * u128::BITS + 2;
+
+/// Highest sell spread a curve can launch with (50%)
+pub const MAX_SELL_SPREAD_BPS: u128 = 5_000;
+
+/// Where the spread withheld from sell payouts ends up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SpreadDestination {
+    /// Stays in `base_reserves`, backing the remaining supply
+    #[default]
+    Reserves,
+    /// Leaves the reserves and is locked in the contract for good
+    Burn,
+    /// Split between fee recipients like a trading fee
+    Fees,
+}
+
+impl SpreadDestination {
+    /// Decode the opcode representation (0 = reserves, 1 = burn, 2 = fees)
+    pub fn from_u128(value: u128) -> Result<Self> {
+        match value {
+            0 => Ok(SpreadDestination::Reserves),
+            1 => Ok(SpreadDestination::Burn),
+            2 => Ok(SpreadDestination::Fees),
+            _ => Err(anyhow!("Invalid spread destination: {}", value)),
+        }
+    }
+}
+
+/// Where the curve is in its graduation lifecycle
+///
+/// `Trading` moves to `GraduationPending` once a trade meets the graduation
+/// criteria, then to `Graduated` or, if the attempt fails, to `Failed`.
+/// `Failed` trades like `Trading` so holders are never locked in; a retry
+/// moves it back to `GraduationPending`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GraduationState {
+    /// Open for buys and sells
+    #[default]
+    Trading,
+    /// Liquidity has moved to the AMM pool
+    Graduated,
+    /// Criteria met and graduation under way
+    GraduationPending,
+    /// The last graduation attempt failed; see `graduation_failure`
+    Failed,
+}
+
+impl GraduationState {
+    /// Decode the stored byte; `Graduated` keeps the old graduated flag's value
+    pub fn from_u8(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(GraduationState::Trading),
+            1 => Ok(GraduationState::Graduated),
+            2 => Ok(GraduationState::GraduationPending),
+            3 => Ok(GraduationState::Failed),
+            _ => Err(anyhow!("Invalid graduation state: {}", value)),
+        }
+    }
+
+    pub fn as_u8(self) -> u8 {
+        match self {
+            GraduationState::Trading => 0,
+            GraduationState::Graduated => 1,
+            GraduationState::GraduationPending => 2,
+            GraduationState::Failed => 3,
+        }
+    }
+
+    /// Whether `RetryGraduation` may attempt graduation from this state
+    pub fn is_retryable(self) -> bool {
+        matches!(self, GraduationState::GraduationPending | GraduationState::Failed)
+    }
+}
+
+/// Discount on sell payouts, decaying linearly from `initial_bps` to
+/// `final_bps` over the first `decay_blocks` blocks after launch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SellSpread {
+    pub initial_bps: u128,
+    pub final_bps: u128,
+    pub decay_blocks: u64,
+    pub destination: SpreadDestination,
+}
+
+impl Default for SellSpread {
+    fn default() -> Self {
+        Self {
+            initial_bps: 100, // 1%
+            final_bps: 100,
+            decay_blocks: 0,
+            destination: SpreadDestination::Reserves,
+        }
+    }
+}
+
+impl SellSpread {
+    /// Flat spread of `bps` kept in reserves
+    pub fn flat(bps: u128) -> Self {
+        Self {
+            initial_bps: bps,
+            final_bps: bps,
+            ..Self::default()
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.initial_bps > MAX_SELL_SPREAD_BPS {
+            return Err(anyhow!("Sell spread cannot exceed {} bps", MAX_SELL_SPREAD_BPS));
+        }
+        if self.final_bps > self.initial_bps {
+            return Err(anyhow!("Sell spread can only decay over time"));
+        }
+        Ok(())
+    }
+
+    /// Spread in effect `blocks_since_launch` blocks after launch
+    pub fn bps_at(&self, blocks_since_launch: u64) -> u128 {
+        if blocks_since_launch >= self.decay_blocks {
+            return self.final_bps;
+        }
+
+        let decayed = (self.initial_bps - self.final_bps) * blocks_since_launch as u128
+            / self.decay_blocks as u128;
+        self.initial_bps - decayed
+    }
+
+    /// Spread withheld from `proceeds`, rounded against the seller
+    pub fn spread_on(&self, proceeds: u128, blocks_since_launch: u64) -> Result<u128> {
+        fixed_point::mul_div_up(proceeds, self.bps_at(blocks_since_launch), BPS)
+    }
+}
+
 /// Bonding curve state management
 pub struct CurveCalculator;
 
 impl CurveCalculator {
     /// Calculate the buy price for a given number of tokens
-    /// Uses exponential bonding curve: price = base_price * (1 + growth_rate/10000)^supply
+    /// Dispatches to the curve model selected by `params.curve_type`
     pub fn calculate_buy_price(
         current_supply: u128,
         tokens_to_buy: u128,
@@ -34,23 +181,12 @@ impl CurveCalculator {
             return Err(anyhow!("Purchase would exceed maximum supply"));
         }
 
-        // Calculate integral under exponential curve
-        // For exponential curve f(x) = base * (1 + rate)^x
-        // Integral from a to b is: base * ((1 + rate)^b - (1 + rate)^a) / ln(1 + rate)
-        // We approximate this using trapezoidal rule for precision
-        
-        let start_price = Self::price_at_supply(current_supply, params)?;
-        let end_price = Self::price_at_supply(new_supply, params)?;
-        
-        // Trapezoidal rule: (start_price + end_price) * quantity / 2
-        let average_price = overflow_error(start_price.checked_add(end_price))? / 2;
-        let total_cost = overflow_error(average_price.checked_mul(tokens_to_buy))?;
-        
-        Ok(total_cost)
+        // Exact area under the curve, rounded up for buys
+        curve_models::model_for(params).cost_to_buy(current_supply, tokens_to_buy)
     }
 
-    /// Calculate the sell price for a given number of tokens
-    pub fn calculate_sell_price(
+    /// Base released by the curve for `tokens_to_sell`, before any spread
+    pub fn calculate_sell_proceeds(
         current_supply: u128,
         tokens_to_sell: u128,
         params: &CurveParams,
@@ -63,113 +199,178 @@ impl CurveCalculator {
             return Err(anyhow!("Cannot sell more tokens than current supply"));
         }
 
-        let new_supply = current_supply - tokens_to_sell;
-        
-        let start_price = Self::price_at_supply(new_supply, params)?;
-        let end_price = Self::price_at_supply(current_supply, params)?;
-        
-        // Apply small discount for sells (e.g., 1% lower than buy price)
-        let average_price = overflow_error(start_price.checked_add(end_price))? / 2;
-        let discounted_price = overflow_error(average_price.checked_mul(99))? / 100; // 1% discount
-        let total_payout = overflow_error(discounted_price.checked_mul(tokens_to_sell))?;
-        
-        Ok(total_payout)
+        // Exact area under the curve, rounded down for sells
+        curve_models::model_for(params).proceeds_from_sell(current_supply, tokens_to_sell)
     }
 
-    /// Calculate the price at a specific supply level
-    pub fn price_at_supply(supply: u128, params: &CurveParams) -> Result<u128> {
-        if supply == 0 {
-            return Ok(params.base_price);
-        }
-
-        // Use fixed-point arithmetic to compute (1 + growth_rate/10000)^supply
-        // We'll use a simplified approximation for large supplies
-        let growth_factor = 10000 + params.growth_rate;
-        let price = Self::power_approximation(params.base_price, growth_factor, supply, 10000)?;
-        
-        Ok(price)
+    /// Seller payout and spread withheld for `tokens_to_sell`, at the spread
+    /// in effect `blocks_since_launch` blocks after launch
+    pub fn sell_payout_at(
+        current_supply: u128,
+        tokens_to_sell: u128,
+        params: &CurveParams,
+        blocks_since_launch: u64,
+    ) -> Result<(u128, u128)> {
+        let proceeds = Self::calculate_sell_proceeds(current_supply, tokens_to_sell, params)?;
+        let spread = params.sell_spread.spread_on(proceeds, blocks_since_launch)?;
+        Ok((proceeds - spread, spread))
     }
 
-    /// Approximate (base * (numerator/denominator)^exponent) using repeated multiplication
-    /// with overflow protection and precision management
-    fn power_approximation(
-        base: u128,
-        numerator: u128,
-        exponent: u128,
-        denominator: u128,
+    /// Calculate the sell price for a given number of tokens at the launch spread
+    pub fn calculate_sell_price(
+        current_supply: u128,
+        tokens_to_sell: u128,
+        params: &CurveParams,
     ) -> Result<u128> {
-        if exponent == 0 {
-            return Ok(base);
-        }
+        Ok(Self::sell_payout_at(current_supply, tokens_to_sell, params, 0)?.0)
+    }
 
-        let mut result = base;
-        let mut exp = exponent;
-        
-        // Use binary exponentiation for efficiency
-        while exp > 0 {
-            if exp % 10 == 0 {
-                // Every 10 tokens, apply the growth factor once to avoid overflow
-                result = overflow_error(result.checked_mul(numerator))? / denominator;
-                exp -= 10;
-            } else {
-                // For remaining tokens, apply fractional growth
-                let fractional_growth = denominator + (numerator - denominator) / 10;
-                result = overflow_error(result.checked_mul(fractional_growth))? / denominator;
-                exp -= 1;
-            }
-
-            // Prevent excessive price growth
-            if result > u128::MAX / 1000 {
-                return Ok(u128::MAX / 1000); // Cap at reasonable maximum
-            }
+    /// Send a withheld sell spread to its destination
+    ///
+    /// Returns how much of it leaves `base_reserves`.
+    pub fn apply_sell_spread(spread: u128, params: &CurveParams) -> Result<u128> {
+        match params.sell_spread.destination {
+            SpreadDestination::Reserves => Ok(0),
+            SpreadDestination::Burn => {
+                let mut pointer = Self::burned_spread_pointer();
+                let burned = overflow_error(pointer.get_value::<u128>().checked_add(spread))?;
+                pointer.set_value(burned);
+                Ok(spread)
+            },
+            SpreadDestination::Fees => {
+                FeeEngine::route(&params.fees, spread)?;
+                Ok(spread)
+            },
         }
+    }
 
-        Ok(result)
+    /// Calculate the price at a specific supply level
+    ///
+    /// Prices beyond `MAX_PRICE` saturate rather than fail, so graduation
+    /// checks and quotes stay usable at extreme supplies.
+    pub fn price_at_supply(supply: u128, params: &CurveParams) -> Result<u128> {
+        let price = curve_models::model_for(params)
+            .spot_price(supply)
+            .unwrap_or(MAX_PRICE);
+
+        Ok(std::cmp::min(price, MAX_PRICE))
+    }
+
+    /// `price_at_supply` in Q64.64, keeping sub-unit prices that the whole
+    /// base-unit price rounds to zero
+    pub fn price_at_supply_q64(supply: u128, params: &CurveParams) -> U256 {
+        let cap = U256::mul_u128(MAX_PRICE, fixed_point::ONE);
+        curve_models::model_for(params)
+            .spot_price_q64(supply)
+            .map_or(cap, |price| std::cmp::min(price, cap))
     }
 
-    /// Check if the bonding curve meets graduation criteria
-    pub fn check_graduation_criteria(
+    /// Largest token amount `base_amount` can buy at `current_supply`,
+    /// returned with the leftover base dust to refund
+    pub fn tokens_for_base_amount(
         current_supply: u128,
-        base_reserves: u128,
+        base_amount: u128,
         params: &CurveParams,
-    ) -> bool {
-        // Calculate current market cap
-        let current_price = Self::price_at_supply(current_supply, params).unwrap_or(0);
-        let market_cap = current_supply.saturating_mul(current_price);
-        
-        // Check if market cap exceeds threshold
-        if market_cap >= params.graduation_threshold {
-            return true;
+    ) -> Result<(u128, u128)> {
+        let remaining = params.max_supply.saturating_sub(current_supply);
+        curve_models::model_for(params).tokens_for_base(current_supply, base_amount, remaining)
+    }
+
+    /// Smallest token amount whose sale at `current_supply` pays at least
+    /// `base_out` after the spread, rounded against the seller
+    pub fn tokens_to_sell_for_base_amount(
+        current_supply: u128,
+        base_out: u128,
+        params: &CurveParams,
+        blocks_since_launch: u64,
+    ) -> Result<u128> {
+        if base_out == 0 {
+            return Ok(0);
         }
 
-        // Alternative criteria: minimum liquidity reserves
-        let min_reserves = params.graduation_threshold / 2; // 50,000 BUSD equivalent
-        if base_reserves >= min_reserves {
-            return true;
+        // Selling never beats the current price, so this starts at or below the answer
+        let estimate = base_out / Self::price_at_supply(current_supply, params)?.max(1);
+        let short = curve_models::settle(estimate, current_supply, |amount| {
+            Self::sell_payout_at(current_supply, amount, params, blocks_since_launch)
+                .map(|(payout, _)| payout < base_out)
+                .unwrap_or(false)
+        });
+
+        if short == current_supply {
+            return Err(anyhow!("Selling the entire supply pays less than {} base", base_out));
         }
+        Ok(short + 1)
+    }
 
-        false
+    /// Check if the bonding curve meets its graduation criteria
+    pub fn check_graduation_criteria(metrics: &GraduationMetrics, params: &CurveParams) -> bool {
+        params.criteria().is_met(metrics, params)
     }
 
     /// Storage pointers for bonding curve state
-    pub fn curve_params_pointer() -> StoragePointer {
-        StoragePointer::from_keyword("/curve_params")
+    pub fn curve_params_pointer() -> JournaledPointer {
+        JournaledPointer::from_keyword("/curve_params")
+    }
+
+    pub fn base_reserves_pointer() -> JournaledPointer {
+        JournaledPointer::from_keyword("/base_reserves")
+    }
+
+    pub fn token_reserves_pointer() -> JournaledPointer {
+        JournaledPointer::from_keyword("/token_reserves")
+    }
+
+    /// Holds the `GraduationState` byte (formerly a 0/1 graduated flag)
+    pub fn graduated_pointer() -> JournaledPointer {
+        JournaledPointer::from_keyword("/graduated")
+    }
+
+    pub fn graduation_failure_pointer() -> JournaledPointer {
+        JournaledPointer::from_keyword("/graduation/failure")
+    }
+
+    pub fn buys_pointer() -> JournaledPointer {
+        JournaledPointer::from_keyword("/graduation/buys")
+    }
+
+    pub fn launch_time_pointer() -> JournaledPointer {
+        JournaledPointer::from_keyword("/launch_time")
+    }
+
+    pub fn launch_block_pointer() -> JournaledPointer {
+        JournaledPointer::from_keyword("/launch_block")
+    }
+
+    pub fn burned_spread_pointer() -> JournaledPointer {
+        JournaledPointer::from_keyword("/burned_spread")
+    }
+
+    /// Circulating token supply, shared with `MintableToken`
+    pub fn total_supply_pointer() -> JournaledPointer {
+        JournaledPointer::from_keyword("/totalsupply")
     }
 
-    pub fn base_reserves_pointer() -> StoragePointer {
-        StoragePointer::from_keyword("/base_reserves")
+    pub fn lp_minted_pointer() -> JournaledPointer {
+        JournaledPointer::from_keyword("/tokenomics/lp_minted")
     }
 
-    pub fn token_reserves_pointer() -> StoragePointer {
-        StoragePointer::from_keyword("/token_reserves")
+    pub fn burned_supply_pointer() -> JournaledPointer {
+        JournaledPointer::from_keyword("/tokenomics/burned")
     }
 
-    pub fn graduated_pointer() -> StoragePointer {
-        StoragePointer::from_keyword("/graduated")
+    /// Block the curve was initialized at
+    pub fn get_launch_block() -> u64 {
+        Self::launch_block_pointer().get_value::<u64>()
     }
 
-    pub fn launch_time_pointer() -> StoragePointer {
-        StoragePointer::from_keyword("/launch_time")
+    /// Record the block the curve was initialized at
+    pub fn set_launch_block(block: u64) {
+        Self::launch_block_pointer().set_value::<u64>(block);
+    }
+
+    /// Total sell spread burned so far
+    pub fn get_burned_spread() -> u128 {
+        Self::burned_spread_pointer().get_value::<u128>()
     }
 
     /// Get curve parameters from storage
@@ -211,14 +412,103 @@ impl CurveCalculator {
         Self::token_reserves_pointer().set_value::<u128>(amount);
     }
 
+    /// Keep the virtual token reserve in step with supply for constant-product curves
+    pub fn sync_token_reserves(supply: u128, params: &CurveParams) {
+        if params.curve_type == CurveType::ConstantProduct {
+            Self::set_token_reserves(params.virtual_token_reserve.saturating_sub(supply));
+        }
+    }
+
+    /// Current graduation state; unknown bytes read as `GraduationPending` so
+    /// trading stays paused until a retry settles it
+    pub fn graduation_state() -> GraduationState {
+        GraduationState::from_u8(Self::graduated_pointer().get_value::<u8>())
+            .unwrap_or(GraduationState::GraduationPending)
+    }
+
+    /// Move the curve to `state`
+    pub fn set_graduation_state(state: GraduationState) {
+        Self::graduated_pointer().set_value::<u8>(state.as_u8());
+    }
+
     /// Check if curve has graduated to AMM
     pub fn is_graduated() -> bool {
-        Self::graduated_pointer().get_value::<u8>() == 1
+        Self::graduation_state() == GraduationState::Graduated
     }
 
     /// Mark curve as graduated
     pub fn set_graduated() {
-        Self::graduated_pointer().set_value::<u8>(1);
+        Self::set_graduation_state(GraduationState::Graduated);
+    }
+
+    /// Filled buys counted toward graduation
+    pub fn get_buy_count() -> u128 {
+        Self::buys_pointer().get_value::<u128>()
+    }
+
+    /// Count a filled buy toward the buys condition
+    pub fn record_buy() -> Result<()> {
+        let buys = overflow_error(Self::get_buy_count().checked_add(1))?;
+        Self::buys_pointer().set_value::<u128>(buys);
+        Ok(())
+    }
+
+    /// Why the last graduation attempt failed, if it did
+    pub fn graduation_failure() -> Option<String> {
+        let data = Self::graduation_failure_pointer().get();
+        if data.is_empty() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(data.as_ref()).into_owned())
+    }
+
+    /// Record the reason of a failed attempt, or clear it with `None`
+    pub fn set_graduation_failure(reason: Option<&str>) {
+        let data = reason.map(|reason| reason.as_bytes().to_vec()).unwrap_or_default();
+        Self::graduation_failure_pointer().set(Arc::new(data));
+    }
+
+    /// Tokens sold along the curve: the circulating supply less any minted
+    /// into the pool at graduation
+    pub fn sold_supply() -> u128 {
+        Self::total_supply_pointer()
+            .get_value::<u128>()
+            .saturating_sub(Self::lp_minted_pointer().get_value::<u128>())
+    }
+
+    /// Supply split and graduation outcome of the curve
+    pub fn tokenomics(params: &CurveParams) -> Tokenomics {
+        Tokenomics {
+            lp_minted: Self::lp_minted_pointer().get_value::<u128>(),
+            burned: Self::burned_supply_pointer().get_value::<u128>(),
+            ..Tokenomics::new(params, Self::sold_supply())
+        }
+    }
+
+    /// Mint `amount` tokens for the pool when graduating with `sold` tokens
+    /// sold, burning the rest of the unminted allocation
+    pub fn mint_pool_tokens(sold: u128, amount: u128, params: &CurveParams) -> Result<Tokenomics> {
+        let tokenomics = Tokenomics::new(params, sold).graduate(amount)?;
+
+        let total_supply = Self::total_supply_pointer().get_value::<u128>();
+        Self::total_supply_pointer().set_value::<u128>(overflow_error(total_supply.checked_add(amount))?);
+        Self::lp_minted_pointer().set_value::<u128>(tokenomics.lp_minted);
+        Self::burned_supply_pointer().set_value::<u128>(tokenomics.burned);
+        Ok(tokenomics)
+    }
+
+    /// Fail unless the curve is open for trading
+    ///
+    /// Trading reopens after a failed graduation, so a stuck AMM never
+    /// locks holders in; only an attempt under way pauses it.
+    pub fn ensure_trading() -> Result<()> {
+        match Self::graduation_state() {
+            GraduationState::Trading | GraduationState::Failed => Ok(()),
+            GraduationState::Graduated => Err(anyhow!("Bonding curve has graduated to AMM")),
+            GraduationState::GraduationPending => {
+                Err(anyhow!("Graduation pending: trading is paused until it completes"))
+            },
+        }
     }
 }
 
@@ -231,7 +521,7 @@ mod tests {
         let params = CurveParams::default();
         
         // Test buying 1000 tokens at 0 supply
-        let price = BondingCurve::calculate_buy_price(0, 1000, &params).unwrap();
+        let price = CurveCalculator::calculate_buy_price(0, 1000, &params).unwrap();
         assert!(price > 0);
         assert!(price >= params.base_price * 1000);
     }
@@ -241,7 +531,7 @@ mod tests {
         let params = CurveParams::default();
         
         // Test selling 500 tokens from 1000 supply
-        let price = BondingCurve::calculate_sell_price(1000, 500, &params).unwrap();
+        let price = CurveCalculator::calculate_sell_price(1000, 500, &params).unwrap();
         assert!(price > 0);
     }
 
@@ -250,10 +540,10 @@ mod tests {
         let params = CurveParams::default();
         
         // Should not graduate with low supply and reserves
-        assert!(!BondingCurve::check_graduation_criteria(1000, 1000, &params));
+        assert!(!CurveCalculator::check_graduation_criteria(&GraduationMetrics::at(1000, 1000), &params));
         
         // Should graduate with high reserves
         let high_reserves = params.graduation_threshold;
-        assert!(BondingCurve::check_graduation_criteria(1000, high_reserves, &params));
+        assert!(CurveCalculator::check_graduation_criteria(&GraduationMetrics::at(1000, high_reserves), &params));
     }
 } 
\ No newline at end of file
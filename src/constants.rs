@@ -4,8 +4,8 @@ pub const ALKANE_FACTORY_FREE_MINT_ID: u128 = 0x0ffe;
 
 // Security constants
 pub const MAX_SLIPPAGE_BPS: u128 = 500;              // 5% maximum slippage
-pub const MIN_LIQUIDITY_FOR_GRADUATION: u128 = 3_500_000_000; // $35k USD minimum liquidity
-pub const MIN_HOLDERS_FOR_GRADUATION: u32 = 100;     // Minimum unique holders
+pub const MIN_LIQUIDITY_FOR_GRADUATION: u128 = 3_500_000_000; // $35k suggested target of the reserves condition
+pub const MIN_HOLDERS_FOR_GRADUATION: u32 = 100;     // Suggested target of the buys condition, the holder proxy
 pub const TIME_LOCK_DURATION: u64 = 86400;           // 24 hour time lock
 
 // Trading limits
@@ -20,10 +20,15 @@ pub const FACTORY_DEPLOYMENT_FEE: u128 = 100_000_000; // 0.001 BTC deployment fe
 
 // Economic constants
 pub const DEFAULT_BASE_PRICE: u128 = 4_000_000;     // 0.04 BUSD starting price
-pub const DEFAULT_GROWTH_RATE: u128 = 150;          // 1.5% growth per token
+pub const DEFAULT_GROWTH_RATE: u128 = 150;          // 1.5% growth per growth step
+pub const DEFAULT_GROWTH_STEPS: u128 = 100;         // One growth step per 1% of max supply
 pub const DEFAULT_GRADUATION_THRESHOLD: u128 = 6_900_000_000; // $69k market cap
 pub const DEFAULT_MAX_SUPPLY: u128 = 1_000_000_000; // 1 billion tokens
 
 // AMM integration constants
 pub const AMM_INITIAL_LIQUIDITY_RATIO: u128 = 5000; // 50% of reserves for AMM
+pub const DEFAULT_LP_RESERVE_BPS: u128 = 2_000;     // 20% of max supply reserved to seed the pool
 pub const LP_BURN_PERCENTAGE: u128 = 8000;          // 80% LP burned by default
+pub const EMERGENCY_GRADUATION_DELAY_BLOCKS: u64 = 4_320; // ~30 days of blocks before emergency graduation
+pub const EMERGENCY_GRADUATION_MIN_RESERVES: u128 = 100_000_000; // 1 BUSD floor for an emergency pool
+pub const GRADUATION_PRICE_TOLERANCE_BPS: u128 = 100;   // Pool may open within 1% of the final curve price
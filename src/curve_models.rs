@@ -0,0 +1,767 @@
+//! Bonding Curve Models
+//!
+//! Pricing shapes a curve can be launched with. Each model implements
+//! `BondingCurveModel`, and `CurveCalculator` dispatches to the one selected
+//! by `CurveParams::curve_type`:
+//! - Exponential: base * (1 + rate)^(x / step), step = max_supply / 100
+//! - Linear/Quadratic/Cubic: base + (max - base) * (x / max_supply)^d
+//! - Sigmoid: base + (max - base) / (1 + e^(-k * (x - midpoint)))
+//! - Piecewise-linear: interpolation between (supply, price) breakpoints
+//! - Constant-product: x * y = k over virtual base and token reserves
+//! - Reserve-ratio: Bancor formula, reserve = weight * supply * price
+//!
+//! Costs are exact integrals of the price function in fixed point, rounded
+//! up for buys and down for sells.
+
+use crate::fixed_point::{self, WideFloat, U256, ONE};
+use crate::{CurveParams, CurveType};
+use alkanes_support::utils::overflow_error;
+use anyhow::{anyhow, Result};
+
+/// Non-exponential curves keep supplies below 2^63 so squared terms fit in u128
+pub const MAX_MODEL_SUPPLY: u128 = 1u128 << 63;
+
+/// Denominator for reserve-ratio connector weights (parts per million)
+pub const CONNECTOR_WEIGHT_PPM: u128 = 1_000_000;
+
+/// Upper bound on Newton steps in the default `estimate_tokens`. The steps
+/// are not guaranteed to converge for every shape: past the Sigmoid midpoint
+/// the price curve is concave and flattens, and each step divides by a price
+/// rounded to whole base units. The estimate is only a starting point;
+/// `settle` corrects it and terminates however the steps end.
+pub const MAX_NEWTON_STEPS: u32 = 16;
+
+/// Pricing model behind a bonding curve
+pub trait BondingCurveModel {
+    /// Marginal price at `supply`, rounded down to whole base units
+    fn spot_price(&self, supply: u128) -> Result<u128>;
+
+    /// Marginal price at `supply` in Q64.64, keeping the fraction of a base
+    /// unit `spot_price` rounds away. The default scales `spot_price`.
+    fn spot_price_q64(&self, supply: u128) -> Result<U256> {
+        Ok(U256::mul_u128(self.spot_price(supply)?, ONE))
+    }
+
+    /// Base needed to mint `amount` tokens starting at `supply`, rounded up
+    fn cost_to_buy(&self, supply: u128, amount: u128) -> Result<u128>;
+
+    /// Base released by burning `amount` tokens down from `supply`, rounded down
+    fn proceeds_from_sell(&self, supply: u128, amount: u128) -> Result<u128>;
+
+    /// Approximate number of tokens `base_amount` buys from `supply`
+    ///
+    /// Models with a closed-form inverse override this. The default runs
+    /// Newton's method on `cost_to_buy - base_amount` with the spot price as
+    /// the derivative, starting from `base_amount / spot_price`.
+    fn estimate_tokens(&self, supply: u128, base_amount: u128, max_amount: u128) -> Result<u128> {
+        let mut tokens = match self.spot_price(supply)? {
+            0 => max_amount,
+            price => (base_amount / price).min(max_amount),
+        };
+
+        for _ in 0..MAX_NEWTON_STEPS {
+            if tokens == 0 {
+                break;
+            }
+            let cost = self.cost_to_buy(supply, tokens)?;
+            if cost <= base_amount {
+                break;
+            }
+            let end = overflow_error(supply.checked_add(tokens))?;
+            let price = self.spot_price(end)?.max(1);
+            tokens = tokens.saturating_sub(((cost - base_amount) / price).max(1));
+        }
+
+        Ok(tokens)
+    }
+
+    /// Largest amount up to `max_amount` whose cost does not exceed
+    /// `base_amount`, with the base left over after paying for it
+    ///
+    /// Starts from `estimate_tokens` and `settle`s onto the exact rounding
+    /// boundary of `cost_to_buy`, so any remainder always favours the curve.
+    /// With an exact estimate that takes two probes. A cost that overflows
+    /// counts as unaffordable.
+    fn tokens_for_base(&self, supply: u128, base_amount: u128, max_amount: u128) -> Result<(u128, u128)> {
+        let estimate = self
+            .estimate_tokens(supply, base_amount, max_amount)
+            .unwrap_or(max_amount);
+        let tokens = settle(estimate, max_amount, |amount| {
+            self.cost_to_buy(supply, amount)
+                .map(|cost| cost <= base_amount)
+                .unwrap_or(false)
+        });
+
+        let cost = if tokens == 0 { 0 } else { self.cost_to_buy(supply, tokens)? };
+        Ok((tokens, base_amount - cost))
+    }
+}
+
+/// Largest amount up to `max_amount` for which `holds` is true, starting
+/// from `estimate`
+///
+/// `holds` must be monotone (true up to the answer, false beyond it) and is
+/// assumed true at zero. Gallops away from the estimate to bracket the
+/// answer, then bisects. In total it probes at most
+/// `bonding_curve::MAX_SEARCH_PROBES` amounts.
+pub fn settle(estimate: u128, max_amount: u128, holds: impl Fn(u128) -> bool) -> u128 {
+    settle_with_probes(estimate, max_amount, holds).0
+}
+
+/// `settle`, also returning the number of amounts it probed
+pub fn settle_with_probes(estimate: u128, max_amount: u128, holds: impl Fn(u128) -> bool) -> (u128, u32) {
+    let probes = std::cell::Cell::new(0u32);
+    let holds = |amount: u128| {
+        probes.set(probes.get() + 1);
+        amount == 0 || holds(amount)
+    };
+    let estimate = estimate.min(max_amount);
+
+    let (mut low, mut high) = if holds(estimate) {
+        let mut low = estimate;
+        let mut step = 1u128;
+        loop {
+            if low == max_amount {
+                break (low, low);
+            }
+            let probe = low.saturating_add(step).min(max_amount);
+            if !holds(probe) {
+                break (low, probe - 1);
+            }
+            low = probe;
+            step = step.saturating_mul(2);
+        }
+    } else {
+        let mut high = estimate - 1;
+        let mut step = 1u128;
+        loop {
+            let probe = high.saturating_sub(step);
+            if holds(probe) {
+                break (probe, high);
+            }
+            high = probe - 1;
+            step = step.saturating_mul(2);
+        }
+    };
+
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        if holds(mid) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    (low, probes.get())
+}
+
+/// Select the model configured in `params`
+pub fn model_for(params: &CurveParams) -> Box<dyn BondingCurveModel + '_> {
+    match params.curve_type {
+        CurveType::Exponential => Box::new(ExponentialCurve { params }),
+        CurveType::Linear => Box::new(PolynomialCurve { params, degree: 1 }),
+        CurveType::Quadratic => Box::new(PolynomialCurve { params, degree: 2 }),
+        CurveType::Cubic => Box::new(PolynomialCurve { params, degree: 3 }),
+        CurveType::Sigmoid => Box::new(SigmoidCurve { params }),
+        CurveType::PiecewiseLinear => Box::new(PiecewiseLinearCurve { params }),
+        CurveType::ConstantProduct => Box::new(ConstantProductCurve { params }),
+        CurveType::ReserveRatio => Box::new(ReserveRatioCurve { params }),
+    }
+}
+
+/// Check that `params` describe a well-formed curve of its type
+pub fn validate(params: &CurveParams) -> Result<()> {
+    if params.max_supply == 0 {
+        return Err(anyhow!("Max supply must be positive"));
+    }
+
+    if params.curve_type == CurveType::Exponential {
+        return ExponentialCurve { params }
+            .spot_price(params.max_supply)
+            .map(|_| ())
+            .map_err(|_| anyhow!("Exponential price overflows before max supply"));
+    }
+
+    if params.max_supply > MAX_MODEL_SUPPLY {
+        return Err(anyhow!("Max supply too large for {:?} curve", params.curve_type));
+    }
+
+    match params.curve_type {
+        CurveType::Linear | CurveType::Quadratic | CurveType::Cubic => {
+            if params.max_price <= params.base_price {
+                return Err(anyhow!("Max price must exceed base price"));
+            }
+        },
+
+        CurveType::Sigmoid => {
+            if params.max_price <= params.base_price {
+                return Err(anyhow!("Max price must exceed base price"));
+            }
+            if params.growth_rate == 0 {
+                return Err(anyhow!("Sigmoid steepness (growth rate) must be positive"));
+            }
+            if params.midpoint_supply > params.max_supply {
+                return Err(anyhow!("Sigmoid midpoint must not exceed max supply"));
+            }
+        },
+
+        CurveType::PiecewiseLinear => {
+            if params.breakpoints.is_empty() {
+                return Err(anyhow!("Piecewise-linear curve needs at least one breakpoint"));
+            }
+
+            let mut previous = (0u128, params.base_price);
+            for &(supply, price) in &params.breakpoints {
+                if supply <= previous.0 || supply > params.max_supply {
+                    return Err(anyhow!("Breakpoint supplies must increase and stay within max supply"));
+                }
+                if price < previous.1 {
+                    return Err(anyhow!("Breakpoint prices must not decrease"));
+                }
+                previous = (supply, price);
+            }
+        },
+
+        CurveType::ConstantProduct => {
+            if params.virtual_base_reserve == 0 {
+                return Err(anyhow!("Virtual base reserve must be positive"));
+            }
+            if params.virtual_token_reserve <= params.max_supply {
+                return Err(anyhow!("Virtual token reserve must exceed max supply"));
+            }
+        },
+
+        CurveType::ReserveRatio => {
+            if params.connector_weight == 0 || params.connector_weight > CONNECTOR_WEIGHT_PPM {
+                return Err(anyhow!("Connector weight must be between 1 and 1000000 ppm"));
+            }
+            if params.virtual_supply == 0 || params.virtual_base_reserve == 0 {
+                return Err(anyhow!("Virtual supply and reserve must be positive"));
+            }
+        },
+
+        CurveType::Exponential => {},
+    }
+
+    Ok(())
+}
+
+/// price = base * (1 + growth_rate / 10000)^(supply / step), with
+/// step = max_supply / growth_steps, or one base unit when growth_steps is 0
+pub struct ExponentialCurve<'a> {
+    params: &'a CurveParams,
+}
+
+impl ExponentialCurve<'_> {
+    fn growth_numerator(&self) -> Result<u128> {
+        overflow_error(fixed_point::BPS.checked_add(self.params.growth_rate))
+    }
+
+    /// Supply over which the price grows by one factor of (1 + rate)
+    fn step(&self) -> u128 {
+        match self.params.growth_steps {
+            0 => 1,
+            steps => (self.params.max_supply / steps).max(1),
+        }
+    }
+
+    /// (1 + rate)^(supply / step)
+    ///
+    /// Whole steps use exponentiation by squaring, so the cost is bounded by
+    /// `fixed_point::MAX_POW_ITERATIONS` regardless of supply. The partial
+    /// step is 2^(log2(1 + rate) * remainder / step) via `exp2_frac`.
+    fn growth_factor(&self, supply: u128) -> Result<WideFloat> {
+        let step = self.step();
+        let whole = WideFloat::from_ratio(self.growth_numerator()?, fixed_point::BPS)?
+            .powi(supply / step)?;
+        let remainder = supply % step;
+        if remainder == 0 || self.params.growth_rate == 0 {
+            return Ok(whole);
+        }
+
+        let growth = fixed_point::from_ratio(self.growth_numerator()?, fixed_point::BPS)?;
+        let exponent = fixed_point::mul_div(fixed_point::log2(growth)?, remainder, step)?;
+        let partial = u32::try_from(exponent >> 64)
+            .ok()
+            .filter(|shift| *shift < 63)
+            .map(|shift| fixed_point::exp2_frac(exponent) << shift)
+            .ok_or_else(|| anyhow!("Growth rate too large"))?;
+
+        whole.mul(WideFloat::from_ratio(partial, ONE)?)
+    }
+
+    /// base_price * (1 + rate)^(supply / step) in Q.64, kept in 256 bits
+    fn scaled_price(&self, supply: u128) -> Result<U256> {
+        self.growth_factor(supply)?
+            .scaled_q64(self.params.base_price)
+            .map_err(|_| anyhow!("Price overflow at supply {}", supply))
+    }
+
+    /// step * base * ((1 + rate)^(b / step) - (1 + rate)^(a / step)) / ln(1 + rate)
+    ///
+    /// Each endpoint price carries a relative error below 2^-57. The cost
+    /// telescopes over those endpoint prices, so splitting a trade changes
+    /// its total only by the per-trade rounding.
+    fn integral(&self, from_supply: u128, to_supply: u128, round_up: bool) -> Result<u128> {
+        let amount = to_supply - from_supply;
+        if self.params.growth_rate == 0 {
+            return overflow_error(self.params.base_price.checked_mul(amount));
+        }
+
+        let step = self.step();
+        let growth = fixed_point::from_ratio(self.growth_numerator()?, fixed_point::BPS)?;
+        let ln_growth = fixed_point::ln(growth)?;
+        let scaled_diff = overflow_error(
+            self.scaled_price(to_supply)?
+                .checked_sub(self.scaled_price(from_supply)?),
+        )?;
+
+        // Both sides carry a 2^64 scale, so the quotient is in base token
+        // units per step; the remainder is scaled separately to stay exact
+        let (quotient, remainder) = scaled_diff
+            .div_rem_u128(ln_growth)
+            .ok_or_else(|| anyhow!("Invalid growth rate"))?;
+        let cost = overflow_error(
+            quotient
+                .to_u128()
+                .and_then(|quotient| quotient.checked_mul(step)),
+        )?;
+        let partial = if round_up {
+            fixed_point::mul_div_up(remainder, step, ln_growth)?
+        } else {
+            fixed_point::mul_div(remainder, step, ln_growth)?
+        };
+
+        overflow_error(cost.checked_add(partial))
+    }
+}
+
+impl BondingCurveModel for ExponentialCurve<'_> {
+    fn spot_price(&self, supply: u128) -> Result<u128> {
+        overflow_error(self.scaled_price(supply)?.shr(64).to_u128())
+    }
+
+    fn spot_price_q64(&self, supply: u128) -> Result<U256> {
+        self.scaled_price(supply)
+    }
+
+    /// n = step * log_g(1 + b * ln(g) / (step * price(a))), the integral solved for n
+    fn estimate_tokens(&self, supply: u128, base_amount: u128, max_amount: u128) -> Result<u128> {
+        if self.params.growth_rate == 0 {
+            return match self.params.base_price {
+                0 => Ok(max_amount),
+                price => Ok(base_amount / price),
+            };
+        }
+
+        let step = self.step();
+        let growth = fixed_point::from_ratio(self.growth_numerator()?, fixed_point::BPS)?;
+        let ln_growth = fixed_point::ln(growth)?;
+        let step_value = overflow_error(self.params.base_price.checked_mul(step))?;
+        let relative = fixed_point::ratio_q64(
+            U256::mul_u128(base_amount, ln_growth),
+            self.growth_factor(supply)?.scaled_q64(step_value)?,
+        )?;
+
+        let log_target = fixed_point::log2(overflow_error(ONE.checked_add(relative))?)?;
+        fixed_point::mul_div(log_target, step, fixed_point::log2(growth)?)
+    }
+
+    fn cost_to_buy(&self, supply: u128, amount: u128) -> Result<u128> {
+        let end = overflow_error(supply.checked_add(amount))?;
+        self.integral(supply, end, true)
+    }
+
+    fn proceeds_from_sell(&self, supply: u128, amount: u128) -> Result<u128> {
+        let start = overflow_error(supply.checked_sub(amount))?;
+        self.integral(start, supply, false)
+    }
+}
+
+/// price = base + (max_price - base) * (supply / max_supply)^degree
+pub struct PolynomialCurve<'a> {
+    params: &'a CurveParams,
+    degree: u32,
+}
+
+impl PolynomialCurve<'_> {
+    fn span(&self) -> u128 {
+        self.params.max_price.saturating_sub(self.params.base_price)
+    }
+
+    /// supply^(degree + 1) / max_supply^degree in Q64.64
+    fn scaled_moment(&self, supply: u128) -> Result<u128> {
+        if supply > MAX_MODEL_SUPPLY {
+            return Err(anyhow!("Supply too large for polynomial curve"));
+        }
+
+        let mut moment = supply << 64;
+        for _ in 0..self.degree {
+            moment = fixed_point::mul_div(moment, supply, self.params.max_supply)?;
+        }
+        Ok(moment)
+    }
+
+    fn integral(&self, from_supply: u128, to_supply: u128, round_up: bool) -> Result<u128> {
+        let amount = to_supply - from_supply;
+        let flat = overflow_error(self.params.base_price.checked_mul(amount))?;
+
+        let delta = overflow_error(
+            self.scaled_moment(to_supply)?
+                .checked_sub(self.scaled_moment(from_supply)?),
+        )?;
+        let denominator = (self.degree as u128 + 1) << 64;
+        let curved = if round_up {
+            fixed_point::mul_div_up(self.span(), delta, denominator)?
+        } else {
+            fixed_point::mul_div(self.span(), delta, denominator)?
+        };
+
+        overflow_error(flat.checked_add(curved))
+    }
+}
+
+impl BondingCurveModel for PolynomialCurve<'_> {
+    fn spot_price(&self, supply: u128) -> Result<u128> {
+        let mut ratio = ONE;
+        for _ in 0..self.degree {
+            ratio = fixed_point::mul_div(ratio, supply, self.params.max_supply)?;
+        }
+        let curved = fixed_point::mul_div(self.span(), ratio, ONE)?;
+        overflow_error(self.params.base_price.checked_add(curved))
+    }
+
+    fn cost_to_buy(&self, supply: u128, amount: u128) -> Result<u128> {
+        let end = overflow_error(supply.checked_add(amount))?;
+        self.integral(supply, end, true)
+    }
+
+    fn proceeds_from_sell(&self, supply: u128, amount: u128) -> Result<u128> {
+        let start = overflow_error(supply.checked_sub(amount))?;
+        self.integral(start, supply, false)
+    }
+}
+
+/// price = base + (max_price - base) / (1 + e^(-k * (supply - midpoint)))
+///
+/// The steepness k is `growth_rate` basis points per 1% of `max_supply`, so
+/// the curve keeps its shape at any supply scale: across the whole supply the
+/// exponent moves by `growth_rate / 100`.
+pub struct SigmoidCurve<'a> {
+    params: &'a CurveParams,
+}
+
+impl SigmoidCurve<'_> {
+    fn span(&self) -> u128 {
+        self.params.max_price.saturating_sub(self.params.base_price)
+    }
+
+    /// Supply over which the exponent moves by `growth_rate`: 100 * max_supply
+    fn scale(&self) -> Result<u128> {
+        overflow_error(self.params.max_supply.checked_mul(100))
+    }
+
+    /// k * (supply - midpoint) in Q64.64 as (magnitude, is_negative)
+    fn offset(&self, supply: u128) -> Result<(u128, bool)> {
+        let midpoint = self.params.midpoint_supply;
+        let (distance, negative) = if supply >= midpoint {
+            (supply - midpoint, false)
+        } else {
+            (midpoint - supply, true)
+        };
+        let rate = overflow_error(self.params.growth_rate.checked_mul(ONE))?;
+        Ok((fixed_point::mul_div(distance, rate, self.scale()?)?, negative))
+    }
+
+    /// base * n + (span / k) * (softplus(k(b - m)) - softplus(k(a - m)))
+    fn integral(&self, from_supply: u128, to_supply: u128, round_up: bool) -> Result<u128> {
+        let amount = to_supply - from_supply;
+        let flat = overflow_error(self.params.base_price.checked_mul(amount))?;
+
+        let (to_magnitude, to_negative) = self.offset(to_supply)?;
+        let (from_magnitude, from_negative) = self.offset(from_supply)?;
+        let delta = overflow_error(
+            fixed_point::softplus(to_magnitude, to_negative)?
+                .checked_sub(fixed_point::softplus(from_magnitude, from_negative)?),
+        )?;
+
+        // delta / k = delta * scale / growth_rate tokens, still in Q64.64
+        let curved = if round_up {
+            let width = fixed_point::mul_div_up(delta, self.scale()?, self.params.growth_rate)?;
+            fixed_point::mul_div_up(self.span(), width, ONE)?
+        } else {
+            let width = fixed_point::mul_div(delta, self.scale()?, self.params.growth_rate)?;
+            fixed_point::mul_div(self.span(), width, ONE)?
+        };
+
+        overflow_error(flat.checked_add(curved))
+    }
+}
+
+impl BondingCurveModel for SigmoidCurve<'_> {
+    fn spot_price(&self, supply: u128) -> Result<u128> {
+        let (magnitude, negative) = self.offset(supply)?;
+        let tail = fixed_point::exp_neg(magnitude)?;
+
+        // sigma(z) = 1 / (1 + e^-z) = e^z / (1 + e^z)
+        let sigma = if negative {
+            fixed_point::mul_div(tail, ONE, ONE + tail)?
+        } else {
+            fixed_point::mul_div(ONE, ONE, ONE + tail)?
+        };
+
+        let curved = fixed_point::mul_div(self.span(), sigma, ONE)?;
+        overflow_error(self.params.base_price.checked_add(curved))
+    }
+
+    fn cost_to_buy(&self, supply: u128, amount: u128) -> Result<u128> {
+        let end = overflow_error(supply.checked_add(amount))?;
+        self.integral(supply, end, true)
+    }
+
+    fn proceeds_from_sell(&self, supply: u128, amount: u128) -> Result<u128> {
+        let start = overflow_error(supply.checked_sub(amount))?;
+        self.integral(start, supply, false)
+    }
+}
+
+/// Linear interpolation from (0, base_price) through each breakpoint,
+/// flat at the last breakpoint price beyond it
+pub struct PiecewiseLinearCurve<'a> {
+    params: &'a CurveParams,
+}
+
+impl PiecewiseLinearCurve<'_> {
+    fn points(&self) -> Vec<(u128, u128)> {
+        let mut points = Vec::with_capacity(self.params.breakpoints.len() + 1);
+        points.push((0, self.params.base_price));
+        points.extend(self.params.breakpoints.iter().copied());
+        points
+    }
+
+    /// Area of one segment between `low` and `high`, which lie inside it
+    fn segment_area(
+        start: (u128, u128),
+        end: (u128, u128),
+        low: u128,
+        high: u128,
+        round_up: bool,
+    ) -> Result<u128> {
+        let width = high - low;
+        let flat = overflow_error(start.1.checked_mul(width))?;
+
+        // slope * ((high - s0)^2 - (low - s0)^2) / 2 = dp * width * (high + low - 2 s0) / (2 len)
+        let rise = end.1 - start.1;
+        let run = end.0 - start.0;
+        let spread = overflow_error(
+            width.checked_mul(high + low - 2 * start.0),
+        )?;
+        let sloped = if round_up {
+            fixed_point::mul_div_up(rise, spread, 2 * run)?
+        } else {
+            fixed_point::mul_div(rise, spread, 2 * run)?
+        };
+
+        overflow_error(flat.checked_add(sloped))
+    }
+
+    fn integral(&self, from_supply: u128, to_supply: u128, round_up: bool) -> Result<u128> {
+        let points = self.points();
+        let mut total = 0u128;
+
+        for segment in points.windows(2) {
+            let (start, end) = (segment[0], segment[1]);
+            let low = from_supply.max(start.0);
+            let high = to_supply.min(end.0);
+            if low < high {
+                let area = Self::segment_area(start, end, low, high, round_up)?;
+                total = overflow_error(total.checked_add(area))?;
+            }
+        }
+
+        let last = points[points.len() - 1];
+        if to_supply > last.0 {
+            let width = to_supply - from_supply.max(last.0);
+            let tail = overflow_error(last.1.checked_mul(width))?;
+            total = overflow_error(total.checked_add(tail))?;
+        }
+
+        Ok(total)
+    }
+}
+
+impl BondingCurveModel for PiecewiseLinearCurve<'_> {
+    fn spot_price(&self, supply: u128) -> Result<u128> {
+        let points = self.points();
+
+        for segment in points.windows(2) {
+            let (start, end) = (segment[0], segment[1]);
+            if supply < end.0 {
+                let rise = fixed_point::mul_div(end.1 - start.1, supply - start.0, end.0 - start.0)?;
+                return overflow_error(start.1.checked_add(rise));
+            }
+        }
+
+        Ok(points[points.len() - 1].1)
+    }
+
+    fn cost_to_buy(&self, supply: u128, amount: u128) -> Result<u128> {
+        let end = overflow_error(supply.checked_add(amount))?;
+        self.integral(supply, end, true)
+    }
+
+    fn proceeds_from_sell(&self, supply: u128, amount: u128) -> Result<u128> {
+        let start = overflow_error(supply.checked_sub(amount))?;
+        self.integral(start, supply, false)
+    }
+}
+
+/// Constant-product pricing over virtual reserves (pump.fun style)
+///
+/// With k = virtual_base * virtual_token, minting `supply` tokens leaves
+/// T = virtual_token - supply in the virtual pool against B = k / T base,
+/// so the marginal price is B / T.
+pub struct ConstantProductCurve<'a> {
+    params: &'a CurveParams,
+}
+
+impl ConstantProductCurve<'_> {
+    /// Virtual tokens left in the pool at `supply`
+    fn token_reserve(&self, supply: u128) -> Result<u128> {
+        self.params
+            .virtual_token_reserve
+            .checked_sub(supply)
+            .filter(|reserve| *reserve > 0)
+            .ok_or_else(|| anyhow!("Supply exhausts the virtual token reserve"))
+    }
+
+    /// Virtual base in the pool at `supply` (k / T)
+    fn base_reserve(&self, supply: u128, round_up: bool) -> Result<u128> {
+        let token_reserve = self.token_reserve(supply)?;
+        let (virtual_base, virtual_token) = (self.params.virtual_base_reserve, self.params.virtual_token_reserve);
+        if round_up {
+            fixed_point::mul_div_up(virtual_base, virtual_token, token_reserve)
+        } else {
+            fixed_point::mul_div(virtual_base, virtual_token, token_reserve)
+        }
+    }
+}
+
+impl BondingCurveModel for ConstantProductCurve<'_> {
+    fn spot_price(&self, supply: u128) -> Result<u128> {
+        overflow_error(self.spot_price_q64(supply)?.shr(64).to_u128())
+    }
+
+    /// B / T in Q64.64: pump.fun-scale pools price tokens far below one
+    /// base unit, which a whole-unit quotient would truncate to zero
+    fn spot_price_q64(&self, supply: u128) -> Result<U256> {
+        let (price, _) = U256::mul_u128(self.base_reserve(supply, false)?, ONE)
+            .div_rem_u128(self.token_reserve(supply)?)
+            .ok_or_else(|| anyhow!("Division by zero"))?;
+        Ok(price)
+    }
+
+    /// k / T1 - k / T0 = (k / T0) * n / T1
+    fn cost_to_buy(&self, supply: u128, amount: u128) -> Result<u128> {
+        let end = overflow_error(supply.checked_add(amount))?;
+        fixed_point::mul_div_up(self.base_reserve(supply, true)?, amount, self.token_reserve(end)?)
+    }
+
+    fn proceeds_from_sell(&self, supply: u128, amount: u128) -> Result<u128> {
+        let start = overflow_error(supply.checked_sub(amount))?;
+        fixed_point::mul_div(self.base_reserve(start, false)?, amount, self.token_reserve(supply)?)
+    }
+
+    /// Closed-form inverse: n = T * b / (B + b)
+    ///
+    /// `cost_to_buy` is ceil(B * n / (T - n)) with B rounded up, which is at
+    /// most b exactly when n <= T * b / (B + b), so the floor is exact.
+    fn estimate_tokens(&self, supply: u128, base_amount: u128, _max_amount: u128) -> Result<u128> {
+        let token_reserve = self.token_reserve(supply)?;
+        let base_reserve = self.base_reserve(supply, true)?;
+        let denominator = overflow_error(base_reserve.checked_add(base_amount))?;
+        fixed_point::mul_div(token_reserve, base_amount, denominator)
+    }
+}
+
+/// Bancor constant-reserve-ratio pricing
+///
+/// With S = virtual_supply + supply, the reserve is
+/// R(S) = R0 * (S / S0)^(1 / w) and the price is R / (w * S), so the reserve
+/// always equals w times market cap. Buying n tokens costs R(S + n) - R(S)
+/// (the purchase formula solved for the deposit) and selling them returns
+/// R(S) - R(S - n) (the sale formula).
+pub struct ReserveRatioCurve<'a> {
+    params: &'a CurveParams,
+}
+
+impl ReserveRatioCurve<'_> {
+    fn total_supply(&self, supply: u128) -> Result<u128> {
+        overflow_error(self.params.virtual_supply.checked_add(supply))
+    }
+
+    /// R0 * (S / S0)^(1 / w) in Q.64, kept in 256 bits
+    fn scaled_reserve(&self, supply: u128) -> Result<U256> {
+        let growth = fixed_point::from_ratio(self.total_supply(supply)?, self.params.virtual_supply)?;
+        let (exponent, _) = U256::mul_u128(fixed_point::log2(growth)?, CONNECTOR_WEIGHT_PPM)
+            .div_rem_u128(self.params.connector_weight)
+            .ok_or_else(|| anyhow!("Invalid connector weight"))?;
+
+        fixed_point::scaled_exp2(self.params.virtual_base_reserve, exponent)
+            .map_err(|_| anyhow!("Reserve overflow at supply {}", supply))
+    }
+
+    fn reserve_delta(&self, from_supply: u128, to_supply: u128, round_up: bool) -> Result<u128> {
+        let scaled_diff = overflow_error(
+            self.scaled_reserve(to_supply)?
+                .checked_sub(self.scaled_reserve(from_supply)?),
+        )?;
+        let delta = overflow_error(scaled_diff.shr(64).to_u128())?;
+
+        if round_up && scaled_diff.lo & fixed_point::FRACTION_MASK > 0 {
+            overflow_error(delta.checked_add(1))
+        } else {
+            Ok(delta)
+        }
+    }
+}
+
+impl BondingCurveModel for ReserveRatioCurve<'_> {
+    fn spot_price(&self, supply: u128) -> Result<u128> {
+        let reserve = overflow_error(self.scaled_reserve(supply)?.shr(64).to_u128())?;
+        let market_cap = fixed_point::mul_div(reserve, CONNECTOR_WEIGHT_PPM, self.params.connector_weight)?;
+        Ok(market_cap / self.total_supply(supply)?)
+    }
+
+    fn cost_to_buy(&self, supply: u128, amount: u128) -> Result<u128> {
+        let end = overflow_error(supply.checked_add(amount))?;
+        self.reserve_delta(supply, end, true)
+    }
+
+    /// Purchase formula: S' = S0 * ((R + b) / R0)^w
+    fn estimate_tokens(&self, supply: u128, base_amount: u128, _max_amount: u128) -> Result<u128> {
+        let target = overflow_error(
+            self.scaled_reserve(supply)?
+                .checked_add(U256::mul_u128(base_amount, ONE)),
+        )?;
+        let growth = fixed_point::ratio_q64(target, U256::mul_u128(self.params.virtual_base_reserve, ONE))?;
+        let exponent = fixed_point::mul_div(
+            fixed_point::log2(growth)?,
+            self.params.connector_weight,
+            CONNECTOR_WEIGHT_PPM,
+        )?;
+
+        let new_supply = overflow_error(
+            fixed_point::scaled_exp2(self.params.virtual_supply, U256::from_u128(exponent))?
+                .shr(64)
+                .to_u128(),
+        )?;
+        Ok(new_supply.saturating_sub(self.total_supply(supply)?))
+    }
+
+    fn proceeds_from_sell(&self, supply: u128, amount: u128) -> Result<u128> {
+        let start = overflow_error(supply.checked_sub(amount))?;
+        self.reserve_delta(start, supply, false)
+    }
+}
@@ -4,7 +4,7 @@
 //! It uses the cellpack pattern for efficient deployments and maintains
 //! a registry of all created tokens.
 
-use alkanes_runtime::storage::StoragePointer;
+use crate::journal::JournaledPointer;
 use alkanes_runtime::{println, runtime::AlkaneResponder, stdout};
 use alkanes_support::context::Context;
 use alkanes_support::id::AlkaneId;
@@ -16,7 +16,14 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::fmt::Write;
 
-use crate::{BaseToken, CurveParams};
+use crate::bonding_curve::{SellSpread, SpreadDestination};
+use crate::fees::FeeSchedule;
+use crate::trade::TradeLimits;
+use crate::graduation::{EmergencyGraduation, GraduationCriteria, PriceContinuity, Tokenomics};
+use crate::constants::{DEFAULT_GROWTH_STEPS, DEFAULT_LP_RESERVE_BPS};
+use crate::amm_adapter::AmmBackend;
+use crate::amm_integration::LPDistributionStrategy;
+use crate::{curve_models, BaseToken, CurveParams, CurveType};
 
 /// Token launch parameters provided by users
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,12 +37,78 @@ pub struct TokenLaunchParams {
     // Economics (with defaults)
     pub base_price: Option<u128>,           // Starting price (default: 4000 sats)
     pub growth_rate: Option<u128>,          // Growth rate in basis points (default: 150 = 1.5%)
+    #[serde(default)]
+    pub growth_steps: Option<u128>,         // Exponential compoundings across the curve allocation (default: DEFAULT_GROWTH_STEPS)
     pub graduation_threshold: Option<u128>,  // Market cap for graduation (default: $69k)
-    pub max_supply: Option<u128>,           // Maximum supply (default: 1B)
+    pub max_supply: Option<u128>,           // Maximum supply, curve sale and LP reserve (default: 1B)
+    #[serde(default)]
+    pub lp_reserve_bps: Option<u128>,       // Share of max supply minted at graduation to seed the pool (default: DEFAULT_LP_RESERVE_BPS)
+    
+    // Curve shape
+    #[serde(default)]
+    pub curve_type: CurveType,              // Pricing model (default: exponential)
+    #[serde(default)]
+    pub max_price: Option<u128>,            // Price ceiling for polynomial and sigmoid curves
+    #[serde(default)]
+    pub midpoint_supply: Option<u128>,      // Sigmoid inflection point (default: half the curve allocation)
+    #[serde(default)]
+    pub breakpoints: Vec<(u128, u128)>,     // (supply, price) points for piecewise-linear curves
+    #[serde(default)]
+    pub virtual_base_reserve: Option<u128>, // Virtual base reserve for constant-product curves
+    #[serde(default)]
+    pub virtual_token_reserve: Option<u128>, // Virtual token reserve (default: 5/4 of the curve allocation)
+    #[serde(default)]
+    pub connector_weight: Option<u128>,     // Reserve ratio in ppm (default: 500000)
+    #[serde(default)]
+    pub virtual_supply: Option<u128>,       // Initial virtual supply for reserve-ratio curves
+    
+    // Trading fees (shares are of the fee, in basis points)
+    #[serde(default)]
+    pub trading_fee_bps: Option<u128>,      // Fee per trade (default: TRADING_FEE_BPS)
+    #[serde(default)]
+    pub protocol_fee_share_bps: Option<u128>, // Protocol treasury share (default: 5000)
+    #[serde(default)]
+    pub creator_fee_share_bps: Option<u128>, // Creator share (default: 5000)
+    #[serde(default)]
+    pub referrer_fee_share_bps: Option<u128>, // Referrer share (default: 0)
+    #[serde(default)]
+    pub referrer: Option<String>,           // Referrer fee recipient as "block:tx"
+    
+    // Sell spread
+    #[serde(default)]
+    pub sell_spread_bps: Option<u128>,      // Spread at launch (default: 1%, 0 for reserve-ratio)
+    #[serde(default)]
+    pub sell_spread_final_bps: Option<u128>, // Spread after the decay window (default: no decay)
+    #[serde(default)]
+    pub sell_spread_decay_blocks: Option<u64>, // Blocks over which the spread decays
+    #[serde(default)]
+    pub sell_spread_destination: SpreadDestination, // Reserves, burn or fees (default: reserves)
+    
+    // Per-trade limits
+    #[serde(default)]
+    pub min_buy_amount: Option<u128>,       // Smallest buy in base (default: MIN_BUY_AMOUNT)
+    #[serde(default)]
+    pub max_buy_bps: Option<u128>,          // Largest buy, of remaining supply (default: MAX_BUY_PERCENTAGE)
+    #[serde(default)]
+    pub max_sell_bps: Option<u128>,         // Largest sell, of circulating supply (default: MAX_SELL_PERCENTAGE)
+    #[serde(default)]
+    pub max_slippage_bps: Option<u128>,     // Loosest slippage bound (default: MAX_SLIPPAGE_BPS)
+    
+    // Graduation rules
+    #[serde(default)]
+    pub graduation_criteria: Option<GraduationCriteria>, // Conditions and AND/OR (default: threshold rule; 0 targets are left out)
+    #[serde(default)]
+    pub emergency_graduation_delay_blocks: Option<u64>, // Blocks before forced graduation (default: ~30 days, 0 = never)
+    #[serde(default)]
+    pub emergency_graduation_min_reserves: Option<u128>, // Reserves floor for forced graduation (default: 1 BUSD)
+    #[serde(default)]
+    pub graduation_price_tolerance_bps: Option<u128>, // Pool's allowed gap from the final curve price (default: 1%)
+    #[serde(default)]
+    pub amm_backend: Option<AmmBackend>,    // AMM to graduate to (default: Oyl mainnet factory)
     
     // Platform settings
     pub base_token: BaseToken,              // BUSD or frBTC
-    pub lp_distribution_strategy: u8,        // 0=burn all, 1=distribute to holders, 2=creator allocation
+    pub lp_distribution_strategy: u8,        // 0=burn all (only one supported), 1=holders, 2=creator, 3=DAO
 }
 
 impl Default for TokenLaunchParams {
@@ -46,15 +119,66 @@ impl Default for TokenLaunchParams {
             symbol: 0,
             image_data: Vec::new(),
             base_price: Some(4_000_000),           // 0.04 BUSD ($5k market cap at 125M supply)
-            growth_rate: Some(150),                // 1.5% per token
+            growth_rate: Some(150),                // 1.5% per growth step
+            growth_steps: None,
             graduation_threshold: Some(6_900_000_000), // $69k in BUSD sats
             max_supply: Some(1_000_000_000),       // 1 billion tokens
+            lp_reserve_bps: None,
+            curve_type: CurveType::Exponential,
+            max_price: None,
+            midpoint_supply: None,
+            breakpoints: Vec::new(),
+            virtual_base_reserve: None,
+            virtual_token_reserve: None,
+            connector_weight: None,
+            virtual_supply: None,
+            trading_fee_bps: None,
+            protocol_fee_share_bps: None,
+            creator_fee_share_bps: None,
+            referrer_fee_share_bps: None,
+            referrer: None,
+            sell_spread_bps: None,
+            sell_spread_final_bps: None,
+            sell_spread_decay_blocks: None,
+            sell_spread_destination: SpreadDestination::Reserves,
+            min_buy_amount: None,
+            max_buy_bps: None,
+            max_sell_bps: None,
+            max_slippage_bps: None,
+            graduation_criteria: None,
+            emergency_graduation_delay_blocks: None,
+            emergency_graduation_min_reserves: None,
+            graduation_price_tolerance_bps: None,
+            amm_backend: None,
             base_token: BaseToken::BUSD,
             lp_distribution_strategy: 0,           // Burn all LP by default
         }
     }
 }
 
+impl TokenLaunchParams {
+    /// Referrer fee recipient parsed from its "block:tx" form
+    pub fn referrer_id(&self) -> Result<Option<AlkaneId>> {
+        match &self.referrer {
+            None => Ok(None),
+            Some(referrer) => {
+                let parts: Vec<&str> = referrer.split(':').collect();
+                if parts.len() != 2 {
+                    return Err(anyhow!("Invalid referrer format, expected block:tx"));
+                }
+                let block = parts[0].parse::<u128>()
+                    .map_err(|_| anyhow!("Invalid referrer block number"))?;
+                let tx = parts[1].parse::<u128>()
+                    .map_err(|_| anyhow!("Invalid referrer tx number"))?;
+                if block == 0 && tx == 0 {
+                    return Err(anyhow!("Referrer 0:0 means no referrer; leave it unset instead"));
+                }
+                Ok(Some(AlkaneId { block, tx }))
+            }
+        }
+    }
+}
+
 /// Token registry entry with serializable AlkaneId
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenInfo {
@@ -141,25 +265,25 @@ pub struct BondingCurveFactory;
 
 impl BondingCurveFactory {
     /// Storage pointer for token count
-    fn token_count_pointer() -> StoragePointer {
-        StoragePointer::from_keyword("/factory/token_count")
+    fn token_count_pointer() -> JournaledPointer {
+        JournaledPointer::from_keyword("/factory/token_count")
     }
     
     /// Storage pointer for token registry
-    fn token_registry_pointer(index: u128) -> StoragePointer {
-        StoragePointer::from_keyword(&format!("/factory/tokens/{}", index))
+    fn token_registry_pointer(index: u128) -> JournaledPointer {
+        JournaledPointer::from_keyword(&format!("/factory/tokens/{}", index))
     }
     
     /// Storage pointer for token lookup by ID
-    fn token_lookup_pointer(token_id: &AlkaneId) -> StoragePointer {
+    fn token_lookup_pointer(token_id: &AlkaneId) -> JournaledPointer {
         let key = format!("/factory/lookup/{}:{}", token_id.block, token_id.tx);
-        StoragePointer::from_keyword(&key)
+        JournaledPointer::from_keyword(&key)
     }
     
     /// Storage pointer for creator's tokens
-    fn creator_tokens_pointer(creator: &AlkaneId) -> StoragePointer {
+    fn creator_tokens_pointer(creator: &AlkaneId) -> JournaledPointer {
         let key = format!("/factory/creator/{}:{}", creator.block, creator.tx);
-        StoragePointer::from_keyword(&key)
+        JournaledPointer::from_keyword(&key)
     }
     
     /// Get factory fee (default: 0.001 BTC equivalent)
@@ -180,17 +304,46 @@ impl BondingCurveFactory {
     }
     
     /// Storage pointer for factory fees
-    pub fn factory_fee_pointer() -> StoragePointer {
-        StoragePointer::from_keyword("/factory/fee")
+    pub fn factory_fee_pointer() -> JournaledPointer {
+        JournaledPointer::from_keyword("/factory/fee")
+    }
+    
+    /// Storage pointer for the protocol fee treasury
+    fn treasury_pointer() -> JournaledPointer {
+        JournaledPointer::from_keyword("/factory/treasury")
+    }
+    
+    /// Treasury launched curves register as their protocol fee recipient
+    pub fn get_treasury() -> Option<AlkaneId> {
+        let data = Self::treasury_pointer().get();
+        if data.len() < 32 {
+            return None;
+        }
+        Some(AlkaneId {
+            block: u128::from_le_bytes(data[..16].try_into().ok()?),
+            tx: u128::from_le_bytes(data[16..32].try_into().ok()?),
+        })
+    }
+    
+    /// Set the protocol fee treasury (admin only)
+    pub fn set_treasury(treasury: &AlkaneId) -> Result<()> {
+        if *treasury == (AlkaneId { block: 0, tx: 0 }) {
+            return Err(anyhow!("Treasury cannot be 0:0"));
+        }
+        let mut data = Vec::with_capacity(32);
+        data.extend_from_slice(&treasury.block.to_le_bytes());
+        data.extend_from_slice(&treasury.tx.to_le_bytes());
+        Self::treasury_pointer().set(Arc::new(data));
+        Ok(())
     }
     
     /// Storage pointer for collected fees
-    fn collected_fees_pointer(base_token: &BaseToken) -> StoragePointer {
+    fn collected_fees_pointer(base_token: &BaseToken) -> JournaledPointer {
         let key = match base_token {
             BaseToken::BUSD => "/factory/fees/busd",
             BaseToken::FrBtc => "/factory/fees/frbtc",
         };
-        StoragePointer::from_keyword(key)
+        JournaledPointer::from_keyword(key)
     }
     
     /// Get current token count
@@ -220,13 +373,17 @@ impl BondingCurveFactory {
         // Check factory fee payment
         Self::verify_fee_payment(context, &params.base_token)?;
         
+        // The factory never claims the curve's protocol fees itself
+        let treasury = Self::get_treasury()
+            .ok_or_else(|| anyhow!("Factory has no treasury to receive protocol fees"))?;
+        
         // Generate token ID (using cellpack pattern)
         let token_index = Self::increment_token_count()?;
         let token_id = Self::generate_token_id(token_index);
         
         // Deploy bonding curve contract instance
         let curve_params = Self::params_to_curve_params(&params);
-        Self::deploy_bonding_curve(&token_id, &curve_params, &params)?;
+        Self::deploy_bonding_curve(&token_id, &curve_params, &params, &treasury)?;
         
         // Store token info in registry
         let token_info = TokenInfo::new(
@@ -277,8 +434,10 @@ impl BondingCurveFactory {
             return Err(anyhow!("Base price must be between 0.00001 and 10 BUSD"));
         }
         
+        // Growth rate only shapes exponential and sigmoid curves
         let growth_rate = params.growth_rate.unwrap_or(150);
-        if growth_rate < 10 || growth_rate > 1000 {
+        let uses_growth_rate = matches!(params.curve_type, CurveType::Exponential | CurveType::Sigmoid);
+        if uses_growth_rate && (growth_rate < 10 || growth_rate > 1000) {
             return Err(anyhow!("Growth rate must be between 0.1% and 10%"));
         }
         
@@ -286,8 +445,23 @@ impl BondingCurveFactory {
         if max_supply < 1_000_000 || max_supply > 100_000_000_000 {
             return Err(anyhow!("Max supply must be between 1M and 100B"));
         }
-        
-        Ok(())
+
+        LPDistributionStrategy::from_u128(params.lp_distribution_strategy.into())?.ensure_supported()?;
+
+        params.referrer_id()?;
+
+        Tokenomics::split_supply(max_supply, params.lp_reserve_bps.unwrap_or(DEFAULT_LP_RESERVE_BPS))?;
+        
+        // Shape-specific checks (price ceiling, breakpoints, ...)
+        let curve_params = Self::params_to_curve_params(params);
+        curve_models::validate(&curve_params)?;
+        curve_params.fees.validate()?;
+        curve_params.sell_spread.validate()?;
+        curve_params.limits.validate()?;
+        curve_params.criteria().validate()?;
+        curve_params.emergency_graduation.validate()?;
+        curve_params.price_continuity.validate()?;
+        curve_params.amm_backend.validate()
     }
     
     /// Verify factory fee payment
@@ -336,12 +510,66 @@ impl BondingCurveFactory {
     
     /// Convert launch params to curve params
     fn params_to_curve_params(params: &TokenLaunchParams) -> CurveParams {
+        // Split max supply into the curve sale and the pool's reserve
+        let total_supply = params.max_supply.unwrap_or(1_000_000_000);
+        let (max_supply, lp_reserve_supply) =
+            Tokenomics::split_supply(total_supply, params.lp_reserve_bps.unwrap_or(DEFAULT_LP_RESERVE_BPS))
+                .unwrap_or((total_supply, 0));
+        let defaults = FeeSchedule::default();
+        let limits = TradeLimits::default();
+        let emergency = EmergencyGraduation::default();
+        let continuity = PriceContinuity::default();
+
+        // Reserve-ratio curves pay the exact sale formula unless asked otherwise
+        let default_spread = match params.curve_type {
+            CurveType::ReserveRatio => 0,
+            _ => SellSpread::default().initial_bps,
+        };
+        let sell_spread_bps = params.sell_spread_bps.unwrap_or(default_spread);
         CurveParams {
             base_price: params.base_price.unwrap_or(4_000_000),
             growth_rate: params.growth_rate.unwrap_or(150),
+            growth_steps: params.growth_steps.unwrap_or(DEFAULT_GROWTH_STEPS),
             graduation_threshold: params.graduation_threshold.unwrap_or(6_900_000_000),
             base_token: params.base_token,
-            max_supply: params.max_supply.unwrap_or(1_000_000_000),
+            max_supply,
+            curve_type: params.curve_type,
+            max_price: params.max_price.unwrap_or(0),
+            midpoint_supply: params.midpoint_supply.unwrap_or(max_supply / 2),
+            breakpoints: params.breakpoints.clone(),
+            virtual_base_reserve: params.virtual_base_reserve.unwrap_or(0),
+            virtual_token_reserve: params.virtual_token_reserve.unwrap_or(max_supply / 4 * 5),
+            connector_weight: params.connector_weight.unwrap_or(500_000),
+            virtual_supply: params.virtual_supply.unwrap_or(0),
+            fees: FeeSchedule {
+                trading_fee_bps: params.trading_fee_bps.unwrap_or(defaults.trading_fee_bps),
+                protocol_share_bps: params.protocol_fee_share_bps.unwrap_or(defaults.protocol_share_bps),
+                creator_share_bps: params.creator_fee_share_bps.unwrap_or(defaults.creator_share_bps),
+                referrer_share_bps: params.referrer_fee_share_bps.unwrap_or(defaults.referrer_share_bps),
+            },
+            sell_spread: SellSpread {
+                initial_bps: sell_spread_bps,
+                final_bps: params.sell_spread_final_bps.unwrap_or(sell_spread_bps),
+                decay_blocks: params.sell_spread_decay_blocks.unwrap_or(0),
+                destination: params.sell_spread_destination,
+            },
+            limits: TradeLimits {
+                min_buy_amount: params.min_buy_amount.unwrap_or(limits.min_buy_amount),
+                max_buy_bps: params.max_buy_bps.unwrap_or(limits.max_buy_bps),
+                max_sell_bps: params.max_sell_bps.unwrap_or(limits.max_sell_bps),
+                max_slippage_bps: params.max_slippage_bps.unwrap_or(limits.max_slippage_bps),
+            },
+            graduation_criteria: params.graduation_criteria.clone()
+                .map(|criteria| GraduationCriteria::new(criteria.mode, criteria.conditions)),
+            emergency_graduation: EmergencyGraduation {
+                delay_blocks: params.emergency_graduation_delay_blocks.unwrap_or(emergency.delay_blocks),
+                min_reserves: params.emergency_graduation_min_reserves.unwrap_or(emergency.min_reserves),
+            },
+            price_continuity: PriceContinuity {
+                tolerance_bps: params.graduation_price_tolerance_bps.unwrap_or(continuity.tolerance_bps),
+            },
+            lp_reserve_supply,
+            amm_backend: params.amm_backend.unwrap_or_default(),
         }
     }
     
@@ -350,6 +578,7 @@ impl BondingCurveFactory {
         token_id: &AlkaneId,
         curve_params: &CurveParams,
         launch_params: &TokenLaunchParams,
+        treasury: &AlkaneId,
     ) -> Result<()> {
         // In a real implementation, this would:
         // 1. Use cellpack pattern to deploy lightweight instance
@@ -358,14 +587,17 @@ impl BondingCurveFactory {
         // 4. Return deployed address
         
         // For now, we simulate by storing the parameters
+        let referrer = launch_params.referrer_id()?;
         let deployment_key = format!("/deployed/{}/{}", token_id.block, token_id.tx);
-        let mut pointer = StoragePointer::from_keyword(&deployment_key);
+        let mut pointer = JournaledPointer::from_keyword(&deployment_key);
         
         let deployment_data = serde_json::json!({
             "curve_params": curve_params,
             "name": format!("{}{}", crate::trim(launch_params.name_part1), crate::trim(launch_params.name_part2)),
             "symbol": crate::trim(launch_params.symbol),
             "lp_strategy": launch_params.lp_distribution_strategy,
+            "referrer": referrer.map(|id| format!("{}:{}", id.block, id.tx)),
+            "treasury": format!("{}:{}", treasury.block, treasury.tx),
         });
         
         pointer.set(Arc::new(
@@ -0,0 +1,323 @@
+//! Trading Fee Engine
+//!
+//! Every curve trade pays `FeeSchedule::trading_fee_bps` of its curve amount
+//! (the cost of a buy or the gross payout of a sell). The fee is split between
+//! the protocol treasury, the token creator and an optional referrer. Each
+//! share accrues in its own storage bucket instead of `base_reserves`, so the
+//! reserves backing the curve stay exact. Recipients withdraw their bucket
+//! with `ClaimFees`.
+
+use crate::constants::TRADING_FEE_BPS;
+use crate::fixed_point::{self, BPS};
+use crate::journal::JournaledPointer;
+use alkanes_support::context::Context;
+use alkanes_support::id::AlkaneId;
+use alkanes_support::parcel::AlkaneTransfer;
+use alkanes_support::response::CallResponse;
+use alkanes_support::utils::overflow_error;
+use anyhow::{anyhow, Result};
+use metashrew_support::index_pointer::KeyValuePointer;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Highest trading fee a curve can launch with (10%)
+pub const MAX_TRADING_FEE_BPS: u128 = 1_000;
+
+/// Parties that receive a share of trading fees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeRecipient {
+    Protocol,
+    Creator,
+    Referrer,
+}
+
+impl FeeRecipient {
+    /// Decode the opcode representation (0 = protocol, 1 = creator, 2 = referrer)
+    pub fn from_u128(value: u128) -> Result<Self> {
+        match value {
+            0 => Ok(FeeRecipient::Protocol),
+            1 => Ok(FeeRecipient::Creator),
+            2 => Ok(FeeRecipient::Referrer),
+            _ => Err(anyhow!("Invalid fee recipient: {}", value)),
+        }
+    }
+
+    fn key(&self) -> &'static str {
+        match self {
+            FeeRecipient::Protocol => "protocol",
+            FeeRecipient::Creator => "creator",
+            FeeRecipient::Referrer => "referrer",
+        }
+    }
+}
+
+/// Fee rate and split, fixed at launch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    pub trading_fee_bps: u128,    // Fee charged on each trade
+    pub protocol_share_bps: u128, // Share of the fee for the protocol treasury
+    pub creator_share_bps: u128,  // Share of the fee for the token creator
+    pub referrer_share_bps: u128, // Share of the fee for the referrer (protocol if none)
+}
+
+impl Default for FeeSchedule {
+    fn default() -> Self {
+        Self {
+            trading_fee_bps: TRADING_FEE_BPS,
+            protocol_share_bps: 5_000,
+            creator_share_bps: 5_000,
+            referrer_share_bps: 0,
+        }
+    }
+}
+
+/// One fee broken down by recipient
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeSplit {
+    pub protocol: u128,
+    pub creator: u128,
+    pub referrer: u128,
+}
+
+impl FeeSplit {
+    pub fn total(&self) -> u128 {
+        self.protocol + self.creator + self.referrer
+    }
+}
+
+impl FeeSchedule {
+    /// Check the rate is within bounds and the shares cover the whole fee
+    pub fn validate(&self) -> Result<()> {
+        if self.trading_fee_bps > MAX_TRADING_FEE_BPS {
+            return Err(anyhow!("Trading fee cannot exceed {} bps", MAX_TRADING_FEE_BPS));
+        }
+
+        let shares = self
+            .protocol_share_bps
+            .checked_add(self.creator_share_bps)
+            .and_then(|sum| sum.checked_add(self.referrer_share_bps));
+        if shares != Some(BPS) {
+            return Err(anyhow!("Fee shares must add up to {} bps", BPS));
+        }
+
+        Ok(())
+    }
+
+    /// Fee on `amount`, rounded up
+    pub fn fee_on(&self, amount: u128) -> Result<u128> {
+        fixed_point::mul_div_up(amount, self.trading_fee_bps, BPS)
+    }
+
+    /// Largest amount whose cost plus fee fits in `total`
+    ///
+    /// amount + ceil(amount * f) <= total exactly when
+    /// amount <= total * BPS / (BPS + f).
+    pub fn budget_before_fee(&self, total: u128) -> Result<u128> {
+        fixed_point::mul_div(total, BPS, BPS + self.trading_fee_bps)
+    }
+
+    /// Smallest gross amount that still leaves `net` after its fee
+    ///
+    /// gross - ceil(gross * f) >= net exactly when
+    /// gross >= net * BPS / (BPS - f).
+    pub fn gross_for_net(&self, net: u128) -> Result<u128> {
+        fixed_point::mul_div_up(net, BPS, BPS - self.trading_fee_bps)
+    }
+
+    /// Divide `fee` by share; rounding dust and an absent referrer's share go
+    /// to the protocol
+    pub fn split(&self, fee: u128, has_referrer: bool) -> Result<FeeSplit> {
+        let creator = fixed_point::mul_div(fee, self.creator_share_bps, BPS)?;
+        let referrer = if has_referrer {
+            fixed_point::mul_div(fee, self.referrer_share_bps, BPS)?
+        } else {
+            0
+        };
+
+        Ok(FeeSplit {
+            protocol: fee - creator - referrer,
+            creator,
+            referrer,
+        })
+    }
+}
+
+/// Storage and claims for accrued trading fees
+pub struct FeeEngine;
+
+impl FeeEngine {
+    fn accrued_pointer(recipient: FeeRecipient) -> JournaledPointer {
+        JournaledPointer::from_keyword(&format!("/fees/accrued/{}", recipient.key()))
+    }
+
+    fn recipient_pointer(recipient: FeeRecipient) -> JournaledPointer {
+        JournaledPointer::from_keyword(&format!("/fees/recipient/{}", recipient.key()))
+    }
+
+    /// Fees accrued to `recipient` and not yet claimed
+    pub fn get_accrued(recipient: FeeRecipient) -> u128 {
+        Self::accrued_pointer(recipient).get_value::<u128>()
+    }
+
+    fn add_accrued(recipient: FeeRecipient, amount: u128) -> Result<()> {
+        let mut pointer = Self::accrued_pointer(recipient);
+        let accrued = overflow_error(pointer.get_value::<u128>().checked_add(amount))?;
+        pointer.set_value(accrued);
+        Ok(())
+    }
+
+    /// Whether `id` is the 0:0 caller every wallet-originated call arrives as
+    pub fn is_wallet_caller(id: &AlkaneId) -> bool {
+        id.block == 0 && id.tx == 0
+    }
+
+    /// Register who may claim `recipient`'s bucket
+    ///
+    /// The claimant must be a contract: registering 0:0 would let any
+    /// wallet claim the bucket.
+    pub fn set_recipient(recipient: FeeRecipient, id: &AlkaneId) -> Result<()> {
+        if Self::is_wallet_caller(id) {
+            return Err(anyhow!("The {} fee recipient must be a contract, not 0:0", recipient.key()));
+        }
+
+        let mut data = Vec::with_capacity(32);
+        data.extend_from_slice(&id.block.to_le_bytes());
+        data.extend_from_slice(&id.tx.to_le_bytes());
+        Self::recipient_pointer(recipient).set(Arc::new(data));
+        Ok(())
+    }
+
+    /// Registered claimant for `recipient`'s bucket
+    pub fn get_recipient(recipient: FeeRecipient) -> Option<AlkaneId> {
+        let data = Self::recipient_pointer(recipient).get();
+        if data.len() < 32 {
+            return None;
+        }
+
+        let block = u128::from_le_bytes(data[0..16].try_into().ok()?);
+        let tx = u128::from_le_bytes(data[16..32].try_into().ok()?);
+        Some(AlkaneId { block, tx })
+    }
+
+    /// Whether a referrer was registered at launch
+    pub fn has_referrer() -> bool {
+        Self::get_recipient(FeeRecipient::Referrer).is_some()
+    }
+
+    /// Credit `amount` to `recipient`'s bucket alone
+    pub fn credit(recipient: FeeRecipient, amount: u128) -> Result<()> {
+        Self::add_accrued(recipient, amount)
+    }
+
+    /// Credit each recipient's bucket with its part of `split`
+    pub fn accrue(split: &FeeSplit) -> Result<()> {
+        Self::add_accrued(FeeRecipient::Protocol, split.protocol)?;
+        Self::add_accrued(FeeRecipient::Creator, split.creator)?;
+        Self::add_accrued(FeeRecipient::Referrer, split.referrer)
+    }
+
+    /// Credit `amount` itself to the recipients by their shares
+    pub fn route(schedule: &FeeSchedule, amount: u128) -> Result<FeeSplit> {
+        let split = schedule.split(amount, Self::has_referrer())?;
+        Self::accrue(&split)?;
+        Ok(split)
+    }
+
+    /// Pay out `recipient`'s bucket in `base_token`
+    ///
+    /// Only the registered claimant may call, so the payout always returns
+    /// to it. Alkanes sent with the call are forwarded back untouched.
+    pub fn claim(context: &Context, recipient: FeeRecipient, base_token: AlkaneId) -> Result<CallResponse> {
+        let claimant = Self::get_recipient(recipient)
+            .filter(|claimant| !Self::is_wallet_caller(claimant))
+            .ok_or_else(|| anyhow!("No claimant registered for {} fees", recipient.key()))?;
+
+        if context.caller != claimant {
+            return Err(anyhow!("Only the {} fee recipient can claim these fees", recipient.key()));
+        }
+
+        let amount = Self::get_accrued(recipient);
+        if amount == 0 {
+            return Err(anyhow!("No {} fees to claim", recipient.key()));
+        }
+        Self::accrued_pointer(recipient).set_value::<u128>(0);
+
+        let mut response = CallResponse::forward(&context.incoming_alkanes);
+        response.alkanes.0.push(AlkaneTransfer {
+            id: base_token,
+            value: amount,
+        });
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_schedule_uses_trading_fee() {
+        let schedule = FeeSchedule::default();
+        assert_eq!(schedule.trading_fee_bps, TRADING_FEE_BPS);
+        assert!(schedule.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validation() {
+        let too_high = FeeSchedule {
+            trading_fee_bps: MAX_TRADING_FEE_BPS + 1,
+            ..FeeSchedule::default()
+        };
+        assert!(too_high.validate().is_err());
+
+        let short = FeeSchedule {
+            protocol_share_bps: 4_000,
+            ..FeeSchedule::default()
+        };
+        assert!(short.validate().is_err());
+    }
+
+    #[test]
+    fn test_fee_rounds_against_trader() {
+        let schedule = FeeSchedule::default(); // 0.5%
+        assert_eq!(schedule.fee_on(1_000_000).unwrap(), 5_000);
+        assert_eq!(schedule.fee_on(1_001).unwrap(), 6);
+        assert_eq!(schedule.fee_on(0).unwrap(), 0);
+
+        // Cost plus fee never exceeds what the buyer sent
+        for total in [1u128, 999, 1_000_000, 123_456_789] {
+            let budget = schedule.budget_before_fee(total).unwrap();
+            assert!(budget + schedule.fee_on(budget).unwrap() <= total);
+            let over = budget + 1;
+            assert!(over + schedule.fee_on(over).unwrap() > total);
+        }
+
+        // A seller asking for a net amount always receives it
+        for net in [1u128, 999, 1_000_000, 123_456_789] {
+            let gross = schedule.gross_for_net(net).unwrap();
+            assert!(gross - schedule.fee_on(gross).unwrap() >= net);
+            let under = gross - 1;
+            assert!(under - schedule.fee_on(under).unwrap() < net);
+        }
+    }
+
+    #[test]
+    fn test_split_between_recipients() {
+        let schedule = FeeSchedule {
+            trading_fee_bps: 100,
+            protocol_share_bps: 5_000,
+            creator_share_bps: 3_000,
+            referrer_share_bps: 2_000,
+        };
+
+        let split = schedule.split(1_001, true).unwrap();
+        assert_eq!(split.creator, 300);
+        assert_eq!(split.referrer, 200);
+        assert_eq!(split.protocol, 501);
+        assert_eq!(split.total(), 1_001);
+
+        // Without a referrer its share goes to the protocol
+        let split = schedule.split(1_000, false).unwrap();
+        assert_eq!(split, FeeSplit { protocol: 700, creator: 300, referrer: 0 });
+    }
+}
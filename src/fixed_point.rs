@@ -0,0 +1,577 @@
+//! Deterministic Fixed-Point Math
+//!
+//! Integer-only arithmetic used by the bonding curve pricing code. Every
+//! operation is either exact or rounds down, so every indexer computes the
+//! same quote for the same inputs.
+//!
+//! This module provides:
+//! - `U256` for 128x128-bit intermediate products
+//! - Unsigned Q64.64 values stored in a `u128` (`ONE` = 1.0)
+//! - `log2`/`ln`/`exp2_frac`/`exp_neg`/`softplus` with bounded error
+//! - `WideFloat` for exponentiation by squaring without overflow
+
+use anyhow::{anyhow, Result};
+
+/// 1.0 in Q64.64
+pub const ONE: u128 = 1u128 << 64;
+
+/// ln(2) in Q64.64, rounded down
+pub const LN_2: u128 = 0xB172_17F7_D1CF_79AB;
+
+/// Mask selecting the fractional bits of a Q64.64 value
+pub const FRACTION_MASK: u128 = ONE - 1;
+
+/// Basis point denominator used by curve parameters
+pub const BPS: u128 = 10_000;
+
+/// Upper bound on loop iterations in `WideFloat::powi`: one per bit of a
+/// u128 exponent, each costing at most two wide multiplications
+pub const MAX_POW_ITERATIONS: u32 = 128;
+
+/// Unsigned 256-bit integer used for wide intermediates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct U256 {
+    pub hi: u128,
+    pub lo: u128,
+}
+
+impl U256 {
+    pub const ZERO: U256 = U256 { hi: 0, lo: 0 };
+
+    pub fn from_u128(value: u128) -> Self {
+        Self { hi: 0, lo: value }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.hi == 0 && self.lo == 0
+    }
+
+    /// Full 256-bit product of two u128 values
+    pub fn mul_u128(a: u128, b: u128) -> Self {
+        let mask = u64::MAX as u128;
+        let (a_hi, a_lo) = (a >> 64, a & mask);
+        let (b_hi, b_lo) = (b >> 64, b & mask);
+
+        let ll = a_lo * b_lo;
+        let lh = a_lo * b_hi;
+        let hl = a_hi * b_lo;
+        let hh = a_hi * b_hi;
+
+        // A carry out of the middle sum is worth 2^192, i.e. 2^64 in the high word
+        let (mid, mid_carry) = lh.overflowing_add(hl);
+        let (lo, lo_carry) = ll.overflowing_add(mid << 64);
+        let hi = hh + (mid >> 64) + ((mid_carry as u128) << 64) + lo_carry as u128;
+
+        Self { hi, lo }
+    }
+
+    /// Number of significant bits
+    pub fn bits(&self) -> u32 {
+        if self.hi != 0 {
+            256 - self.hi.leading_zeros()
+        } else {
+            128 - self.lo.leading_zeros()
+        }
+    }
+
+    pub fn checked_add(self, other: U256) -> Option<U256> {
+        let (lo, carry) = self.lo.overflowing_add(other.lo);
+        let hi = self.hi.checked_add(other.hi)?.checked_add(carry as u128)?;
+        Some(U256 { hi, lo })
+    }
+
+    pub fn checked_sub(self, other: U256) -> Option<U256> {
+        let (lo, borrow) = self.lo.overflowing_sub(other.lo);
+        let hi = self.hi.checked_sub(other.hi)?.checked_sub(borrow as u128)?;
+        Some(U256 { hi, lo })
+    }
+
+    /// Left shift, returning None if any set bit would be shifted out
+    pub fn checked_shl(self, shift: u32) -> Option<U256> {
+        if self.is_zero() || shift == 0 {
+            return Some(self);
+        }
+        if self.bits() + shift > 256 {
+            return None;
+        }
+        Some(match shift {
+            1..=127 => U256 {
+                hi: (self.hi << shift) | (self.lo >> (128 - shift)),
+                lo: self.lo << shift,
+            },
+            _ => U256 {
+                hi: self.lo << (shift - 128),
+                lo: 0,
+            },
+        })
+    }
+
+    /// Logical right shift (rounds down)
+    pub fn shr(self, shift: u32) -> U256 {
+        match shift {
+            0 => self,
+            1..=127 => U256 {
+                hi: self.hi >> shift,
+                lo: (self.lo >> shift) | (self.hi << (128 - shift)),
+            },
+            128..=255 => U256 {
+                hi: 0,
+                lo: self.hi >> (shift - 128),
+            },
+            _ => U256::ZERO,
+        }
+    }
+
+    /// Divide by a u128, returning (quotient, remainder)
+    pub fn div_rem_u128(self, divisor: u128) -> Option<(U256, u128)> {
+        if divisor == 0 {
+            return None;
+        }
+
+        let q_hi = self.hi / divisor;
+        let mut rem = self.hi % divisor;
+        let mut q_lo = 0u128;
+
+        // Restoring division over the low word; `rem < divisor` on entry to every step
+        for i in (0..128).rev() {
+            let carry = rem >> 127;
+            rem = (rem << 1) | ((self.lo >> i) & 1);
+            q_lo <<= 1;
+            if carry == 1 || rem >= divisor {
+                rem = rem.wrapping_sub(divisor);
+                q_lo |= 1;
+            }
+        }
+
+        Some((U256 { hi: q_hi, lo: q_lo }, rem))
+    }
+
+    pub fn to_u128(self) -> Option<u128> {
+        if self.hi == 0 {
+            Some(self.lo)
+        } else {
+            None
+        }
+    }
+
+    /// Largest `r` with `r * r <= self`, which always fits in a u128
+    ///
+    /// Set bit by bit from the top, one 256-bit square per result bit.
+    pub fn isqrt(self) -> u128 {
+        let mut root = 0u128;
+        for bit in (0..(self.bits() + 1) / 2).rev() {
+            let candidate = root | (1u128 << bit);
+            if U256::mul_u128(candidate, candidate) <= self {
+                root = candidate;
+            }
+        }
+        root
+    }
+}
+
+/// Unsigned floating value `mantissa * 2^exponent`
+///
+/// The mantissa is normalised so its top bit is set, giving 128 bits of
+/// precision at any magnitude. Used for repeated squaring, where a plain
+/// Q64.64 value would overflow after a handful of steps. All operations
+/// round down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WideFloat {
+    pub mantissa: u128,
+    pub exponent: i64,
+}
+
+impl WideFloat {
+    /// 1.0
+    pub const ONE: WideFloat = WideFloat {
+        mantissa: 1u128 << 127,
+        exponent: -127,
+    };
+
+    /// numerator / denominator with full 128-bit precision
+    pub fn from_ratio(numerator: u128, denominator: u128) -> Result<Self> {
+        if numerator == 0 {
+            return Err(anyhow!("WideFloat cannot represent zero"));
+        }
+
+        // Divide with 128 extra bits, then normalise the quotient to exactly 128 bits
+        let (quotient, _) = U256 { hi: numerator, lo: 0 }
+            .div_rem_u128(denominator)
+            .ok_or_else(|| anyhow!("Division by zero"))?;
+        let bits = quotient.bits() as i64;
+        let mantissa = if bits > 128 {
+            quotient.shr((bits - 128) as u32).lo
+        } else {
+            quotient.lo << (128 - bits)
+        };
+
+        Ok(Self {
+            mantissa,
+            exponent: bits - 128 - 128,
+        })
+    }
+
+    /// Product of two values, rounded down
+    pub fn mul(self, other: WideFloat) -> Result<Self> {
+        let product = U256::mul_u128(self.mantissa, other.mantissa);
+        let exponent = self
+            .exponent
+            .checked_add(other.exponent)
+            .ok_or_else(|| anyhow!("Fixed-point overflow"))?;
+
+        // Both mantissas are >= 2^127, so the product has 255 or 256 bits
+        let (mantissa, shift) = if product.bits() == 256 {
+            (product.hi, 128)
+        } else {
+            (product.shr(127).lo, 127)
+        };
+
+        Ok(Self {
+            mantissa,
+            exponent: exponent
+                .checked_add(shift)
+                .ok_or_else(|| anyhow!("Fixed-point overflow"))?,
+        })
+    }
+
+    /// self^power by exponentiation by squaring
+    ///
+    /// Runs at most `MAX_POW_ITERATIONS` iterations (one per bit of `power`),
+    /// so 1B takes 30. Each multiplication rounds down by less than 2^-127
+    /// relative, so rounding adds at most 2 * (log2(power) + 1) * 2^-127.
+    pub fn powi(self, power: u128) -> Result<Self> {
+        let mut result = Self::ONE;
+        let mut square = self;
+        let mut remaining = power;
+
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result = result.mul(square)?;
+            }
+            remaining >>= 1;
+            if remaining > 0 {
+                square = square.mul(square)?;
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// scale * self as a Q.64 value in 256 bits, or an error if it does not fit
+    pub fn scaled_q64(self, scale: u128) -> Result<U256> {
+        let product = U256::mul_u128(scale, self.mantissa);
+        let shift = self
+            .exponent
+            .checked_add(64)
+            .ok_or_else(|| anyhow!("Fixed-point overflow"))?;
+
+        if shift >= 0 {
+            u32::try_from(shift)
+                .ok()
+                .and_then(|shift| product.checked_shl(shift))
+                .ok_or_else(|| anyhow!("Fixed-point overflow"))
+        } else {
+            Ok(u32::try_from(-shift)
+                .map(|shift| product.shr(shift))
+                .unwrap_or(U256::ZERO))
+        }
+    }
+}
+
+/// floor(a * b / denominator) without intermediate overflow
+pub fn mul_div(a: u128, b: u128, denominator: u128) -> Result<u128> {
+    let (quotient, _) = U256::mul_u128(a, b)
+        .div_rem_u128(denominator)
+        .ok_or_else(|| anyhow!("Division by zero"))?;
+    quotient.to_u128().ok_or_else(|| anyhow!("Fixed-point overflow"))
+}
+
+/// ceil(a * b / denominator) without intermediate overflow
+pub fn mul_div_up(a: u128, b: u128, denominator: u128) -> Result<u128> {
+    let (quotient, remainder) = U256::mul_u128(a, b)
+        .div_rem_u128(denominator)
+        .ok_or_else(|| anyhow!("Division by zero"))?;
+    let quotient = quotient.to_u128().ok_or_else(|| anyhow!("Fixed-point overflow"))?;
+    if remainder > 0 {
+        quotient.checked_add(1).ok_or_else(|| anyhow!("Fixed-point overflow"))
+    } else {
+        Ok(quotient)
+    }
+}
+
+/// floor(sqrt(a * b)), exact for any u128 operands
+pub fn sqrt_mul(a: u128, b: u128) -> u128 {
+    U256::mul_u128(a, b).isqrt()
+}
+
+/// Q64.64 product, rounded down
+pub fn mul_q64(a: u128, b: u128) -> Result<u128> {
+    U256::mul_u128(a, b)
+        .shr(64)
+        .to_u128()
+        .ok_or_else(|| anyhow!("Fixed-point overflow"))
+}
+
+/// Q64.64 value of numerator / denominator, rounded down
+pub fn from_ratio(numerator: u128, denominator: u128) -> Result<u128> {
+    mul_div(numerator, ONE, denominator)
+}
+
+/// Q64.64 value of numerator / denominator for 256-bit operands, rounded down
+///
+/// Both operands are shifted until the denominator fits in 128 bits, which
+/// loses at most 2^-127 of relative precision.
+pub fn ratio_q64(numerator: U256, denominator: U256) -> Result<u128> {
+    let shift = denominator.bits().saturating_sub(128);
+    let divisor = denominator.shr(shift).lo;
+
+    numerator
+        .shr(shift)
+        .checked_shl(64)
+        .and_then(|scaled| scaled.div_rem_u128(divisor))
+        .and_then(|(quotient, _)| quotient.to_u128())
+        .ok_or_else(|| anyhow!("Fixed-point overflow"))
+}
+
+/// Base-2 logarithm of a Q64.64 value >= 1.0
+///
+/// Computed bit by bit through repeated squaring (64 iterations). The result
+/// is rounded down with an absolute error below 2^-62.
+pub fn log2(x: u128) -> Result<u128> {
+    if x < ONE {
+        return Err(anyhow!("log2 is only defined for arguments >= 1.0"));
+    }
+
+    let msb = 127 - x.leading_zeros();
+    let mut result = ((msb - 64) as u128) << 64;
+
+    // Normalise into [1, 2)
+    let mut y = x >> (msb - 64);
+    let mut bit = ONE >> 1;
+    while bit > 0 {
+        // y < 2, so y^2 < 4 and always fits after the shift
+        y = U256::mul_u128(y, y).shr(64).lo;
+        if y >= 2 * ONE {
+            y >>= 1;
+            result |= bit;
+        }
+        bit >>= 1;
+    }
+
+    Ok(result)
+}
+
+/// Natural logarithm of a Q64.64 value >= 1.0, rounded down
+pub fn ln(x: u128) -> Result<u128> {
+    mul_q64(log2(x)?, LN_2)
+}
+
+/// 2^frac for a purely fractional Q64.64 exponent, result in [1.0, 2.0)
+///
+/// Evaluates the Taylor series of e^(frac * ln 2). The argument is below 0.7,
+/// so the series is exhausted in at most 32 terms and the result is rounded
+/// down with an absolute error below 2^-59.
+pub fn exp2_frac(frac: u128) -> u128 {
+    let t = U256::mul_u128(frac & FRACTION_MASK, LN_2).shr(64).lo;
+
+    let mut sum = ONE;
+    let mut term = ONE;
+    let mut k = 1u128;
+    loop {
+        term = U256::mul_u128(term, t).shr(64).lo / k;
+        if term == 0 {
+            break;
+        }
+        sum += term;
+        k += 1;
+    }
+
+    sum
+}
+
+/// e^-x for any Q64.64 value x >= 0, result in [0, 1.0]
+///
+/// Rewritten as 2^-(x / ln 2) and evaluated with `exp2_frac`; the result is
+/// rounded down with an absolute error below 2^-58. From x = 128 ln 2 the
+/// result is below the Q64.64 resolution and returns 0 without dividing,
+/// which would overflow for x of about 0.69 * 2^64 or more.
+pub fn exp_neg(x: u128) -> Result<u128> {
+    if x >= 128 * LN_2 {
+        return Ok(0);
+    }
+
+    let exponent = mul_div(x, ONE, LN_2)?;
+    let whole = exponent >> 64;
+    let frac = exponent & FRACTION_MASK;
+
+    // 2^-frac = 2^(1 - frac) / 2
+    let mantissa = if frac == 0 {
+        ONE
+    } else {
+        exp2_frac(ONE - frac) >> 1
+    };
+
+    Ok(mantissa >> whole)
+}
+
+/// ln(1 + e^z) for a signed Q64.64 value z = sign * magnitude
+///
+/// Computed as max(z, 0) + ln(1 + e^-|z|), defined for every magnitude a
+/// u128 holds (|z| < 2^64): the tail is at most ln 2, and is exactly 0 once
+/// |z| reaches 128 ln 2, so adding it to a large magnitude cannot overflow.
+pub fn softplus(magnitude: u128, negative: bool) -> Result<u128> {
+    let tail = ln(ONE + exp_neg(magnitude)?)?;
+    if negative {
+        Ok(tail)
+    } else {
+        magnitude
+            .checked_add(tail)
+            .ok_or_else(|| anyhow!("Fixed-point overflow"))
+    }
+}
+
+/// 2^exponent for a Q.64 exponent held in a U256, scaled by `scale`
+///
+/// Returns scale * 2^exponent * 2^64 (i.e. a Q.64 value) as a U256, or an
+/// error if the result does not fit in 256 bits.
+pub fn scaled_exp2(scale: u128, exponent: U256) -> Result<U256> {
+    let whole = exponent
+        .shr(64)
+        .to_u128()
+        .filter(|whole| *whole < 256)
+        .ok_or_else(|| anyhow!("Fixed-point overflow"))?;
+    let mantissa = exp2_frac(exponent.lo & FRACTION_MASK);
+
+    U256::mul_u128(scale, mantissa)
+        .checked_shl(whole as u32)
+        .ok_or_else(|| anyhow!("Fixed-point overflow"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_f64(x: u128) -> f64 {
+        x as f64 / ONE as f64
+    }
+
+    #[test]
+    fn test_u256_mul_and_div_roundtrip() {
+        let a = u128::MAX - 12345;
+        let b = 987_654_321_987_654_321u128;
+        let product = U256::mul_u128(a, b);
+
+        let (quotient, remainder) = product.div_rem_u128(b).unwrap();
+        assert_eq!(quotient.to_u128(), Some(a));
+        assert_eq!(remainder, 0);
+
+        let max = U256::mul_u128(u128::MAX, u128::MAX);
+        assert_eq!(max.hi, u128::MAX - 1);
+        assert_eq!(max.lo, 1);
+    }
+
+    #[test]
+    fn test_isqrt_is_exact() {
+        assert_eq!(sqrt_mul(0, 5), 0);
+        assert_eq!(sqrt_mul(1, 1), 1);
+        assert_eq!(sqrt_mul(3, 5), 3);
+        assert_eq!(sqrt_mul(4, 4), 4);
+        assert_eq!(sqrt_mul(1_000_000, 4_000_000), 2_000_000);
+
+        // Products past u64 and u128 keep every bit
+        assert_eq!(sqrt_mul(1u128 << 70, 1u128 << 70), 1u128 << 70);
+        assert_eq!(sqrt_mul(u128::MAX, u128::MAX), u128::MAX);
+        let root = sqrt_mul(u128::MAX, 3);
+        assert!(U256::mul_u128(root, root) <= U256::mul_u128(u128::MAX, 3));
+        assert!(U256::mul_u128(root + 1, root + 1) > U256::mul_u128(u128::MAX, 3));
+    }
+
+    #[test]
+    fn test_mul_div_rounding() {
+        assert_eq!(mul_div(10, 10, 3).unwrap(), 33);
+        assert_eq!(mul_div_up(10, 10, 3).unwrap(), 34);
+        assert_eq!(mul_div_up(10, 9, 3).unwrap(), 30);
+        assert!(mul_div(u128::MAX, u128::MAX, 1).is_err());
+        assert!(mul_div(1, 1, 0).is_err());
+    }
+
+    #[test]
+    fn test_log2_and_ln() {
+        assert_eq!(log2(ONE).unwrap(), 0);
+        assert_eq!(log2(8 * ONE).unwrap(), 3 * ONE);
+
+        let x = from_ratio(10_150, 10_000).unwrap();
+        let expected = 1.015_f64.ln();
+        assert!((to_f64(ln(x).unwrap()) - expected).abs() < 1e-15);
+
+        assert!(log2(ONE - 1).is_err());
+    }
+
+    #[test]
+    fn test_exp2_frac() {
+        assert_eq!(exp2_frac(0), ONE);
+
+        let half = exp2_frac(ONE / 2);
+        assert!((to_f64(half) - 2f64.sqrt()).abs() < 1e-15);
+
+        let almost_one = exp2_frac(FRACTION_MASK);
+        assert!(almost_one < 2 * ONE);
+    }
+
+    #[test]
+    fn test_wide_float_powi() {
+        let growth = WideFloat::from_ratio(10_150, 10_000).unwrap();
+
+        let value = growth.powi(100).unwrap().scaled_q64(1_000).unwrap();
+        let expected = 1_000.0 * 1.015_f64.powi(100);
+        let actual = value.shr(64).to_u128().unwrap() as f64;
+        assert!((actual - expected).abs() < 1.0);
+
+        assert_eq!(growth.powi(0).unwrap(), WideFloat::ONE);
+
+        // 2^100 in Q.64 is exactly 2^164
+        let two = WideFloat::from_ratio(2, 1).unwrap();
+        let value = two.powi(100).unwrap().scaled_q64(1).unwrap();
+        assert_eq!(value, U256 { hi: 1u128 << 36, lo: 0 });
+    }
+
+    #[test]
+    fn test_wide_float_powi_large_exponent() {
+        // 1B units at 0.01 bps growth stays finite, and huge growth overflows cleanly
+        let growth = WideFloat::from_ratio(1_000_000_001, 1_000_000_000).unwrap();
+        let value = growth.powi(1_000_000_000).unwrap().scaled_q64(1_000_000).unwrap();
+        let expected = 1_000_000.0 * std::f64::consts::E;
+        let actual = value.shr(64).to_u128().unwrap() as f64;
+        assert!((actual - expected).abs() / expected < 1e-9);
+
+        let growth = WideFloat::from_ratio(2, 1).unwrap();
+        assert!(growth.powi(1_000_000_000).unwrap().scaled_q64(1).is_err());
+    }
+
+    #[test]
+    fn test_exp_neg_and_softplus() {
+        assert_eq!(exp_neg(0).unwrap(), ONE);
+        assert!((to_f64(exp_neg(ONE).unwrap()) - (-1f64).exp()).abs() < 1e-15);
+        assert_eq!(exp_neg(200 * ONE).unwrap(), 0);
+        assert_eq!(exp_neg(u128::MAX).unwrap(), 0);
+
+        let expected = (1.0 + 3f64.exp()).ln();
+        assert!((to_f64(softplus(3 * ONE, false).unwrap()) - expected).abs() < 1e-14);
+        let expected = (1.0 + (-3f64).exp()).ln();
+        assert!((to_f64(softplus(3 * ONE, true).unwrap()) - expected).abs() < 1e-14);
+
+        // The whole Q64.64 range, where e^-|z| is far below resolution
+        assert_eq!(softplus(u128::MAX, false).unwrap(), u128::MAX);
+        assert_eq!(softplus(u128::MAX, true).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_scaled_exp2_overflow() {
+        let exponent = U256::from_u128(300 * ONE);
+        assert!(scaled_exp2(1, exponent).is_err());
+
+        let exponent = U256::from_u128(10 * ONE);
+        let value = scaled_exp2(3, exponent).unwrap();
+        assert_eq!(value.shr(64).to_u128(), Some(3 * 1024));
+    }
+}
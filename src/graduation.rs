@@ -0,0 +1,572 @@
+//! Graduation Criteria
+//!
+//! A curve graduates when its `GraduationCriteria` hold: conditions on market
+//! cap, reserves, share of supply sold, age and buy count, chosen at launch and
+//! combined with AND (`All`) or OR (`Any`). Curves launched without criteria
+//! keep the original rule: market cap at the graduation threshold, or
+//! reserves at half of it.
+//!
+//! There is no holder-count condition. Bought tokens land in transaction
+//! outputs the curve never sees again, and every wallet calls it as the same
+//! 0:0 caller, so neither holders nor distinct buyers can be told apart.
+//! Filled buys stand in for them; `MIN_HOLDERS_FOR_GRADUATION` is the
+//! suggested target.
+//!
+//! `PriceContinuity` bounds how far the AMM pool may open from the curve's
+//! final price, and `Tokenomics` tracks the split of supply between the
+//! curve sale and the tokens reserved for the pool.
+
+use crate::bonding_curve::CurveCalculator;
+use crate::constants::{
+    EMERGENCY_GRADUATION_DELAY_BLOCKS, EMERGENCY_GRADUATION_MIN_RESERVES, GRADUATION_PRICE_TOLERANCE_BPS,
+};
+use crate::fixed_point::{self, BPS, U256};
+use crate::CurveParams;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// Most conditions a launch may combine
+pub const MAX_GRADUATION_CONDITIONS: usize = 5;
+
+/// How the conditions of a `GraduationCriteria` combine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CriteriaMode {
+    /// Graduate once any condition holds (OR)
+    #[default]
+    Any,
+    /// Graduate once every condition holds (AND)
+    All,
+}
+
+/// One graduation condition and its target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GraduationCondition {
+    /// Supply times marginal price, in base units
+    MarketCap(u128),
+    /// Base reserves backing the curve
+    Reserves(u128),
+    /// Supply sold, in basis points of `max_supply`
+    SoldBps(u128),
+    /// Blocks since launch
+    MinAge(u64),
+    /// Filled buys, standing in for holder count (see the module docs)
+    Buys(u128),
+}
+
+impl GraduationCondition {
+    /// Name used in progress reports
+    pub fn name(&self) -> &'static str {
+        match self {
+            GraduationCondition::MarketCap(_) => "market_cap",
+            GraduationCondition::Reserves(_) => "reserves",
+            GraduationCondition::SoldBps(_) => "sold_bps",
+            GraduationCondition::MinAge(_) => "min_age_blocks",
+            GraduationCondition::Buys(_) => "buys",
+        }
+    }
+
+    pub fn target(&self) -> u128 {
+        match *self {
+            GraduationCondition::MarketCap(target)
+            | GraduationCondition::Reserves(target)
+            | GraduationCondition::SoldBps(target)
+            | GraduationCondition::Buys(target) => target,
+            GraduationCondition::MinAge(blocks) => blocks as u128,
+        }
+    }
+
+    /// The measured quantity this condition compares against its target
+    pub fn current(&self, metrics: &GraduationMetrics, params: &CurveParams) -> u128 {
+        match self {
+            GraduationCondition::MarketCap(_) => metrics.market_cap(params),
+            GraduationCondition::Reserves(_) => metrics.base_reserves,
+            GraduationCondition::SoldBps(_) => metrics.sold_bps(params),
+            GraduationCondition::MinAge(_) => metrics.blocks_since_launch as u128,
+            GraduationCondition::Buys(_) => metrics.buys,
+        }
+    }
+
+    pub fn is_met(&self, metrics: &GraduationMetrics, params: &CurveParams) -> bool {
+        self.current(metrics, params) >= self.target()
+    }
+}
+
+/// Curve figures graduation is judged on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GraduationMetrics {
+    pub supply: u128,
+    pub base_reserves: u128,
+    pub blocks_since_launch: u64,
+    pub buys: u128,
+}
+
+impl GraduationMetrics {
+    /// Metrics of a curve with no age and no buys yet
+    pub fn at(supply: u128, base_reserves: u128) -> Self {
+        Self { supply, base_reserves, ..Self::default() }
+    }
+
+    /// Supply times the marginal price, saturating
+    ///
+    /// The price is taken in Q64.64 so curves quoting fractions of a base
+    /// unit per token still report a market cap.
+    pub fn market_cap(&self, params: &CurveParams) -> u128 {
+        let price = CurveCalculator::price_at_supply_q64(self.supply, params);
+        match price.to_u128() {
+            Some(price) => U256::mul_u128(self.supply, price).shr(64).to_u128().unwrap_or(u128::MAX),
+            None => self.supply.saturating_mul(price.shr(64).to_u128().unwrap_or(u128::MAX)),
+        }
+    }
+
+    /// Supply sold in basis points of `max_supply`, capped at 100%
+    pub fn sold_bps(&self, params: &CurveParams) -> u128 {
+        fixed_point::mul_div(self.supply, BPS, params.max_supply.max(1))
+            .map_or(BPS, |bps| bps.min(BPS))
+    }
+}
+
+/// Conditions a curve must meet to graduate, and how they combine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraduationCriteria {
+    pub mode: CriteriaMode,
+    pub conditions: Vec<GraduationCondition>,
+}
+
+impl GraduationCriteria {
+    /// The original rule: market cap at `threshold` or reserves at half of it
+    pub fn legacy(threshold: u128) -> Self {
+        Self {
+            mode: CriteriaMode::Any,
+            conditions: vec![
+                GraduationCondition::MarketCap(threshold),
+                GraduationCondition::Reserves(threshold / 2),
+            ],
+        }
+    }
+
+    /// Combine `conditions` by `mode`; a zero target leaves its condition
+    /// out. Every launch path builds its criteria here, so the factory and
+    /// `Initialize` read a zero target the same way
+    pub fn new(mode: CriteriaMode, conditions: impl IntoIterator<Item = GraduationCondition>) -> Self {
+        let conditions = conditions
+            .into_iter()
+            .filter(|condition| condition.target() > 0)
+            .collect();
+        Self { mode, conditions }
+    }
+
+    /// Decode the `Initialize` arguments: mode 0 keeps the legacy rule,
+    /// 1 is `Any` and 2 is `All`
+    pub fn from_args(
+        mode: u128,
+        market_cap: u128,
+        reserves: u128,
+        sold_bps: u128,
+        min_age_blocks: u128,
+        buys: u128,
+    ) -> Result<Option<Self>> {
+        let mode = match mode {
+            0 => return Ok(None),
+            1 => CriteriaMode::Any,
+            2 => CriteriaMode::All,
+            _ => return Err(anyhow!("Invalid graduation mode: {}", mode)),
+        };
+        let min_age_blocks = u64::try_from(min_age_blocks)
+            .map_err(|_| anyhow!("Graduation age too long"))?;
+
+        Ok(Some(Self::new(mode, [
+            GraduationCondition::MarketCap(market_cap),
+            GraduationCondition::Reserves(reserves),
+            GraduationCondition::SoldBps(sold_bps),
+            GraduationCondition::MinAge(min_age_blocks),
+            GraduationCondition::Buys(buys),
+        ])))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.conditions.is_empty() {
+            return Err(anyhow!("Graduation criteria need at least one condition"));
+        }
+        if self.conditions.len() > MAX_GRADUATION_CONDITIONS {
+            return Err(anyhow!("At most {} graduation conditions", MAX_GRADUATION_CONDITIONS));
+        }
+        for condition in &self.conditions {
+            if condition.target() == 0 {
+                return Err(anyhow!("Graduation condition {} needs a non-zero target", condition.name()));
+            }
+            if let GraduationCondition::SoldBps(bps) = condition {
+                if *bps > BPS {
+                    return Err(anyhow!("Graduation sold share cannot exceed {} bps", BPS));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_met(&self, metrics: &GraduationMetrics, params: &CurveParams) -> bool {
+        let mut results = self.conditions.iter().map(|condition| condition.is_met(metrics, params));
+        match self.mode {
+            CriteriaMode::Any => results.any(|met| met),
+            CriteriaMode::All => !self.conditions.is_empty() && results.all(|met| met),
+        }
+    }
+
+    /// Progress toward each condition, in order
+    pub fn progress(&self, metrics: &GraduationMetrics, params: &CurveParams) -> Vec<ConditionProgress> {
+        self.conditions
+            .iter()
+            .map(|condition| {
+                let current = condition.current(metrics, params);
+                let target = condition.target();
+                ConditionProgress {
+                    condition: condition.name(),
+                    current,
+                    target,
+                    met: current >= target,
+                    progress_bps: fixed_point::mul_div(current, BPS, target.max(1))
+                        .map_or(BPS, |bps| bps.min(BPS)),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Escape hatch for stalled launches: once `delay_blocks` have passed since
+/// launch, anyone may graduate a curve holding at least `min_reserves`,
+/// even if its criteria are not met
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmergencyGraduation {
+    pub delay_blocks: u64,  // 0 disables emergency graduation
+    pub min_reserves: u128, // Liquidity floor for the pool
+}
+
+impl Default for EmergencyGraduation {
+    fn default() -> Self {
+        Self {
+            delay_blocks: EMERGENCY_GRADUATION_DELAY_BLOCKS,
+            min_reserves: EMERGENCY_GRADUATION_MIN_RESERVES,
+        }
+    }
+}
+
+impl EmergencyGraduation {
+    pub fn validate(&self) -> Result<()> {
+        if self.delay_blocks > 0 && self.min_reserves == 0 {
+            return Err(anyhow!("Emergency graduation needs a non-zero liquidity floor"));
+        }
+        Ok(())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.delay_blocks > 0
+    }
+
+    /// Whether a curve this old, with this supply and reserves, may graduate
+    /// regardless of its criteria
+    pub fn is_eligible(&self, blocks_since_launch: u64, supply: u128, base_reserves: u128) -> bool {
+        self.is_enabled()
+            && blocks_since_launch >= self.delay_blocks
+            && supply > 0
+            && base_reserves >= self.min_reserves
+    }
+}
+
+/// How far the pool's opening price may stray from the curve's final
+/// marginal price; graduation aborts rather than open the pool outside it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceContinuity {
+    pub tolerance_bps: u128, // 0 demands an exact match
+}
+
+impl Default for PriceContinuity {
+    fn default() -> Self {
+        Self { tolerance_bps: GRADUATION_PRICE_TOLERANCE_BPS }
+    }
+}
+
+impl PriceContinuity {
+    pub fn validate(&self) -> Result<()> {
+        if self.tolerance_bps > BPS {
+            return Err(anyhow!("Graduation price tolerance cannot exceed 100%"));
+        }
+        Ok(())
+    }
+
+    /// Relative gap between the price of a pool holding `token_reserve`
+    /// against `base_reserve` and the price `numerator / denominator`, in
+    /// Q64.64; None for an empty pool or a gap too large to represent
+    pub fn deviation(token_reserve: u128, base_reserve: u128, (numerator, denominator): (u128, u128)) -> Option<u128> {
+        let pool = U256::mul_u128(base_reserve, denominator);
+        let curve = U256::mul_u128(token_reserve, numerator);
+        if curve.is_zero() {
+            return None;
+        }
+
+        let gap = pool.checked_sub(curve).or_else(|| curve.checked_sub(pool))?;
+        fixed_point::ratio_q64(gap, curve).ok()
+    }
+
+    /// Deviation in basis points, rounded to nearest, for reporting
+    pub fn deviation_bps(token_reserve: u128, base_reserve: u128, price: (u128, u128)) -> Option<u128> {
+        let gap = Self::deviation(token_reserve, base_reserve, price)?;
+        U256::mul_u128(gap, BPS)
+            .checked_add(U256::from_u128(1u128 << 63))?
+            .shr(64)
+            .to_u128()
+    }
+
+    /// Whether a pool holding these reserves opens within tolerance of `price`
+    pub fn allows(&self, token_reserve: u128, base_reserve: u128, price: (u128, u128)) -> bool {
+        match (Self::deviation(token_reserve, base_reserve, price), fixed_point::from_ratio(self.tolerance_bps, BPS)) {
+            (Some(gap), Ok(tolerance)) => gap <= tolerance,
+            _ => false,
+        }
+    }
+}
+
+/// How a launch's supply splits between the curve sale and the pool, and
+/// what graduation minted and burned of it
+///
+/// The LP reserve is minted only at graduation, to pair with the base
+/// reserves. Whatever is still unminted then, unsold curve allocation or
+/// unused reserve, is burned: it can never be minted afterwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct Tokenomics {
+    pub max_supply: u128,       // Curve allocation plus LP reserve
+    pub curve_allocation: u128, // Most tokens the curve sells
+    pub lp_reserve: u128,       // Minted at graduation to seed the pool
+    pub sold: u128,             // Sold along the curve so far
+    pub lp_minted: u128,        // Minted into the pool at graduation
+    pub burned: u128,           // Retired unminted at graduation
+}
+
+impl Tokenomics {
+    /// Allocation of a curve that has sold `sold` and not yet graduated
+    pub fn new(params: &CurveParams, sold: u128) -> Self {
+        Self {
+            max_supply: params.max_supply.saturating_add(params.lp_reserve_supply),
+            curve_allocation: params.max_supply,
+            lp_reserve: params.lp_reserve_supply,
+            sold,
+            lp_minted: 0,
+            burned: 0,
+        }
+    }
+
+    /// Split a launch's `total_supply` into its curve allocation and an LP
+    /// reserve of `lp_reserve_bps` of it; the pool can only be seeded from
+    /// the reserve, so one is required
+    pub fn split_supply(total_supply: u128, lp_reserve_bps: u128) -> Result<(u128, u128)> {
+        let lp_reserve = fixed_point::mul_div(total_supply, lp_reserve_bps, BPS)?;
+        if lp_reserve == 0 {
+            return Err(anyhow!("An LP reserve is required to seed the pool at graduation"));
+        }
+        match total_supply.checked_sub(lp_reserve) {
+            Some(curve_allocation) if curve_allocation > 0 => Ok((curve_allocation, lp_reserve)),
+            _ => Err(anyhow!("LP reserve must leave part of max supply for the curve sale")),
+        }
+    }
+
+    /// Most tokens graduation may mint into the pool: the LP reserve only.
+    /// Unsold curve allocation is never pooled; graduation burns it
+    pub fn pool_allocation(&self) -> u128 {
+        self.lp_reserve
+    }
+
+    /// Outcome of graduating with `lp_minted` tokens in the pool: the rest
+    /// of the unminted supply is burned
+    pub fn graduate(self, lp_minted: u128) -> Result<Self> {
+        if lp_minted > self.pool_allocation() {
+            return Err(anyhow!(
+                "Pool needs {} tokens, only {} allocated to it",
+                lp_minted,
+                self.pool_allocation()
+            ));
+        }
+
+        let minted = self.sold.saturating_add(lp_minted);
+        Ok(Self {
+            lp_minted,
+            burned: self.max_supply.saturating_sub(minted),
+            ..self
+        })
+    }
+}
+
+/// Where a curve stands on one condition
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConditionProgress {
+    pub condition: &'static str,
+    pub current: u128,
+    pub target: u128,
+    pub met: bool,
+    pub progress_bps: u128, // Share of the target reached, capped at 100%
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::linear_params;
+
+    fn metrics(supply: u128, base_reserves: u128, blocks_since_launch: u64, buys: u128) -> GraduationMetrics {
+        GraduationMetrics { supply, base_reserves, blocks_since_launch, buys }
+    }
+
+    #[test]
+    fn test_legacy_rule_matches_threshold() {
+        let params = linear_params();
+        let criteria = GraduationCriteria::legacy(params.graduation_threshold);
+
+        assert!(!criteria.is_met(&GraduationMetrics::at(1_000, 1_000), &params));
+        assert!(criteria.is_met(&GraduationMetrics::at(1_000, params.graduation_threshold / 2), &params));
+
+        // 250k sold at a marginal price of 26,000 is a 6.5B market cap
+        let sold = GraduationMetrics::at(250_000, 0);
+        assert_eq!(sold.market_cap(&params), 6_500_000_000);
+        assert!(!criteria.is_met(&sold, &params));
+    }
+
+    #[test]
+    fn test_all_requires_every_condition() {
+        let params = linear_params();
+        let criteria = GraduationCriteria::from_args(2, 0, 1_000_000, 5_000, 144, 10)
+            .unwrap()
+            .unwrap();
+        assert_eq!(criteria.conditions.len(), 4);
+        criteria.validate().unwrap();
+
+        assert!(criteria.is_met(&metrics(500_000, 1_000_000, 144, 10), &params));
+        assert!(!criteria.is_met(&metrics(500_000, 1_000_000, 143, 10), &params));
+        assert!(!criteria.is_met(&metrics(499_999, 1_000_000, 144, 10), &params));
+        assert!(!criteria.is_met(&metrics(500_000, 1_000_000, 144, 9), &params));
+    }
+
+    #[test]
+    fn test_any_requires_one_condition() {
+        let params = linear_params();
+        let criteria = GraduationCriteria::from_args(1, 0, 0, 9_000, 10_000, 0)
+            .unwrap()
+            .unwrap();
+
+        assert!(!criteria.is_met(&metrics(100_000, 0, 9_999, 0), &params));
+        assert!(criteria.is_met(&metrics(100_000, 0, 10_000, 0), &params));
+        assert!(criteria.is_met(&metrics(900_000, 0, 0, 0), &params));
+    }
+
+    #[test]
+    fn test_from_args_and_validation() {
+        assert_eq!(GraduationCriteria::from_args(0, 1, 1, 1, 1, 1).unwrap(), None);
+        assert!(GraduationCriteria::from_args(3, 1, 0, 0, 0, 0).is_err());
+
+        // Mode set but every target zero
+        let empty = GraduationCriteria::from_args(1, 0, 0, 0, 0, 0).unwrap().unwrap();
+        assert!(empty.validate().is_err());
+
+        let oversold = GraduationCriteria::from_args(1, 0, 0, 10_001, 0, 0).unwrap().unwrap();
+        assert!(oversold.validate().is_err());
+    }
+
+    #[test]
+    fn test_zero_targets_are_left_out() {
+        let criteria = GraduationCriteria::new(CriteriaMode::All, [
+            GraduationCondition::Reserves(0),
+            GraduationCondition::Buys(0),
+            GraduationCondition::MinAge(0),
+            GraduationCondition::SoldBps(5_000),
+        ]);
+        assert_eq!(criteria.conditions, [GraduationCondition::SoldBps(5_000)]);
+        assert!(criteria.validate().is_ok());
+
+        // `Initialize` arguments land on the same criteria
+        assert_eq!(GraduationCriteria::from_args(2, 0, 0, 5_000, 0, 0).unwrap(), Some(criteria));
+    }
+
+    #[test]
+    fn test_progress_report() {
+        let params = linear_params();
+        let criteria = GraduationCriteria {
+            mode: CriteriaMode::All,
+            conditions: vec![GraduationCondition::Reserves(1_000), GraduationCondition::MinAge(100)],
+        };
+
+        let progress = criteria.progress(&metrics(0, 250, 300, 0), &params);
+        assert_eq!(progress.len(), 2);
+        assert_eq!(progress[0].condition, "reserves");
+        assert_eq!((progress[0].current, progress[0].target), (250, 1_000));
+        assert_eq!(progress[0].progress_bps, 2_500);
+        assert!(!progress[0].met);
+        assert_eq!(progress[1].progress_bps, BPS);
+        assert!(progress[1].met);
+    }
+
+    #[test]
+    fn test_emergency_graduation_window() {
+        let emergency = EmergencyGraduation { delay_blocks: 1_000, min_reserves: 500 };
+        emergency.validate().unwrap();
+
+        assert!(!emergency.is_eligible(999, 10, 500));
+        assert!(emergency.is_eligible(1_000, 10, 500));
+        assert!(!emergency.is_eligible(1_000, 10, 499));
+        assert!(!emergency.is_eligible(1_000, 0, 500));
+
+        let disabled = EmergencyGraduation { delay_blocks: 0, min_reserves: 0 };
+        disabled.validate().unwrap();
+        assert!(!disabled.is_eligible(u64::MAX, 10, u128::MAX));
+
+        assert!(EmergencyGraduation { delay_blocks: 1, min_reserves: 0 }.validate().is_err());
+    }
+
+    #[test]
+    fn test_price_continuity_tolerance() {
+        let continuity = PriceContinuity { tolerance_bps: 100 };
+        continuity.validate().unwrap();
+
+        assert_eq!(PriceContinuity::deviation_bps(1_000, 150_000, (100, 1)), Some(5_000));
+        assert_eq!(PriceContinuity::deviation_bps(1_000, 1_000, (100, 1)), Some(9_900));
+
+        // 1,000 tokens against 101,000 base is 1% above a price of 100
+        assert!(continuity.allows(1_000, 101_000, (100, 1)));
+        assert!(!continuity.allows(1_000, 101_001, (100, 1)));
+        assert!(continuity.allows(1_000, 99_000, (100, 1)));
+        assert!(!continuity.allows(1_000, 98_999, (100, 1)));
+
+        // Fractional prices compare exactly
+        assert!(PriceContinuity { tolerance_bps: 0 }.allows(3, 1, (1, 3)));
+        assert!(!continuity.allows(0, 1_000, (100, 1)));
+
+        assert!(PriceContinuity { tolerance_bps: BPS + 1 }.validate().is_err());
+    }
+
+    #[test]
+    fn test_tokenomics_split_and_graduation() {
+        let params = CurveParams { lp_reserve_supply: 250_000, ..linear_params() };
+        let tokenomics = Tokenomics::new(&params, 600_000);
+        assert_eq!(tokenomics.max_supply, 1_250_000);
+        assert_eq!(tokenomics.pool_allocation(), 250_000);
+
+        // 400k unsold and 50k of unused reserve are burned
+        let graduated = tokenomics.graduate(200_000).unwrap();
+        assert_eq!((graduated.lp_minted, graduated.burned), (200_000, 450_000));
+        assert_eq!(graduated.sold + graduated.lp_minted + graduated.burned, graduated.max_supply);
+
+        assert!(tokenomics.graduate(250_001).is_err());
+
+        // Without a reserve nothing may be pooled and all 400k unsold burn
+        let unreserved = Tokenomics::new(&CurveParams { lp_reserve_supply: 0, ..linear_params() }, 600_000);
+        assert_eq!(unreserved.pool_allocation(), 0);
+        assert!(unreserved.graduate(1).is_err());
+        assert_eq!(unreserved.graduate(0).unwrap().burned, 400_000);
+    }
+
+    #[test]
+    fn test_tokenomics_supply_split() {
+        assert_eq!(Tokenomics::split_supply(1_000_000, 2_500).unwrap(), (750_000, 250_000));
+
+        // The pool needs a reserve, and the curve must keep something to sell
+        assert!(Tokenomics::split_supply(1_000_000, 0).is_err());
+        assert!(Tokenomics::split_supply(1_000, 1).is_err());
+        assert!(Tokenomics::split_supply(1_000_000, BPS).is_err());
+        assert!(Tokenomics::split_supply(1_000_000, BPS + 1).is_err());
+        assert!(Tokenomics::split_supply(0, 0).is_err());
+    }
+}
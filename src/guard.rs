@@ -0,0 +1,51 @@
+//! Reentrancy Guard
+//!
+//! Buys, sells and graduation hold a storage-backed lock while they run.
+//! Storage written before an external call is visible to the callee, so a
+//! contract that calls back into the curve mid-operation (e.g. a malicious
+//! pool during graduation) finds the lock taken and is rejected.
+
+use alkanes_runtime::storage::StoragePointer;
+use anyhow::{anyhow, Result};
+use metashrew_support::index_pointer::KeyValuePointer;
+use std::sync::Arc;
+
+/// Held for the duration of a state-changing operation; released on drop
+#[derive(Debug)]
+pub struct ReentrancyGuard(());
+
+impl ReentrancyGuard {
+    /// Name of the operation holding the lock, empty when free
+    fn lock_pointer() -> StoragePointer {
+        StoragePointer::from_keyword("/reentrancy_lock")
+    }
+
+    /// Operation currently holding the lock, if any
+    pub fn holder() -> Option<String> {
+        let data = Self::lock_pointer().get();
+        if data.is_empty() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(data.as_ref()).into_owned())
+    }
+
+    pub fn is_locked() -> bool {
+        Self::holder().is_some()
+    }
+
+    /// Take the lock for `operation`, failing if any operation holds it
+    pub fn enter(operation: &str) -> Result<Self> {
+        if let Some(holder) = Self::holder() {
+            return Err(anyhow!("Reentrant call rejected: {} is already in progress", holder));
+        }
+
+        Self::lock_pointer().set(Arc::new(operation.as_bytes().to_vec()));
+        Ok(ReentrancyGuard(()))
+    }
+}
+
+impl Drop for ReentrancyGuard {
+    fn drop(&mut self) {
+        Self::lock_pointer().set(Arc::new(Vec::new()));
+    }
+}
@@ -0,0 +1,184 @@
+//! Storage Journal
+//!
+//! Opcode handlers run inside `Journal::transact`. Writes through a
+//! `JournaledPointer` are staged and reach storage only when the handler
+//! succeeds, so a failure part-way leaves nothing half-written.
+//! Transactions nest: an inner one that fails discards only its own writes
+//! and the outer operation carries on, e.g. a buy whose auto-graduation
+//! fails keeps the purchase.
+//!
+//! Outside a transaction writes go straight to storage. The reentrancy lock
+//! bypasses the journal on purpose, since callees must see it during
+//! external calls.
+
+use alkanes_runtime::storage::StoragePointer;
+use anyhow::Result;
+use metashrew_support::index_pointer::KeyValuePointer;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+type Layer = BTreeMap<Vec<u8>, Arc<Vec<u8>>>;
+
+thread_local! {
+    /// Staged writes of each open transaction, innermost last
+    static LAYERS: RefCell<Vec<Layer>> = RefCell::new(Vec::new());
+}
+
+/// Transactions over journaled storage
+pub struct Journal;
+
+impl Journal {
+    /// Number of open transactions
+    pub fn depth() -> usize {
+        LAYERS.with(|layers| layers.borrow().len())
+    }
+
+    /// Run `operation` in a transaction: its writes are committed if it
+    /// succeeds and discarded if it fails
+    ///
+    /// Inside another transaction, committing hands the writes to the
+    /// enclosing one rather than to storage.
+    pub fn transact<T>(operation: impl FnOnce() -> Result<T>) -> Result<T> {
+        LAYERS.with(|layers| layers.borrow_mut().push(Layer::new()));
+        let result = operation();
+        let staged = LAYERS.with(|layers| layers.borrow_mut().pop()).unwrap_or_default();
+
+        if result.is_ok() {
+            Self::commit(staged);
+        }
+        result
+    }
+
+    fn commit(staged: Layer) {
+        let unstaged = LAYERS.with(|layers| match layers.borrow_mut().last_mut() {
+            Some(parent) => {
+                parent.extend(staged);
+                None
+            },
+            None => Some(staged),
+        });
+
+        for (key, value) in unstaged.into_iter().flatten() {
+            StoragePointer::wrap(&key).set(value);
+        }
+    }
+
+    /// Latest staged value of `key`, if any open transaction wrote it
+    fn staged(key: &[u8]) -> Option<Arc<Vec<u8>>> {
+        LAYERS.with(|layers| {
+            layers
+                .borrow()
+                .iter()
+                .rev()
+                .find_map(|layer| layer.get(key).cloned())
+        })
+    }
+
+    /// Stage a write in the innermost transaction; false if none is open
+    fn stage(key: &[u8], value: Arc<Vec<u8>>) -> bool {
+        LAYERS.with(|layers| match layers.borrow_mut().last_mut() {
+            Some(layer) => {
+                layer.insert(key.to_vec(), value);
+                true
+            },
+            None => false,
+        })
+    }
+}
+
+/// `StoragePointer` whose writes go through the journal
+#[derive(Debug, Clone, Default)]
+pub struct JournaledPointer(Arc<Vec<u8>>);
+
+impl KeyValuePointer for JournaledPointer {
+    fn wrap(word: &Vec<u8>) -> Self {
+        Self(Arc::new(word.clone()))
+    }
+
+    fn unwrap(&self) -> Arc<Vec<u8>> {
+        self.0.clone()
+    }
+
+    fn get(&self) -> Arc<Vec<u8>> {
+        Journal::staged(&self.0).unwrap_or_else(|| StoragePointer::wrap(&self.0).get())
+    }
+
+    fn set(&mut self, value: Arc<Vec<u8>>) {
+        if !Journal::stage(&self.0, value.clone()) {
+            StoragePointer::wrap(&self.0).set(value);
+        }
+    }
+
+    fn inherits(&mut self, from: &Self) {
+        self.0 = from.0.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    fn pointer(key: &str) -> JournaledPointer {
+        JournaledPointer::from_keyword(key)
+    }
+
+    #[test]
+    fn test_commit_on_success() {
+        Journal::transact(|| {
+            pointer("/journal/commit").set_value::<u128>(7);
+            assert_eq!(pointer("/journal/commit").get_value::<u128>(), 7);
+            assert_eq!(StoragePointer::from_keyword("/journal/commit").get_value::<u128>(), 0);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(StoragePointer::from_keyword("/journal/commit").get_value::<u128>(), 7);
+        assert_eq!(Journal::depth(), 0);
+    }
+
+    #[test]
+    fn test_discard_on_failure() {
+        pointer("/journal/discard").set_value::<u128>(1);
+
+        let result: Result<()> = Journal::transact(|| {
+            pointer("/journal/discard").set_value::<u128>(2);
+            pointer("/journal/discard/other").set_value::<u128>(3);
+            Err(anyhow!("failed part-way"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(pointer("/journal/discard").get_value::<u128>(), 1);
+        assert_eq!(pointer("/journal/discard/other").get_value::<u128>(), 0);
+    }
+
+    #[test]
+    fn test_failed_inner_transaction_keeps_outer_writes() {
+        Journal::transact(|| {
+            pointer("/journal/outer").set_value::<u128>(1);
+
+            let inner: Result<()> = Journal::transact(|| {
+                pointer("/journal/outer").set_value::<u128>(99);
+                pointer("/journal/inner").set_value::<u128>(2);
+                Err(anyhow!("inner failed"))
+            });
+            assert!(inner.is_err());
+            assert_eq!(pointer("/journal/outer").get_value::<u128>(), 1);
+
+            Journal::transact(|| {
+                pointer("/journal/nested").set_value::<u128>(3);
+                Ok(())
+            })?;
+
+            // Committed into the outer transaction, not yet to storage
+            assert_eq!(StoragePointer::from_keyword("/journal/nested").get_value::<u128>(), 0);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(pointer("/journal/outer").get_value::<u128>(), 1);
+        assert_eq!(pointer("/journal/inner").get_value::<u128>(), 0);
+        assert_eq!(pointer("/journal/nested").get_value::<u128>(), 3);
+    }
+}
@@ -10,13 +10,14 @@
 //! - Automatic liquidity graduation to Oyl AMM pools
 //! - Comprehensive security patterns and access controls
 
-use alkanes_runtime::storage::StoragePointer;
+use crate::journal::JournaledPointer;
 use alkanes_runtime::{declare_alkane, message::MessageDispatch, runtime::AlkaneResponder, println};
 use alkanes_support::gz;
 use alkanes_support::response::CallResponse;
 use alkanes_support::utils::overflow_error;
 use alkanes_support::witness::find_witness_payload;
-use alkanes_support::{context::Context, parcel::AlkaneTransfer, id::AlkaneId};
+use alkanes_support::cellpack::Cellpack;
+use alkanes_support::{context::Context, parcel::{AlkaneTransfer, AlkaneTransferParcel}, id::AlkaneId};
 use anyhow::{anyhow, Result};
 
 use bitcoin::Transaction;
@@ -28,7 +29,17 @@ use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 
 pub mod precompiled;
+pub mod constants;
+pub mod fixed_point;
+pub mod curve_models;
 pub mod bonding_curve;
+pub mod fees;
+pub mod trade;
+pub mod graduation;
+pub mod parcel;
+pub mod guard;
+pub mod journal;
+pub mod amm_adapter;
 pub mod amm_integration;
 pub mod factory;
 #[cfg(test)]
@@ -63,36 +74,127 @@ impl BaseToken {
     }
 }
 
+/// Pricing model used by a bonding curve (see `curve_models`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CurveType {
+    #[default]
+    Exponential,
+    Linear,
+    Quadratic,
+    Cubic,
+    Sigmoid,
+    PiecewiseLinear,
+    ConstantProduct,
+    ReserveRatio,
+}
+
+impl CurveType {
+    /// Decode the opcode representation (0 = exponential ... 7 = reserve-ratio)
+    pub fn from_u128(value: u128) -> Result<Self> {
+        match value {
+            0 => Ok(CurveType::Exponential),
+            1 => Ok(CurveType::Linear),
+            2 => Ok(CurveType::Quadratic),
+            3 => Ok(CurveType::Cubic),
+            4 => Ok(CurveType::Sigmoid),
+            5 => Ok(CurveType::PiecewiseLinear),
+            6 => Ok(CurveType::ConstantProduct),
+            7 => Ok(CurveType::ReserveRatio),
+            _ => Err(anyhow!("Invalid curve type")),
+        }
+    }
+}
+
 /// Bonding curve parameters for token launches
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CurveParams {
     pub base_price: u128,           // Starting price in base token satoshis
-    pub growth_rate: u128,          // Basis points increase per token (e.g., 1500 = 1.5%)
+    pub growth_rate: u128,          // Basis points increase per growth step (e.g., 150 = 1.5%); sigmoid steepness
+    #[serde(default)]
+    pub growth_steps: u128,         // Exponential compoundings across max supply (0 = every base unit)
     pub graduation_threshold: u128,  // Market cap threshold for AMM graduation
     pub base_token: BaseToken,      // Base currency (BUSD or frBTC)
-    pub max_supply: u128,           // Maximum token supply
+    pub max_supply: u128,           // Curve-sale allocation: most tokens the curve sells
+    #[serde(default)]
+    pub curve_type: CurveType,      // Pricing model
+    #[serde(default)]
+    pub max_price: u128,            // Price ceiling for polynomial and sigmoid curves
+    #[serde(default)]
+    pub midpoint_supply: u128,      // Sigmoid inflection point
+    #[serde(default)]
+    pub breakpoints: Vec<(u128, u128)>, // (supply, price) points for piecewise-linear curves
+    #[serde(default)]
+    pub virtual_base_reserve: u128, // Virtual base reserve for constant-product and reserve-ratio curves
+    #[serde(default)]
+    pub virtual_token_reserve: u128, // Virtual token reserve for constant-product curves
+    #[serde(default)]
+    pub connector_weight: u128, // Reserve ratio in ppm for reserve-ratio curves
+    #[serde(default)]
+    pub virtual_supply: u128, // Initial virtual supply for reserve-ratio curves
+    #[serde(default)]
+    pub fees: fees::FeeSchedule, // Trading fee rate and split
+    #[serde(default)]
+    pub sell_spread: bonding_curve::SellSpread, // Discount on sell payouts and where it goes
+    #[serde(default)]
+    pub limits: trade::TradeLimits, // Per-trade size and slippage limits
+    #[serde(default)]
+    pub graduation_criteria: Option<graduation::GraduationCriteria>, // None = threshold rule
+    #[serde(default)]
+    pub emergency_graduation: graduation::EmergencyGraduation, // Delay and liquidity floor for stalled launches
+    #[serde(default)]
+    pub price_continuity: graduation::PriceContinuity, // How far the pool may open from the final curve price
+    #[serde(default)]
+    pub lp_reserve_supply: u128, // Minted at graduation to seed the pool; unsold supply is burned
+    #[serde(default)]
+    pub amm_backend: amm_adapter::AmmBackend, // AMM the curve graduates to (default: Oyl)
+}
+
+impl CurveParams {
+    /// Graduation criteria in force: the launch's own, or the threshold rule
+    pub fn criteria(&self) -> graduation::GraduationCriteria {
+        self.graduation_criteria
+            .clone()
+            .unwrap_or_else(|| graduation::GraduationCriteria::legacy(self.graduation_threshold))
+    }
 }
 
 impl Default for CurveParams {
     fn default() -> Self {
         Self {
             base_price: 1_000_000,        // 0.01 BUSD (assuming 8 decimals)
-            growth_rate: 1500,            // 1.5% per token
+            growth_rate: 1500,            // 15% per growth step
+            growth_steps: 100,            // One step per 1% of max supply
             graduation_threshold: 10_000_000_000_000, // 100,000 BUSD
             base_token: BaseToken::BUSD,
             max_supply: 1_000_000_000_000_000, // 1 billion tokens
+            curve_type: CurveType::Exponential,
+            max_price: 0,
+            midpoint_supply: 0,
+            breakpoints: Vec::new(),
+            virtual_base_reserve: 0,
+            virtual_token_reserve: 0,
+            connector_weight: 0,
+            virtual_supply: 0,
+            fees: fees::FeeSchedule::default(),
+            sell_spread: bonding_curve::SellSpread::default(),
+            limits: trade::TradeLimits::default(),
+            graduation_criteria: None,
+            emergency_graduation: graduation::EmergencyGraduation::default(),
+            price_continuity: graduation::PriceContinuity::default(),
+            lp_reserve_supply: 250_000_000_000_000, // 20% of the 1.25 billion total
+            amm_backend: amm_adapter::AmmBackend::default(),
         }
     }
 }
 
-/// Returns a StoragePointer for the token name
-fn name_pointer() -> StoragePointer {
-    StoragePointer::from_keyword("/name")
+/// Returns a JournaledPointer for the token name
+fn name_pointer() -> JournaledPointer {
+    JournaledPointer::from_keyword("/name")
 }
 
-/// Returns a StoragePointer for the token symbol
-fn symbol_pointer() -> StoragePointer {
-    StoragePointer::from_keyword("/symbol")
+/// Returns a JournaledPointer for the token symbol
+fn symbol_pointer() -> JournaledPointer {
+    JournaledPointer::from_keyword("/symbol")
 }
 
 /// Trims a u128 value to a String by removing trailing zeros
@@ -206,6 +308,15 @@ enum FactoryMessage {
     /// Get factory statistics
     #[opcode(102)]
     GetFactoryStats,
+
+    /// Set the treasury launched curves pay protocol fees to (admin only)
+    #[opcode(103)]
+    SetTreasury {
+        /// Treasury block number
+        treasury_block: u128,
+        /// Treasury transaction index
+        treasury_tx: u128,
+    },
 }
 
 /// Factory contract for deploying bonding curve tokens
@@ -213,7 +324,12 @@ enum FactoryMessage {
 pub struct Factory(());
 
 impl Factory {
+    /// Handle a factory message; its storage writes commit only if it succeeds
     fn handle_factory_message(&self, message: FactoryMessage) -> Result<CallResponse> {
+        journal::Journal::transact(|| self.dispatch_factory_message(message))
+    }
+
+    fn dispatch_factory_message(&self, message: FactoryMessage) -> Result<CallResponse> {
         match message {
             FactoryMessage::CreateToken { params } => {
                 let launch_params: TokenLaunchParams = serde_json::from_slice(&params)
@@ -276,6 +392,15 @@ impl Factory {
                 Ok(response)
             },
             
+            FactoryMessage::SetTreasury { treasury_block, treasury_tx } => {
+                // TODO: Add admin access control
+                BondingCurveFactory::set_treasury(&AlkaneId { block: treasury_block, tx: treasury_tx })?;
+
+                let mut response = CallResponse::default();
+                response.data = vec![1]; // Success indicator
+                Ok(response)
+            },
+            
             FactoryMessage::GetFactoryStats => {
                 let stats = serde_json::json!({
                     "total_tokens": BondingCurveFactory::get_token_count(),
@@ -358,23 +483,23 @@ pub trait MintableToken: AlkaneResponder {
     }
 
     /// Get the pointer to the token name
-    fn name_pointer(&self) -> StoragePointer {
+    fn name_pointer(&self) -> JournaledPointer {
         name_pointer()
     }
 
     /// Get the pointer to the token symbol
-    fn symbol_pointer(&self) -> StoragePointer {
+    fn symbol_pointer(&self) -> JournaledPointer {
         symbol_pointer()
     }
 
     /// Set a string field in storage
-    fn set_string_field(&self, mut pointer: StoragePointer, v: u128) {
+    fn set_string_field(&self, mut pointer: JournaledPointer, v: u128) {
         pointer.set(Arc::new(trim(v).as_bytes().to_vec()));
     }
 
     /// Get the pointer to the total supply
-    fn total_supply_pointer(&self) -> StoragePointer {
-        StoragePointer::from_keyword("/totalsupply")
+    fn total_supply_pointer(&self) -> JournaledPointer {
+        bonding_curve::CurveCalculator::total_supply_pointer()
     }
 
     /// Get the total supply
@@ -406,8 +531,8 @@ pub trait MintableToken: AlkaneResponder {
     }
 
     /// Get the pointer to the token data
-    fn data_pointer(&self) -> StoragePointer {
-        StoragePointer::from_keyword("/data")
+    fn data_pointer(&self) -> JournaledPointer {
+        JournaledPointer::from_keyword("/data")
     }
 
     /// Get the token data
@@ -451,10 +576,90 @@ enum BondingCurveMessage {
         graduation_threshold: u128,
         /// Base token type (0 = BUSD, 1 = frBTC)
         base_token_type: u128,
-        /// Maximum supply
+        /// Maximum supply, curve sale and LP reserve together
         max_supply: u128,
         /// LP distribution strategy
         lp_distribution_strategy: u128,
+        /// Curve type (0 = exponential, 1 = linear, 2 = quadratic, 3 = cubic, 4 = sigmoid, 5 = piecewise-linear, 6 = constant-product, 7 = reserve-ratio)
+        curve_type: u128,
+        /// Price ceiling for polynomial and sigmoid curves
+        max_price: u128,
+        /// Sigmoid inflection point
+        midpoint_supply: u128,
+        /// Flattened (supply, price) breakpoints for piecewise-linear curves
+        breakpoints: Vec<u128>,
+        /// Virtual base reserve for constant-product and reserve-ratio curves
+        virtual_base_reserve: u128,
+        /// Virtual token reserve for constant-product curves
+        virtual_token_reserve: u128,
+        /// Connector weight in ppm for reserve-ratio curves
+        connector_weight: u128,
+        /// Initial virtual supply for reserve-ratio curves
+        virtual_supply: u128,
+        /// Trading fee in basis points
+        trading_fee_bps: u128,
+        /// Protocol share of the fee in basis points
+        protocol_fee_share_bps: u128,
+        /// Creator share of the fee in basis points
+        creator_fee_share_bps: u128,
+        /// Referrer share of the fee in basis points
+        referrer_fee_share_bps: u128,
+        /// Creator fee recipient block
+        creator_block: u128,
+        /// Creator fee recipient tx
+        creator_tx: u128,
+        /// Referrer fee recipient block (0:0 = no referrer)
+        referrer_block: u128,
+        /// Referrer fee recipient tx
+        referrer_tx: u128,
+        /// Protocol fee treasury block (0:0 = the calling contract; required from wallets)
+        treasury_block: u128,
+        /// Protocol fee treasury tx
+        treasury_tx: u128,
+        /// Sell spread at launch in basis points
+        sell_spread_initial_bps: u128,
+        /// Sell spread after the decay window in basis points
+        sell_spread_final_bps: u128,
+        /// Blocks over which the sell spread decays (0 = no decay)
+        sell_spread_decay_blocks: u128,
+        /// Sell spread destination (0 = reserves, 1 = burn, 2 = fees)
+        sell_spread_destination: u128,
+        /// Smallest base amount a buy may spend
+        min_buy_amount: u128,
+        /// Largest buy in basis points of the remaining supply
+        max_buy_bps: u128,
+        /// Largest sell in basis points of the circulating supply
+        max_sell_bps: u128,
+        /// Loosest slippage bound trades accept, in basis points of the caller's quote
+        max_slippage_bps: u128,
+        /// Graduation mode (0 = threshold rule, 1 = any condition, 2 = all conditions)
+        graduation_mode: u128,
+        /// Market cap to graduate at (0 = no market cap condition)
+        graduation_min_market_cap: u128,
+        /// Base reserves to graduate at (0 = no reserves condition)
+        graduation_min_reserves: u128,
+        /// Share of max supply sold to graduate at, in basis points (0 = none)
+        graduation_min_sold_bps: u128,
+        /// Blocks since launch before graduating (0 = none)
+        graduation_min_age_blocks: u128,
+        /// Filled buys to graduate at (0 = none)
+        graduation_min_buys: u128,
+        /// Blocks after launch before anyone may force graduation (0 = never)
+        emergency_delay_blocks: u128,
+        /// Base reserves a curve needs for emergency graduation
+        emergency_min_reserves: u128,
+        /// Largest gap between the pool's opening price and the final curve price, in basis points
+        graduation_price_tolerance_bps: u128,
+        /// Share of max_supply reserved to seed the pool at graduation, in basis points (required)
+        lp_reserve_bps: u128,
+        /// AMM to graduate to (0 = Oyl)
+        amm_backend: u128,
+        /// AMM factory block (0 with tx 0 = the AMM's mainnet factory)
+        amm_factory_block: u128,
+        /// AMM factory tx
+        amm_factory_tx: u128,
+        /// Times the exponential growth rate compounds across the curve allocation (0 = every base unit)
+        growth_steps: u128,
     },
 
     /// Buy tokens with base currency
@@ -462,6 +667,8 @@ enum BondingCurveMessage {
     BuyTokens {
         /// Minimum tokens expected (slippage protection)
         min_tokens_out: u128,
+        /// Tokens the caller was quoted (0 = no quote)
+        quoted_tokens_out: u128,
     },
 
     /// Sell tokens for base currency
@@ -471,8 +678,80 @@ enum BondingCurveMessage {
         token_amount: u128,
         /// Minimum base tokens expected (slippage protection)
         min_base_out: u128,
+        /// Base tokens the caller was quoted (0 = no quote)
+        quoted_base_out: u128,
+    },
+
+    /// Buy an exact token amount, refunding unspent base
+    #[opcode(207)]
+    BuyExactTokens {
+        /// Number of tokens to buy
+        token_amount: u128,
+        /// Maximum base tokens to spend (slippage protection)
+        max_base_in: u128,
+        /// Base tokens the caller was quoted (0 = no quote)
+        quoted_base_in: u128,
+    },
+
+    /// Sell tokens for an exact base amount, returning unused tokens
+    #[opcode(208)]
+    SellForExactBase {
+        /// Maximum tokens to sell (slippage protection)
+        max_tokens_in: u128,
+        /// Exact base tokens to receive
+        base_amount: u128,
+        /// Tokens the caller was quoted (0 = no quote)
+        quoted_tokens_in: u128,
+    },
+
+    /// Claim accrued trading fees
+    #[opcode(209)]
+    ClaimFees {
+        /// Fee bucket (0 = protocol, 1 = creator, 2 = referrer)
+        recipient: u128,
     },
 
+    /// Get accrued trading fees per recipient
+    #[opcode(210)]
+    GetAccruedFees,
+
+    /// Get a versioned buy quote (see `trade::TradePlan::to_quote`)
+    #[opcode(211)]
+    QuoteBuy {
+        /// Number of tokens to quote
+        token_amount: u128,
+    },
+
+    /// Get a versioned sell quote (see `trade::TradePlan::to_quote`)
+    #[opcode(212)]
+    QuoteSell {
+        /// Number of tokens to quote
+        token_amount: u128,
+    },
+
+    /// Get a versioned quote for spending `base_amount` like `BuyTokens`
+    #[opcode(213)]
+    QuoteBuyWithBase {
+        /// Base tokens to spend, fee included
+        base_amount: u128,
+    },
+
+    /// Get the per-trade limits and the current buy and sell caps
+    #[opcode(214)]
+    GetTradeLimits,
+
+    /// Retry a pending or failed graduation; anyone may call it
+    #[opcode(215)]
+    RetryGraduation,
+
+    /// Get the graduation state byte followed by the last failure reason
+    #[opcode(216)]
+    GetGraduationStatus,
+
+    /// Get progress toward each graduation condition as JSON
+    #[opcode(217)]
+    GetGraduationProgress,
+
     /// Get buy quote for token amount
     #[opcode(203)]
     GetBuyQuote {
@@ -525,7 +804,29 @@ enum BondingCurveMessage {
 }
 
 impl BondingCurve {
-    fn handle_message(&self, message: BondingCurveMessage) -> Result<CallResponse> {
+    /// Handle a curve message sent in `context`; its storage writes commit
+    /// only if it succeeds
+    fn handle_message(&self, context: &Context, message: BondingCurveMessage) -> Result<CallResponse> {
+        self.handle_message_via(self, context, message)
+    }
+
+    /// `handle_message`, making graduation's calls to other alkanes through
+    /// `caller`
+    fn handle_message_via(
+        &self,
+        caller: &dyn amm_integration::AlkaneCaller,
+        context: &Context,
+        message: BondingCurveMessage,
+    ) -> Result<CallResponse> {
+        journal::Journal::transact(|| self.dispatch_message(caller, context, message))
+    }
+
+    fn dispatch_message(
+        &self,
+        caller: &dyn amm_integration::AlkaneCaller,
+        context: &Context,
+        message: BondingCurveMessage,
+    ) -> Result<CallResponse> {
         match message {
             BondingCurveMessage::Initialize {
                 name_part1,
@@ -537,38 +838,170 @@ impl BondingCurve {
                 base_token_type,
                 max_supply,
                 lp_distribution_strategy,
+                curve_type,
+                max_price,
+                midpoint_supply,
+                breakpoints,
+                virtual_base_reserve,
+                virtual_token_reserve,
+                connector_weight,
+                virtual_supply,
+                trading_fee_bps,
+                protocol_fee_share_bps,
+                creator_fee_share_bps,
+                referrer_fee_share_bps,
+                creator_block,
+                creator_tx,
+                referrer_block,
+                referrer_tx,
+                treasury_block,
+                treasury_tx,
+                sell_spread_initial_bps,
+                sell_spread_final_bps,
+                sell_spread_decay_blocks,
+                sell_spread_destination,
+                min_buy_amount,
+                max_buy_bps,
+                max_sell_bps,
+                max_slippage_bps,
+                graduation_mode,
+                graduation_min_market_cap,
+                graduation_min_reserves,
+                graduation_min_sold_bps,
+                graduation_min_age_blocks,
+                graduation_min_buys,
+                emergency_delay_blocks,
+                emergency_min_reserves,
+                graduation_price_tolerance_bps,
+                lp_reserve_bps,
+                amm_backend,
+                amm_factory_block,
+                amm_factory_tx,
+                growth_steps,
             } => {
                 self.initialize(
+                    context,
                     name_part1,
                     name_part2,
                     symbol,
                     base_price,
                     growth_rate,
+                    growth_steps,
                     graduation_threshold,
                     base_token_type,
                     max_supply,
                     lp_distribution_strategy,
+                    curve_type,
+                    max_price,
+                    midpoint_supply,
+                    breakpoints,
+                    virtual_base_reserve,
+                    virtual_token_reserve,
+                    connector_weight,
+                    virtual_supply,
+                    fees::FeeSchedule {
+                        trading_fee_bps,
+                        protocol_share_bps: protocol_fee_share_bps,
+                        creator_share_bps: creator_fee_share_bps,
+                        referrer_share_bps: referrer_fee_share_bps,
+                    },
+                    AlkaneId { block: creator_block, tx: creator_tx },
+                    AlkaneId { block: referrer_block, tx: referrer_tx },
+                    AlkaneId { block: treasury_block, tx: treasury_tx },
+                    bonding_curve::SellSpread {
+                        initial_bps: sell_spread_initial_bps,
+                        final_bps: sell_spread_final_bps,
+                        decay_blocks: u64::try_from(sell_spread_decay_blocks)
+                            .map_err(|_| anyhow!("Sell spread decay window too long"))?,
+                        destination: bonding_curve::SpreadDestination::from_u128(sell_spread_destination)?,
+                    },
+                    trade::TradeLimits {
+                        min_buy_amount,
+                        max_buy_bps,
+                        max_sell_bps,
+                        max_slippage_bps,
+                    },
+                    graduation::GraduationCriteria::from_args(
+                        graduation_mode,
+                        graduation_min_market_cap,
+                        graduation_min_reserves,
+                        graduation_min_sold_bps,
+                        graduation_min_age_blocks,
+                        graduation_min_buys,
+                    )?,
+                    graduation::EmergencyGraduation {
+                        delay_blocks: u64::try_from(emergency_delay_blocks)
+                            .map_err(|_| anyhow!("Emergency graduation delay too long"))?,
+                        min_reserves: emergency_min_reserves,
+                    },
+                    graduation::PriceContinuity { tolerance_bps: graduation_price_tolerance_bps },
+                    lp_reserve_bps,
+                    amm_adapter::AmmBackend::from_args(amm_backend, amm_factory_block, amm_factory_tx)?,
                 )
             },
             
-            BondingCurveMessage::BuyTokens { min_tokens_out } => {
-                self.buy_tokens(min_tokens_out)
+            BondingCurveMessage::BuyTokens { min_tokens_out, quoted_tokens_out } => {
+                self.buy_tokens(caller, context, min_tokens_out, quoted_tokens_out)
+            },
+            
+            BondingCurveMessage::SellTokens { token_amount, min_base_out, quoted_base_out } => {
+                self.sell_tokens(caller, context, token_amount, min_base_out, quoted_base_out)
+            },
+            
+            BondingCurveMessage::BuyExactTokens { token_amount, max_base_in, quoted_base_in } => {
+                self.buy_exact_tokens(caller, context, token_amount, max_base_in, quoted_base_in)
+            },
+            
+            BondingCurveMessage::SellForExactBase { max_tokens_in, base_amount, quoted_tokens_in } => {
+                self.sell_for_exact_base(caller, context, max_tokens_in, base_amount, quoted_tokens_in)
             },
             
-            BondingCurveMessage::SellTokens { token_amount, min_base_out } => {
-                self.sell_tokens(token_amount, min_base_out)
+            BondingCurveMessage::ClaimFees { recipient } => {
+                self.claim_fees(context, recipient)
+            },
+            
+            BondingCurveMessage::GetAccruedFees => {
+                self.get_accrued_fees()
+            },
+            
+            BondingCurveMessage::QuoteBuy { token_amount } => {
+                self.quote_buy(token_amount)
+            },
+            
+            BondingCurveMessage::QuoteSell { token_amount } => {
+                self.quote_sell(token_amount)
+            },
+            
+            BondingCurveMessage::QuoteBuyWithBase { base_amount } => {
+                self.quote_buy_with_base(base_amount)
+            },
+            
+            BondingCurveMessage::GetTradeLimits => {
+                self.get_trade_limits()
+            },
+            
+            BondingCurveMessage::RetryGraduation => {
+                self.retry_graduation(caller, context)
+            },
+            
+            BondingCurveMessage::GetGraduationStatus => {
+                self.get_graduation_status()
+            },
+            
+            BondingCurveMessage::GetGraduationProgress => {
+                self.get_graduation_progress()
             },
             
             BondingCurveMessage::GetBuyQuote { token_amount } => {
-                self.get_buy_quote(token_amount)
+                self.get_buy_quote(context, token_amount)
             },
             
             BondingCurveMessage::GetSellQuote { token_amount } => {
-                self.get_sell_quote(token_amount)
+                self.get_sell_quote(context, token_amount)
             },
             
             BondingCurveMessage::Graduate => {
-                self.graduate()
+                self.graduate(caller, context)
             },
             
             BondingCurveMessage::GetCurveState => {
@@ -605,31 +1038,67 @@ impl BondingCurve {
         }
     }
 
-    fn context(&self) -> Result<Context> {
-        // Use current Alkanes API
-        Context::parse(&mut Cursor::new(CONTEXT.transaction()))
-            .map_err(|e| anyhow!("Failed to parse context: {}", e))
-    }
-    
-    // Add missing current_supply method
+    /// Supply the curve prices against: tokens sold, excluding any minted
+    /// into the pool at graduation
     fn current_supply(&self) -> u128 {
-        self.total_supply()
+        bonding_curve::CurveCalculator::sold_supply()
+    }
+
+    /// Blocks elapsed since the curve was initialized
+    fn blocks_since_launch(&self) -> u64 {
+        self.height().saturating_sub(bonding_curve::CurveCalculator::get_launch_block())
+    }
+
+    /// Mark the curve initialized, failing if it already was
+    ///
+    /// Shares `observe_initialization`'s key but writes through the journal,
+    /// so an `Initialize` that fails validation can still be retried.
+    fn observe_curve_initialization() -> Result<()> {
+        let mut initialized = JournaledPointer::from_keyword("/initialized");
+        if !initialized.get().is_empty() {
+            return Err(anyhow!("Bonding curve is already initialized"));
+        }
+        initialized.set(Arc::new(vec![0x01]));
+        Ok(())
     }
 
     /// Initialize the bonding curve with parameters
     fn initialize(
         &self,
+        context: &Context,
         name_part1: u128,
         name_part2: u128,
         symbol: u128,
         base_price: u128,
         growth_rate: u128,
+        growth_steps: u128,
         graduation_threshold: u128,
         base_token_type: u128,
         max_supply: u128,
         lp_distribution_strategy: u128,
+        curve_type: u128,
+        max_price: u128,
+        midpoint_supply: u128,
+        breakpoints: Vec<u128>,
+        virtual_base_reserve: u128,
+        virtual_token_reserve: u128,
+        connector_weight: u128,
+        virtual_supply: u128,
+        fee_schedule: fees::FeeSchedule,
+        creator: AlkaneId,
+        referrer: AlkaneId,
+        treasury: AlkaneId,
+        sell_spread: bonding_curve::SellSpread,
+        limits: trade::TradeLimits,
+        graduation_criteria: Option<graduation::GraduationCriteria>,
+        emergency_graduation: graduation::EmergencyGraduation,
+        price_continuity: graduation::PriceContinuity,
+        lp_reserve_bps: u128,
+        amm_backend: amm_adapter::AmmBackend,
     ) -> Result<CallResponse> {
-        let context = self.context()?;
+        // Initialize runs once; a second call could re-register the fee recipients
+        Self::observe_curve_initialization()?;
+
         let response = CallResponse::forward(&context.incoming_alkanes);
 
         // Validate parameters
@@ -639,34 +1108,83 @@ impl BondingCurve {
             _ => return Err(anyhow!("Invalid base token type")),
         };
 
-        if lp_distribution_strategy > 3 {
-            return Err(anyhow!("Invalid LP distribution strategy (0-3)"));
+        let lp_strategy = amm_integration::LPDistributionStrategy::from_u128(lp_distribution_strategy)?;
+        lp_strategy.ensure_supported()?;
+
+        if breakpoints.len() % 2 != 0 {
+            return Err(anyhow!("Breakpoints must be (supply, price) pairs"));
         }
 
+        // max_supply covers both the curve sale and the LP reserve
+        let (curve_allocation, lp_reserve_supply) = graduation::Tokenomics::split_supply(max_supply, lp_reserve_bps)?;
+
         let params = CurveParams {
             base_price,
             growth_rate,
+            growth_steps,
             graduation_threshold,
             base_token,
-            max_supply,
+            max_supply: curve_allocation,
+            curve_type: CurveType::from_u128(curve_type)?,
+            max_price,
+            midpoint_supply,
+            breakpoints: breakpoints.chunks(2).map(|pair| (pair[0], pair[1])).collect(),
+            virtual_base_reserve,
+            virtual_token_reserve,
+            connector_weight,
+            virtual_supply,
+            fees: fee_schedule,
+            sell_spread,
+            limits,
+            graduation_criteria,
+            emergency_graduation,
+            price_continuity,
+            lp_reserve_supply,
+            amm_backend,
         };
 
+        curve_models::validate(&params)?;
+        params.fees.validate()?;
+        params.sell_spread.validate()?;
+        params.limits.validate()?;
+        params.criteria().validate()?;
+        params.emergency_graduation.validate()?;
+        params.price_continuity.validate()?;
+        params.amm_backend.validate()?;
         bonding_curve::CurveCalculator::set_curve_params(&params)?;
 
         // Set token metadata
         let name = TokenName::new(name_part1, name_part2);
         <Self as MintableToken>::set_name_and_symbol(self, name, symbol);
 
-        // Store LP distribution strategy
-        let mut lp_pointer = StoragePointer::from_keyword("/lp_strategy");
-        lp_pointer.set_value(lp_distribution_strategy as u8);
+        // Store LP distribution strategy where graduation reads it
+        amm_integration::AMMIntegration::set_lp_distribution_strategy(lp_strategy);
 
-        // Initialize reserves to zero
+        // Initialize reserves (virtual token reserve for constant-product curves)
         bonding_curve::CurveCalculator::set_base_reserves(0);
         bonding_curve::CurveCalculator::set_token_reserves(0);
+        bonding_curve::CurveCalculator::sync_token_reserves(0, &params);
+        bonding_curve::CurveCalculator::set_launch_block(self.height());
+
+        // Register fee recipients. Only the recipient itself can claim, so
+        // the factory, which never calls ClaimFees, names a treasury; curves
+        // initialized directly by a contract default to it. Wallets all call
+        // as 0:0, so a wallet deployer must name the treasury
+        let treasury = match treasury {
+            AlkaneId { block: 0, tx: 0 } if fees::FeeEngine::is_wallet_caller(&context.caller) => {
+                return Err(anyhow!("A treasury is required when Initialize is not called by a contract"));
+            },
+            AlkaneId { block: 0, tx: 0 } => context.caller.clone(),
+            treasury => treasury,
+        };
+        fees::FeeEngine::set_recipient(fees::FeeRecipient::Protocol, &treasury)?;
+        fees::FeeEngine::set_recipient(fees::FeeRecipient::Creator, &creator)?;
+        if !fees::FeeEngine::is_wallet_caller(&referrer) {
+            fees::FeeEngine::set_recipient(fees::FeeRecipient::Referrer, &referrer)?;
+        }
 
         // Store token creator
-        let mut creator_pointer = StoragePointer::from_keyword("/token/creator");
+        let mut creator_pointer = JournaledPointer::from_keyword("/token/creator");
         let mut creator_data = Vec::new();
         creator_data.extend_from_slice(&context.myself.block.to_le_bytes());
         creator_data.extend_from_slice(&context.myself.tx.to_le_bytes());
@@ -677,137 +1195,331 @@ impl BondingCurve {
         Ok(response)
     }
 
-    /// Buy tokens with base currency
-    fn buy_tokens(&self, min_tokens_out: u128) -> Result<CallResponse> {
-        let context = self.context()?;
-        let mut response = CallResponse::forward(&context.incoming_alkanes);
+    /// Buy tokens with base currency, refunding whatever the buy does not use
+    fn buy_tokens(
+        &self,
+        caller: &dyn amm_integration::AlkaneCaller,
+        context: &Context,
+        min_tokens_out: u128,
+        quoted_tokens_out: u128,
+    ) -> Result<CallResponse> {
+        let _guard = guard::ReentrancyGuard::enter("buy")?;
 
-        // Check if already graduated
-        if bonding_curve::CurveCalculator::is_graduated() {
-            return Err(anyhow!("Bonding curve has graduated to AMM"));
-        }
+        // Trades only run while the curve is neither graduated nor graduating
+        bonding_curve::CurveCalculator::ensure_trading()?;
 
-        // Get curve parameters and current state
         let params = bonding_curve::CurveCalculator::get_curve_params()?;
-        let current_supply = self.current_supply();
-        
-        // Find the base token input from incoming alkanes
-        let base_input = context.incoming_alkanes.0
-            .iter()
-            .find(|transfer| transfer.id == params.base_token.alkane_id())
-            .ok_or_else(|| anyhow!("No base token input found"))?;
+        let base_id = params.base_token.alkane_id();
 
-        let base_amount = base_input.value;
+        // Find the base token input from incoming alkanes
+        let mut parcel = parcel::ParcelLedger::new(&context.incoming_alkanes)?;
+        let base_amount = parcel.available(&base_id);
+        if base_amount == 0 {
+            return Err(anyhow!("No base token input found"));
+        }
 
-        // Calculate how many tokens to mint for this amount
-        let tokens_to_mint = self.calculate_tokens_for_base_amount(base_amount, &params)?;
+        // The trading fee is charged on top of the curve cost
+        let plan = trade::TradePlan::buy_with_base(&self.curve_state(), &params, base_amount)?;
 
         // Check slippage protection
-        if tokens_to_mint < min_tokens_out {
+        if plan.tokens < min_tokens_out {
             return Err(anyhow!("Slippage exceeded: got {} tokens, expected at least {}", 
-                tokens_to_mint, min_tokens_out));
+                plan.tokens, min_tokens_out));
         }
+        params.limits.check_min_out(min_tokens_out, quoted_tokens_out)?;
 
-        // Mint the tokens
-        response.alkanes.0.push(self.mint(&context, tokens_to_mint)?);
+        // Consume the cost and fee; the dust, including base a partial fill at
+        // max supply could not use, stays in the parcel and is returned
+        parcel.debit(&base_id, plan.trader_base)?;
+        let minted = self.mint(context, plan.tokens)?;
+        parcel.credit(&minted.id, minted.value)?;
 
-        // Update reserves
-        let current_reserves = bonding_curve::CurveCalculator::get_base_reserves();
-        bonding_curve::CurveCalculator::set_base_reserves(current_reserves + base_amount);
+        // Only the exact cost enters reserves
+        let graduation = self.execute_plan(caller, context, &plan, &params)?;
 
-        // Check for graduation after purchase
-        let new_supply = current_supply + tokens_to_mint;
-        if bonding_curve::CurveCalculator::check_graduation_criteria(new_supply, current_reserves + base_amount, &params) {
-            // Trigger graduation
-            let _ = amm_integration::AMMIntegration::graduate_to_amm(&context, new_supply);
+        Ok(Self::with_graduation(parcel.into_response(), graduation))
+    }
+
+    /// Sell tokens for base currency
+    fn sell_tokens(
+        &self,
+        caller: &dyn amm_integration::AlkaneCaller,
+        context: &Context,
+        token_amount: u128,
+        min_base_out: u128,
+        quoted_base_out: u128,
+    ) -> Result<CallResponse> {
+        let _guard = guard::ReentrancyGuard::enter("sell")?;
+
+        // Trades only run while the curve is neither graduated nor graduating
+        bonding_curve::CurveCalculator::ensure_trading()?;
+
+        // Only tokens the seller actually sent can be sold
+        let mut parcel = Self::take_sold_tokens(context, token_amount)?;
+
+        // The seller receives the curve proceeds net of spread and fee
+        let params = bonding_curve::CurveCalculator::get_curve_params()?;
+        let plan = trade::TradePlan::sell_exact(&self.curve_state(), &params, token_amount)?;
+
+        // Check slippage protection
+        if plan.trader_base < min_base_out {
+            return Err(anyhow!("Slippage exceeded: got {} base tokens, expected at least {}", 
+                plan.trader_base, min_base_out));
         }
+        params.limits.check_min_out(min_base_out, quoted_base_out)?;
 
-        Ok(response)
+        // Return base tokens to seller
+        parcel.credit(&params.base_token.alkane_id(), plan.trader_base)?;
+        let graduation = self.execute_plan(caller, context, &plan, &params)?;
+
+        Ok(Self::with_graduation(parcel.into_response(), graduation))
     }
 
-    /// Sell tokens for base currency
-    fn sell_tokens(&self, token_amount: u128, min_base_out: u128) -> Result<CallResponse> {
-        let context = self.context()?;
-        let mut response = CallResponse::forward(&context.incoming_alkanes);
+    /// Buy exactly `token_amount` tokens, refunding whatever base is left
+    fn buy_exact_tokens(
+        &self,
+        caller: &dyn amm_integration::AlkaneCaller,
+        context: &Context,
+        token_amount: u128,
+        max_base_in: u128,
+        quoted_base_in: u128,
+    ) -> Result<CallResponse> {
+        let _guard = guard::ReentrancyGuard::enter("buy")?;
 
-        // Check if already graduated
-        if bonding_curve::CurveCalculator::is_graduated() {
-            return Err(anyhow!("Bonding curve has graduated to AMM"));
+        // Trades only run while the curve is neither graduated nor graduating
+        bonding_curve::CurveCalculator::ensure_trading()?;
+
+        let params = bonding_curve::CurveCalculator::get_curve_params()?;
+        let base_id = params.base_token.alkane_id();
+
+        let mut parcel = parcel::ParcelLedger::new(&context.incoming_alkanes)?;
+        let base_amount = parcel.available(&base_id);
+        if base_amount == 0 {
+            return Err(anyhow!("No base token input found"));
+        }
+
+        let plan = trade::TradePlan::buy_exact(&self.curve_state(), &params, token_amount)?;
+
+        // Check slippage protection (fee included)
+        if plan.trader_base > max_base_in {
+            return Err(anyhow!("Slippage exceeded: costs {} base tokens, expected at most {}",
+                plan.trader_base, max_base_in));
+        }
+        params.limits.check_max_in(max_base_in, quoted_base_in)?;
+        if plan.trader_base > base_amount {
+            return Err(anyhow!("Insufficient base input: costs {}, received {}",
+                plan.trader_base, base_amount));
         }
 
-        // Get curve parameters and calculate sell price
+        // Unspent base stays in the parcel and is refunded
+        parcel.debit(&base_id, plan.trader_base)?;
+        let minted = self.mint(context, token_amount)?;
+        parcel.credit(&minted.id, minted.value)?;
+
+        // Only the exact cost enters reserves
+        let graduation = self.execute_plan(caller, context, &plan, &params)?;
+
+        Ok(Self::with_graduation(parcel.into_response(), graduation))
+    }
+
+    /// Sell as few tokens as needed to receive exactly `base_amount`,
+    /// returning the unused tokens
+    fn sell_for_exact_base(
+        &self,
+        caller: &dyn amm_integration::AlkaneCaller,
+        context: &Context,
+        max_tokens_in: u128,
+        base_amount: u128,
+        quoted_tokens_in: u128,
+    ) -> Result<CallResponse> {
+        let _guard = guard::ReentrancyGuard::enter("sell")?;
+
+        // Trades only run while the curve is neither graduated nor graduating
+        bonding_curve::CurveCalculator::ensure_trading()?;
+
         let params = bonding_curve::CurveCalculator::get_curve_params()?;
-        let current_supply = self.current_supply();
-        
-        // Calculate base tokens to return
-        let base_payout = bonding_curve::CurveCalculator::calculate_sell_price(
-            current_supply, token_amount, &params
-        )?;
+        let plan = trade::TradePlan::sell_for_base(&self.curve_state(), &params, base_amount)?;
 
         // Check slippage protection
-        if base_payout < min_base_out {
-            return Err(anyhow!("Slippage exceeded: got {} base tokens, expected at least {}", 
-                base_payout, min_base_out));
+        if plan.tokens > max_tokens_in {
+            return Err(anyhow!("Slippage exceeded: needs {} tokens, expected at most {}",
+                plan.tokens, max_tokens_in));
+        }
+        params.limits.check_max_in(max_tokens_in, quoted_tokens_in)?;
+
+        // Unused tokens go back to the seller
+        let mut parcel = Self::take_sold_tokens(context, plan.tokens)?;
+        parcel.credit(&params.base_token.alkane_id(), base_amount)?;
+
+        // Burn the sold tokens and release the base plus fee and spread
+        let graduation = self.execute_plan(caller, context, &plan, &params)?;
+
+        Ok(Self::with_graduation(parcel.into_response(), graduation))
+    }
+
+    /// Curve state trades are planned against
+    fn curve_state(&self) -> trade::CurveState {
+        trade::CurveState {
+            supply: self.current_supply(),
+            base_reserves: bonding_curve::CurveCalculator::get_base_reserves(),
+            blocks_since_launch: self.blocks_since_launch(),
+            has_referrer: fees::FeeEngine::has_referrer(),
+            buys: bonding_curve::CurveCalculator::get_buy_count(),
         }
+    }
 
-        // Check we have enough reserves
-        let current_reserves = bonding_curve::CurveCalculator::get_base_reserves();
-        if base_payout > current_reserves {
-            return Err(anyhow!("Insufficient reserves for sell"));
+    /// Graduation metrics of the curve as it stands
+    fn graduation_metrics(&self) -> graduation::GraduationMetrics {
+        let state = self.curve_state();
+        graduation::GraduationMetrics {
+            supply: state.supply,
+            base_reserves: state.base_reserves,
+            blocks_since_launch: state.blocks_since_launch,
+            buys: state.buys,
         }
+    }
 
-        // Burn the tokens (decrease total supply)
-        let new_supply = current_supply.checked_sub(token_amount)
-            .ok_or_else(|| anyhow!("Cannot burn more tokens than exist"))?;
-        self.set_total_supply(new_supply);
+    /// Apply a planned trade's state changes; buys mint their tokens separately
+    ///
+    /// Returns the graduation's response when the trade graduated the curve.
+    fn execute_plan(
+        &self,
+        caller: &dyn amm_integration::AlkaneCaller,
+        context: &Context,
+        plan: &trade::TradePlan,
+        params: &CurveParams,
+    ) -> Result<Option<CallResponse>> {
+        let metrics = plan.graduation_metrics(&self.curve_state());
+
+        // Fees and spread accrue outside the reserves
+        fees::FeeEngine::accrue(&plan.fee)?;
+        if plan.side == trade::TradeSide::Sell {
+            bonding_curve::CurveCalculator::apply_sell_spread(plan.spread, params)?;
+            self.set_total_supply(plan.supply_after);
+        }
 
-        // Return base tokens to seller
-        response.alkanes.0.push(AlkaneTransfer {
-            id: params.base_token.alkane_id(),
-            value: base_payout,
-        });
+        bonding_curve::CurveCalculator::set_base_reserves(plan.reserves_after);
+        bonding_curve::CurveCalculator::sync_token_reserves(plan.supply_after, params);
+        if plan.side == trade::TradeSide::Buy {
+            bonding_curve::CurveCalculator::record_buy()?;
+        }
 
-        // Update reserves
-        bonding_curve::CurveCalculator::set_base_reserves(current_reserves - base_payout);
+        // Graduate once the purchase meets the criteria. An attempt that
+        // fails before the pool takes any liquidity does not undo the trade:
+        // it leaves the curve `Failed` with the reason recorded, still
+        // trading, and later buys or RetryGraduation try again. One that
+        // fails after reverts the whole call
+        if !plan.graduates {
+            return Ok(None);
+        }
+        Ok(amm_integration::AMMIntegration::attempt_graduation(caller, context, &metrics)?.ok())
+    }
 
-        Ok(response)
+    /// Merge a graduation's response into `response`: its alkanes are added
+    /// and its data (pool id and locked LP tokens) becomes the response data
+    fn with_graduation(mut response: CallResponse, graduation: Option<CallResponse>) -> CallResponse {
+        if let Some(graduation) = graduation {
+            response.alkanes.0.extend(graduation.alkanes.0);
+            response.data = graduation.data;
+        }
+        response
     }
 
-    /// Calculate tokens to mint for a given base amount
-    fn calculate_tokens_for_base_amount(&self, base_amount: u128, params: &CurveParams) -> Result<u128> {
-        let current_supply = self.current_supply();
-        
-        // Binary search to find the right number of tokens
-        let mut low = 0u128;
-        let mut high = params.max_supply.saturating_sub(current_supply);
-        let mut best_tokens = 0u128;
-
-        while low <= high {
-            let mid = (low + high) / 2;
-            let cost = bonding_curve::CurveCalculator::calculate_buy_price(current_supply, mid, params)?;
-            
-            if cost <= base_amount {
-                best_tokens = mid;
-                low = mid + 1;
-            } else {
-                high = mid.saturating_sub(1);
-            }
-
-            if low > high {
-                break;
-            }
+    /// Take `token_amount` of the curve's own token from the incoming parcel
+    ///
+    /// Fails unless the seller actually sent the tokens being sold; any sent
+    /// beyond `token_amount` stay in the returned ledger.
+    fn take_sold_tokens(context: &Context, token_amount: u128) -> Result<parcel::ParcelLedger> {
+        let mut parcel = parcel::ParcelLedger::new(&context.incoming_alkanes)?;
+        let received = parcel.available(&context.myself);
+        if received == 0 {
+            return Err(anyhow!("No token input found"));
         }
+        if token_amount > received {
+            return Err(anyhow!("Insufficient token input: selling {}, received {}",
+                token_amount, received));
+        }
+
+        parcel.debit(&context.myself, token_amount)?;
+        Ok(parcel)
+    }
 
-        if best_tokens == 0 {
-            return Err(anyhow!("Insufficient base amount to buy any tokens"));
+    /// Pay out a fee bucket to its registered recipient
+    fn claim_fees(&self, context: &Context, recipient: u128) -> Result<CallResponse> {
+        let params = bonding_curve::CurveCalculator::get_curve_params()?;
+
+        fees::FeeEngine::claim(
+            context,
+            fees::FeeRecipient::from_u128(recipient)?,
+            params.base_token.alkane_id(),
+        )
+    }
+
+    /// Get accrued fees as protocol, creator and referrer u128s
+    fn get_accrued_fees(&self) -> Result<CallResponse> {
+        let mut data = Vec::with_capacity(48);
+        for recipient in [fees::FeeRecipient::Protocol, fees::FeeRecipient::Creator, fees::FeeRecipient::Referrer] {
+            data.extend_from_slice(&fees::FeeEngine::get_accrued(recipient).to_le_bytes());
         }
 
-        Ok(best_tokens)
+        let mut response = CallResponse::default();
+        response.data = data;
+        Ok(response)
+    }
+
+    /// Quote buying `token_amount` tokens as the trade would execute now
+    fn quote_buy(&self, token_amount: u128) -> Result<CallResponse> {
+        let params = bonding_curve::CurveCalculator::get_curve_params()?;
+        let plan = trade::TradePlan::buy_exact(&self.curve_state(), &params, token_amount)?;
+
+        let mut response = CallResponse::default();
+        response.data = plan.to_quote()?;
+        Ok(response)
+    }
+
+    /// Quote selling `token_amount` tokens as the trade would execute now
+    fn quote_sell(&self, token_amount: u128) -> Result<CallResponse> {
+        let params = bonding_curve::CurveCalculator::get_curve_params()?;
+        let plan = trade::TradePlan::sell_exact(&self.curve_state(), &params, token_amount)?;
+
+        let mut response = CallResponse::default();
+        response.data = plan.to_quote()?;
+        Ok(response)
+    }
+
+    /// Quote spending `base_amount` as `buy_tokens` would execute it now
+    fn quote_buy_with_base(&self, base_amount: u128) -> Result<CallResponse> {
+        let params = bonding_curve::CurveCalculator::get_curve_params()?;
+        let plan = trade::TradePlan::buy_with_base(&self.curve_state(), &params, base_amount)?;
+
+        let mut response = CallResponse::default();
+        response.data = plan.to_quote()?;
+        Ok(response)
+    }
+
+    /// Get the trade limits as min buy amount, max buy bps, max sell bps and
+    /// max slippage bps, followed by the current max buy and sell in tokens
+    fn get_trade_limits(&self) -> Result<CallResponse> {
+        let params = bonding_curve::CurveCalculator::get_curve_params()?;
+        let limits = &params.limits;
+        let supply = self.current_supply();
+
+        let fields = [
+            limits.min_buy_amount,
+            limits.max_buy_bps,
+            limits.max_sell_bps,
+            limits.max_slippage_bps,
+            limits.max_buy_tokens(supply, params.max_supply)?,
+            limits.max_sell_tokens(supply)?,
+        ];
+
+        let mut response = CallResponse::default();
+        response.data = fields.iter().flat_map(|field| field.to_le_bytes()).collect();
+        Ok(response)
     }
 
     /// Get buy quote for token amount
-    fn get_buy_quote(&self, token_amount: u128) -> Result<CallResponse> {
-        let context = self.context()?;
+    fn get_buy_quote(&self, context: &Context, token_amount: u128) -> Result<CallResponse> {
         let mut response = CallResponse::forward(&context.incoming_alkanes);
 
         let params = bonding_curve::CurveCalculator::get_curve_params()?;
@@ -822,15 +1534,14 @@ impl BondingCurve {
     }
 
     /// Get sell quote for token amount
-    fn get_sell_quote(&self, token_amount: u128) -> Result<CallResponse> {
-        let context = self.context()?;
+    fn get_sell_quote(&self, context: &Context, token_amount: u128) -> Result<CallResponse> {
         let mut response = CallResponse::forward(&context.incoming_alkanes);
 
         let params = bonding_curve::CurveCalculator::get_curve_params()?;
         let current_supply = self.current_supply();
         
-        let payout = bonding_curve::CurveCalculator::calculate_sell_price(
-            current_supply, token_amount, &params
+        let (payout, _) = bonding_curve::CurveCalculator::sell_payout_at(
+            current_supply, token_amount, &params, self.blocks_since_launch()
         )?;
 
         response.data = payout.to_le_bytes().to_vec();
@@ -838,11 +1549,92 @@ impl BondingCurve {
     }
 
     /// Attempt graduation to AMM
-    fn graduate(&self) -> Result<CallResponse> {
-        let context = self.context()?;
-        let current_supply = self.current_supply();
+    ///
+    /// Reverts when the curve is not due to graduate. Once it is, a failure
+    /// before the pool takes any liquidity is recorded as `retry_graduation`
+    /// records it, and the response carries the resulting status as
+    /// `get_graduation_status` does; a later failure reverts the call. On
+    /// success the response carries the pool id and the locked LP tokens.
+    fn graduate(&self, caller: &dyn amm_integration::AlkaneCaller, context: &Context) -> Result<CallResponse> {
+        let _guard = guard::ReentrancyGuard::enter("graduation")?;
+
+        let params = bonding_curve::CurveCalculator::get_curve_params()?;
+        let metrics = self.graduation_metrics();
+        amm_integration::AMMIntegration::ensure_graduation_due(&metrics, &params)?;
+
+        let mut response = CallResponse::forward(&context.incoming_alkanes);
+        match amm_integration::AMMIntegration::attempt_graduation(caller, context, &metrics)? {
+            Ok(graduation) => response = Self::with_graduation(response, Some(graduation)),
+            Err(_) => response.data = Self::graduation_status(),
+        }
+        Ok(response)
+    }
 
-        amm_integration::AMMIntegration::graduate_to_amm(&context, current_supply)
+    /// Retry a pending or failed graduation
+    ///
+    /// Succeeds when the attempt fails before the pool takes any liquidity,
+    /// so the failure's reason is kept rather than reverted with the call; a
+    /// later failure reverts it. The response carries the resulting status
+    /// as `get_graduation_status` does.
+    fn retry_graduation(&self, caller: &dyn amm_integration::AlkaneCaller, context: &Context) -> Result<CallResponse> {
+        let _guard = guard::ReentrancyGuard::enter("graduation")?;
+
+        let state = bonding_curve::CurveCalculator::graduation_state();
+        if !state.is_retryable() {
+            return Err(anyhow!("No graduation to retry: curve is {:?}", state));
+        }
+
+        let mut response = CallResponse::forward(&context.incoming_alkanes);
+        let _ = amm_integration::AMMIntegration::attempt_graduation(caller, context, &self.graduation_metrics())?;
+        response.data = Self::graduation_status();
+        Ok(response)
+    }
+
+    /// Get the graduation state byte followed by the last failure reason
+    fn get_graduation_status(&self) -> Result<CallResponse> {
+        let mut response = CallResponse::default();
+        response.data = Self::graduation_status();
+        Ok(response)
+    }
+
+    /// Get the graduation criteria and progress toward each condition
+    fn get_graduation_progress(&self) -> Result<CallResponse> {
+        let params = bonding_curve::CurveCalculator::get_curve_params()?;
+        let criteria = params.criteria();
+        let metrics = self.graduation_metrics();
+
+        let progress = serde_json::json!({
+            "mode": criteria.mode,
+            "met": criteria.is_met(&metrics, &params),
+            "state": bonding_curve::CurveCalculator::graduation_state(),
+            "conditions": criteria.progress(&metrics, &params),
+            "emergency": {
+                "delay_blocks": params.emergency_graduation.delay_blocks,
+                "min_reserves": params.emergency_graduation.min_reserves,
+                "eligible": params.emergency_graduation.is_eligible(
+                    metrics.blocks_since_launch,
+                    metrics.supply,
+                    metrics.base_reserves,
+                ),
+            },
+            "price_tolerance_bps": params.price_continuity.tolerance_bps,
+            "amm_backend": params.amm_backend,
+        });
+        let data = serde_json::to_vec(&progress)
+            .map_err(|e| anyhow!("Failed to serialize graduation progress: {}", e))?;
+
+        let mut response = CallResponse::default();
+        response.data = data;
+        Ok(response)
+    }
+
+    /// State byte (0 trading, 1 graduated, 2 pending, 3 failed) and UTF-8 failure reason
+    fn graduation_status() -> Vec<u8> {
+        let mut data = vec![bonding_curve::CurveCalculator::graduation_state().as_u8()];
+        if let Some(reason) = bonding_curve::CurveCalculator::graduation_failure() {
+            data.extend_from_slice(reason.as_bytes());
+        }
+        data
     }
 
     /// Get curve state information
@@ -851,15 +1643,32 @@ impl BondingCurve {
         let base_reserves = bonding_curve::CurveCalculator::get_base_reserves();
         let is_graduated = bonding_curve::CurveCalculator::is_graduated();
         let amm_pool = amm_integration::AMMIntegration::get_amm_pool_address();
+        let params = bonding_curve::CurveCalculator::get_curve_params()?;
         
         let state = serde_json::json!({
+            "curve_type": params.curve_type,
             "current_supply": current_supply,
             "base_reserves": base_reserves,
+            "token_reserves": bonding_curve::CurveCalculator::get_token_reserves(),
+            "fees": params.fees,
+            "sell_spread": params.sell_spread,
+            "sell_spread_bps": params.sell_spread.bps_at(self.blocks_since_launch()),
+            "limits": params.limits,
+            "graduation_criteria": params.criteria(),
+            "burned_spread": bonding_curve::CurveCalculator::get_burned_spread(),
+            "accrued_fees": {
+                "protocol": fees::FeeEngine::get_accrued(fees::FeeRecipient::Protocol),
+                "creator": fees::FeeEngine::get_accrued(fees::FeeRecipient::Creator),
+                "referrer": fees::FeeEngine::get_accrued(fees::FeeRecipient::Referrer),
+            },
             "is_graduated": is_graduated,
+            "graduation_state": bonding_curve::CurveCalculator::graduation_state(),
+            "graduation_failure": bonding_curve::CurveCalculator::graduation_failure(),
             "amm_pool": amm_pool.map(|id| format!("{}:{}", id.block, id.tx)),
             "token_name": self.name(),
             "token_symbol": self.symbol(),
             "total_supply": self.total_supply(),
+            "tokenomics": bonding_curve::CurveCalculator::tokenomics(&params),
         });
         
         let data = serde_json::to_vec(&state)
@@ -946,3 +1755,9 @@ impl BondingCurve {
 }
 
 impl AlkaneResponder for BondingCurve {}
+
+impl amm_integration::AlkaneCaller for BondingCurve {
+    fn call_alkane(&self, cellpack: &Cellpack, outgoing: &AlkaneTransferParcel) -> Result<CallResponse> {
+        self.call(cellpack, outgoing, self.fuel())
+    }
+}
@@ -0,0 +1,141 @@
+//! Parcel Accounting
+//!
+//! A call to the curve receives a parcel of alkanes and must hand back
+//! everything it does not keep. `ParcelLedger` starts from the incoming
+//! parcel, debits exactly what a trade consumes, credits exactly what it pays
+//! out, and turns the rest into the response, so unrelated alkanes are
+//! forwarded untouched and nothing consumed is echoed back.
+
+use alkanes_support::id::AlkaneId;
+use alkanes_support::parcel::{AlkaneTransfer, AlkaneTransferParcel};
+use alkanes_support::response::CallResponse;
+use alkanes_support::utils::overflow_error;
+use anyhow::{anyhow, Result};
+
+/// Running balance of one call's alkanes, by id in arrival order
+#[derive(Debug, Clone, Default)]
+pub struct ParcelLedger {
+    balances: Vec<AlkaneTransfer>,
+}
+
+impl ParcelLedger {
+    /// Start from the incoming parcel, merging transfers of the same id
+    pub fn new(incoming: &AlkaneTransferParcel) -> Result<Self> {
+        let mut ledger = Self::default();
+        for transfer in incoming.0.iter() {
+            ledger.credit(&transfer.id, transfer.value)?;
+        }
+        Ok(ledger)
+    }
+
+    fn entry(&mut self, id: &AlkaneId) -> Option<&mut AlkaneTransfer> {
+        self.balances.iter_mut().find(|balance| &balance.id == id)
+    }
+
+    /// Amount of `id` still held
+    pub fn available(&self, id: &AlkaneId) -> u128 {
+        self.balances
+            .iter()
+            .find(|balance| &balance.id == id)
+            .map_or(0, |balance| balance.value)
+    }
+
+    /// Consume `amount` of `id`; fails if the parcel holds less
+    pub fn debit(&mut self, id: &AlkaneId, amount: u128) -> Result<()> {
+        let available = self.available(id);
+        if amount > available {
+            return Err(anyhow!("Insufficient {}:{} input: needs {}, received {}",
+                id.block, id.tx, amount, available));
+        }
+        if let Some(balance) = self.entry(id) {
+            balance.value -= amount;
+        }
+        Ok(())
+    }
+
+    /// Add `amount` of `id` to what the call returns
+    pub fn credit(&mut self, id: &AlkaneId, amount: u128) -> Result<()> {
+        match self.entry(id) {
+            Some(balance) => balance.value = overflow_error(balance.value.checked_add(amount))?,
+            None => self.balances.push(AlkaneTransfer { id: id.clone(), value: amount }),
+        }
+        Ok(())
+    }
+
+    /// Response returning every remaining balance
+    pub fn into_response(self) -> CallResponse {
+        let mut response = CallResponse::default();
+        response.alkanes.0.extend(self.balances.into_iter().filter(|balance| balance.value > 0));
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parcel(transfers: &[(AlkaneId, u128)]) -> AlkaneTransferParcel {
+        AlkaneTransferParcel(
+            transfers
+                .iter()
+                .map(|(id, value)| AlkaneTransfer { id: id.clone(), value: *value })
+                .collect(),
+        )
+    }
+
+    fn returned(response: &CallResponse, id: &AlkaneId) -> u128 {
+        response.alkanes.0
+            .iter()
+            .filter(|transfer| &transfer.id == id)
+            .map(|transfer| transfer.value)
+            .sum()
+    }
+
+    #[test]
+    fn test_unrelated_alkanes_are_forwarded() {
+        let (base, token, other) = (AlkaneId::new(2, 56801), AlkaneId::new(2, 1000), AlkaneId::new(4, 7));
+        let mut ledger = ParcelLedger::new(&parcel(&[(base.clone(), 1_000), (other.clone(), 5)])).unwrap();
+
+        ledger.debit(&base, 990).unwrap();
+        ledger.credit(&token, 42).unwrap();
+
+        let response = ledger.into_response();
+        assert_eq!(returned(&response, &base), 10);
+        assert_eq!(returned(&response, &token), 42);
+        assert_eq!(returned(&response, &other), 5);
+    }
+
+    #[test]
+    fn test_consumed_input_is_not_returned() {
+        let base = AlkaneId::new(2, 56801);
+        let mut ledger = ParcelLedger::new(&parcel(&[(base.clone(), 1_000)])).unwrap();
+
+        ledger.debit(&base, 1_000).unwrap();
+        assert!(ledger.into_response().alkanes.0.is_empty());
+    }
+
+    #[test]
+    fn test_split_inputs_merge() {
+        let token = AlkaneId::new(2, 1000);
+        let mut ledger = ParcelLedger::new(&parcel(&[(token.clone(), 600), (token.clone(), 500)])).unwrap();
+        assert_eq!(ledger.available(&token), 1_100);
+
+        ledger.debit(&token, 1_000).unwrap();
+        let response = ledger.into_response();
+        assert_eq!(response.alkanes.0.len(), 1);
+        assert_eq!(returned(&response, &token), 100);
+    }
+
+    #[test]
+    fn test_debit_beyond_input_fails() {
+        let (base, other) = (AlkaneId::new(2, 56801), AlkaneId::new(4, 7));
+        let mut ledger = ParcelLedger::new(&parcel(&[(other.clone(), 1_000)])).unwrap();
+
+        assert!(ledger.debit(&base, 1).is_err());
+        assert!(ledger.debit(&other, 1_001).is_err());
+
+        // A failed debit leaves the balance alone
+        assert_eq!(ledger.available(&other), 1_000);
+        assert!(ledger.debit(&base, 0).is_ok());
+    }
+}
@@ -1,7 +1,7 @@
 //! Bonding curve trading and pricing tests
 
 use super::*;
-use crate::{bonding_curve::CurveCalculator, CurveParams, constants::*};
+use crate::{bonding_curve::CurveCalculator, graduation::GraduationMetrics, CurveParams, constants::*};
 
 #[cfg(test)]
 mod curve_pricing_tests {
@@ -15,6 +15,7 @@ mod curve_pricing_tests {
             graduation_threshold: 10_000_000_000,
             base_token: BaseToken::BUSD,
             max_supply: 1_000_000_000,
+            ..CurveParams::default()
         };
 
         // Test buying first token
@@ -24,7 +25,13 @@ mod curve_pricing_tests {
 
         // Test buying 100 tokens
         let price_100 = CurveCalculator::calculate_buy_price(0, 100, &params).unwrap();
-        assert!(price_100 > price * 100); // Should be more due to curve
+        assert!(price_100 >= params.base_price * 100);
+
+        // Each 1% of max supply costs 1.5% more than the one before
+        let step = params.max_supply / params.growth_steps;
+        let first_step = CurveCalculator::calculate_buy_price(0, step, &params).unwrap();
+        let second_step = CurveCalculator::calculate_buy_price(step, step, &params).unwrap();
+        assert!(second_step.abs_diff(first_step * 1015 / 1000) <= first_step / 1_000_000);
 
         // Test buying at higher supply levels
         let price_high = CurveCalculator::calculate_buy_price(100_000, 1000, &params).unwrap();
@@ -54,22 +61,51 @@ mod curve_pricing_tests {
             graduation_threshold: 10_000_000_000,
             base_token: BaseToken::BUSD,
             max_supply: 1_000_000_000,
+            ..CurveParams::default()
         };
 
+        // Growth applies once per 1% of max supply
+        let step = params.max_supply / params.growth_steps;
         let price_0 = CurveCalculator::price_at_supply(0, &params).unwrap();
-        let price_10 = CurveCalculator::price_at_supply(10, &params).unwrap();
-        let price_100 = CurveCalculator::price_at_supply(100, &params).unwrap();
-        let price_1000 = CurveCalculator::price_at_supply(1000, &params).unwrap();
+        let price_1 = CurveCalculator::price_at_supply(step, &params).unwrap();
+        let price_10 = CurveCalculator::price_at_supply(10 * step, &params).unwrap();
+        let price_100 = CurveCalculator::price_at_supply(100 * step, &params).unwrap();
 
         assert_eq!(price_0, params.base_price);
-        assert!(price_10 > price_0);
+        assert!(price_1.abs_diff(1_010_000) <= 1);
+        assert!(price_10 > price_1);
         assert!(price_100 > price_10);
-        assert!(price_1000 > price_100);
 
         // Verify exponential growth
         let ratio_10 = price_10 as f64 / price_0 as f64;
         let expected_ratio = 1.01_f64.powi(10);
-        assert!((ratio_10 - expected_ratio).abs() < 0.1);
+        assert!((ratio_10 - expected_ratio).abs() < 1e-6);
+        assert_eq!(price_100, 2_704_813);
+
+        // A partial step grows by the matching fractional power
+        let half = CurveCalculator::price_at_supply(step / 2, &params).unwrap();
+        assert_eq!(half, 1_004_987);
+    }
+
+    #[test]
+    fn test_zero_growth_steps_compound_per_base_unit() {
+        // Curves stored before growth_steps existed read it as 0
+        let params = CurveParams {
+            base_price: 1_000_000,
+            growth_rate: 100, // 1% growth
+            growth_steps: 0,
+            max_supply: 1_000_000_000,
+            ..CurveParams::default()
+        };
+
+        let price_1 = CurveCalculator::price_at_supply(1, &params).unwrap();
+        let price_2 = CurveCalculator::price_at_supply(2, &params).unwrap();
+        let price_10 = CurveCalculator::price_at_supply(10, &params).unwrap();
+
+        assert!(price_1.abs_diff(1_010_000) <= 1);
+        assert!(price_2.abs_diff(1_020_100) <= 1);
+        let ratio_10 = price_10 as f64 / params.base_price as f64;
+        assert!((ratio_10 - 1.01_f64.powi(10)).abs() < 1e-6);
     }
 
     #[test]
@@ -80,6 +116,7 @@ mod curve_pricing_tests {
             graduation_threshold: 10_000_000_000,
             base_token: BaseToken::BUSD,
             max_supply: 1000, // Small max supply for testing
+            ..CurveParams::default()
         };
 
         // Should succeed
@@ -114,10 +151,11 @@ mod curve_pricing_tests {
             graduation_threshold: 100_000_000, // Low threshold for testing
             base_token: BaseToken::BUSD,
             max_supply: 1_000_000_000,
+            ..CurveParams::default()
         };
 
         // Low supply, low reserves - should not graduate
-        assert!(!CurveCalculator::check_graduation_criteria(100, 1_000_000, &params));
+        assert!(!CurveCalculator::check_graduation_criteria(&GraduationMetrics::at(50, 1_000_000), &params));
 
         // High market cap - should graduate
         let high_supply = 1_000_000;
@@ -125,7 +163,7 @@ mod curve_pricing_tests {
         let market_cap = high_supply * price;
         
         if market_cap >= params.graduation_threshold {
-            assert!(CurveCalculator::check_graduation_criteria(high_supply, 0, &params));
+            assert!(CurveCalculator::check_graduation_criteria(&GraduationMetrics::at(high_supply, 0), &params));
         }
     }
 
@@ -137,11 +175,12 @@ mod curve_pricing_tests {
             graduation_threshold: 10_000_000_000,
             base_token: BaseToken::BUSD,
             max_supply: 1_000_000_000,
+            ..CurveParams::default()
         };
 
         // High liquidity reserves - should graduate
         let high_reserves = params.graduation_threshold / 2;
-        assert!(CurveCalculator::check_graduation_criteria(1000, high_reserves, &params));
+        assert!(CurveCalculator::check_graduation_criteria(&GraduationMetrics::at(1000, high_reserves), &params));
     }
 
     #[test]
@@ -171,6 +210,38 @@ mod curve_pricing_tests {
         assert!(diff < bulk_price / 1000); // Less than 0.1% difference
     }
 
+    #[test]
+    fn test_large_trade_matches_closed_form() {
+        let params = CurveParams {
+            base_price: 1_000_000,
+            growth_rate: 100, // 1% growth
+            graduation_threshold: 10_000_000_000,
+            base_token: BaseToken::BUSD,
+            max_supply: 1_000_000_000,
+            ..CurveParams::default()
+        };
+
+        // step * base * (1 + r)^(a / step) * ((1 + r)^((b - a) / step) - 1) / ln(1 + r)
+        let step = params.max_supply / params.growth_steps;
+        let expected = |a: u128, b: u128| {
+            let ln_g = 1.01_f64.ln();
+            let (steps, span) = (a as f64 / step as f64, (b - a) as f64 / step as f64);
+            1_000_000.0 * step as f64 * (steps * ln_g).exp() * (span * ln_g).exp_m1() / ln_g
+        };
+
+        for (supply, amount) in [(0u128, 10 * step), (5 * step, 20 * step), (20 * step, step / 2)] {
+            let cost = CurveCalculator::calculate_buy_price(supply, amount, &params).unwrap();
+            let exact = expected(supply, supply + amount);
+            let error = (cost as f64 - exact).abs() / exact;
+            assert!(error < 1e-9, "relative error {} too large", error);
+        }
+
+        // Sells use the same integral, rounded down, less the 1% discount
+        let gross = expected(10 * step, 20 * step);
+        let payout = CurveCalculator::calculate_sell_price(20 * step, 10 * step, &params).unwrap();
+        assert!(((payout as f64) - gross * 0.99).abs() / gross < 1e-9);
+    }
+
     #[test]
     fn test_slippage_calculation() {
         let params = CurveParams::default();
@@ -224,6 +295,7 @@ mod curve_pricing_tests {
             graduation_threshold: u128::MAX / 100,
             base_token: BaseToken::BUSD,
             max_supply: 1000,
+            ..CurveParams::default()
         };
 
         // Should handle without overflow
@@ -237,9 +309,364 @@ mod curve_pricing_tests {
             graduation_threshold: 100_000_000_000_000, // $1M
             base_token: BaseToken::BUSD,
             max_supply: 100_000_000_000, // 100B tokens
+            ..CurveParams::default()
         };
         
         let result = CurveCalculator::price_at_supply(1_000_000, &params);
         assert!(result.is_ok());
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod curve_model_tests {
+    use super::*;
+    use crate::bonding_curve::{SellSpread, SpreadDestination, MAX_SELL_SPREAD_BPS};
+    use crate::{curve_models, fixed_point, CurveType};
+
+    fn shaped_params(curve_type: CurveType) -> CurveParams {
+        CurveParams {
+            base_price: 1_000,
+            growth_rate: 2_000, // Sigmoid exponent runs from -10 to 10 across the supply
+            max_supply: 1_000_000,
+            curve_type,
+            max_price: 101_000,
+            midpoint_supply: 500_000,
+            breakpoints: vec![(100_000, 11_000), (600_000, 61_000)],
+            virtual_base_reserve: 1_250_000_000,
+            virtual_token_reserve: 1_250_000,
+            connector_weight: 500_000,
+            virtual_supply: 1_000_000,
+            ..CurveParams::default()
+        }
+    }
+
+    #[test]
+    fn test_linear_cost_matches_closed_form() {
+        let params = shaped_params(CurveType::Linear);
+
+        // base * n + span * (b^2 - a^2) / (2 * max_supply)
+        let (a, b) = (200_000u128, 300_000u128);
+        let expected = 1_000 * (b - a) + 100_000 * (b * b - a * a) / (2 * 1_000_000);
+        let cost = CurveCalculator::calculate_buy_price(a, b - a, &params).unwrap();
+        assert!(cost >= expected && cost <= expected + 1);
+
+        assert_eq!(CurveCalculator::price_at_supply(0, &params).unwrap(), 1_000);
+        assert_eq!(CurveCalculator::price_at_supply(1_000_000, &params).unwrap(), 101_000);
+        assert_eq!(CurveCalculator::price_at_supply(500_000, &params).unwrap(), 51_000);
+    }
+
+    #[test]
+    fn test_polynomial_degrees_order() {
+        // Higher degrees stay cheaper early on and converge at max supply
+        let linear = shaped_params(CurveType::Linear);
+        let quadratic = shaped_params(CurveType::Quadratic);
+        let cubic = shaped_params(CurveType::Cubic);
+
+        let at = |params: &CurveParams| CurveCalculator::price_at_supply(400_000, params).unwrap();
+        assert!(at(&linear) > at(&quadratic));
+        assert!(at(&quadratic) > at(&cubic));
+
+        let cost = |params: &CurveParams| CurveCalculator::calculate_buy_price(0, 1_000_000, params).unwrap();
+        // Full-range integrals: base + span / (d + 1) per token
+        assert_eq!(cost(&linear), 1_000 * 1_000_000 + 100_000 * 1_000_000 / 2);
+        assert_eq!(cost(&quadratic), 1_000 * 1_000_000 + 100_000 * 1_000_000 / 3 + 1);
+        assert_eq!(cost(&cubic), 1_000 * 1_000_000 + 100_000 * 1_000_000 / 4);
+    }
+
+    #[test]
+    fn test_sigmoid_shape() {
+        let params = shaped_params(CurveType::Sigmoid);
+
+        let mid = CurveCalculator::price_at_supply(500_000, &params).unwrap();
+        assert!(mid.abs_diff(51_000) <= 1);
+
+        let early = CurveCalculator::price_at_supply(0, &params).unwrap();
+        let late = CurveCalculator::price_at_supply(1_000_000, &params).unwrap();
+        assert!(early < 1_100);
+        assert!(late > 100_900);
+
+        // Symmetric window around the midpoint costs base + span / 2 per token
+        let cost = CurveCalculator::calculate_buy_price(490_000, 20_000, &params).unwrap();
+        assert!(cost.abs_diff(51_000 * 20_000) < 20_000);
+    }
+
+    #[test]
+    fn test_sigmoid_smooth_at_default_supply() {
+        // Default steepness over the default billion-token supply
+        let params = CurveParams {
+            curve_type: CurveType::Sigmoid,
+            base_price: DEFAULT_BASE_PRICE,
+            max_price: DEFAULT_BASE_PRICE * 10,
+            growth_rate: DEFAULT_GROWTH_RATE,
+            midpoint_supply: CurveParams::default().max_supply / 2,
+            ..CurveParams::default()
+        };
+        curve_models::validate(&params).unwrap();
+        let span = params.max_price - params.base_price;
+
+        // Every 1% of supply moves the price a little, never a jump
+        let step = params.max_supply / 100;
+        let mut previous = CurveCalculator::price_at_supply(0, &params).unwrap();
+        assert!(previous > params.base_price + span / 10);
+        for i in 1..=100 {
+            let price = CurveCalculator::price_at_supply(i * step, &params).unwrap();
+            assert!(price > previous, "flat at {}%", i);
+            assert!(price - previous < span / 100, "jump at {}%", i);
+            previous = price;
+        }
+        assert!(previous < params.max_price - span / 10);
+
+        // A single token around the midpoint barely moves it
+        let mid = CurveCalculator::price_at_supply(params.midpoint_supply, &params).unwrap();
+        let next = CurveCalculator::price_at_supply(params.midpoint_supply + 1, &params).unwrap();
+        assert!(next - mid <= 1);
+    }
+
+    #[test]
+    fn test_piecewise_linear_segments() {
+        let params = shaped_params(CurveType::PiecewiseLinear);
+
+        assert_eq!(CurveCalculator::price_at_supply(50_000, &params).unwrap(), 6_000);
+        assert_eq!(CurveCalculator::price_at_supply(350_000, &params).unwrap(), 36_000);
+        assert_eq!(CurveCalculator::price_at_supply(900_000, &params).unwrap(), 61_000);
+
+        // Trapezoids across the first breakpoint plus the flat tail
+        let first = (1_000 + 11_000) / 2 * 100_000;
+        let second = (11_000 + 61_000) / 2 * 500_000;
+        let tail = 61_000 * 400_000;
+        let cost = CurveCalculator::calculate_buy_price(0, 1_000_000, &params).unwrap();
+        assert_eq!(cost, first + second + tail);
+    }
+
+    #[test]
+    fn test_tokens_for_base_inverts_cost() {
+        for curve_type in [
+            CurveType::Linear,
+            CurveType::Quadratic,
+            CurveType::Cubic,
+            CurveType::Sigmoid,
+            CurveType::PiecewiseLinear,
+            CurveType::ConstantProduct,
+            CurveType::ReserveRatio,
+        ] {
+            let params = shaped_params(curve_type);
+            let budget = 1_000_000_000u128;
+            let (tokens, dust) = CurveCalculator::tokens_for_base_amount(100_000, budget, &params).unwrap();
+
+            let cost = CurveCalculator::calculate_buy_price(100_000, tokens, &params).unwrap();
+            let next = CurveCalculator::calculate_buy_price(100_000, tokens + 1, &params).unwrap();
+            assert!(cost <= budget, "{:?} overspent", curve_type);
+            assert!(next > budget, "{:?} left a whole token unbought", curve_type);
+            assert_eq!(dust, budget - cost, "{:?} misreported dust", curve_type);
+        }
+    }
+
+    #[test]
+    fn test_inverse_estimates_land_near_answer() {
+        // Closed forms and Newton leave only rounding for the settle step
+        for curve_type in [
+            CurveType::Linear,
+            CurveType::Cubic,
+            CurveType::Sigmoid,
+            CurveType::PiecewiseLinear,
+            CurveType::ConstantProduct,
+            CurveType::ReserveRatio,
+        ] {
+            let params = shaped_params(curve_type);
+            let model = curve_models::model_for(&params);
+            let estimate = model.estimate_tokens(250_000, 2_000_000_000, 750_000).unwrap();
+            let (exact, _) = model.tokens_for_base(250_000, 2_000_000_000, 750_000).unwrap();
+            assert!(estimate.abs_diff(exact) <= 2, "{:?}: {} vs {}", curve_type, estimate, exact);
+        }
+    }
+
+    #[test]
+    fn test_exponential_inverse_and_dust() {
+        let params = CurveParams {
+            base_price: 1_000_000,
+            growth_rate: 10, // 0.1% per 1% of max supply
+            max_supply: 1_000_000,
+            ..CurveParams::default()
+        };
+        let budget = 100_000_000_000u128;
+
+        let model = curve_models::model_for(&params);
+        let estimate = model.estimate_tokens(1_000, budget, 1_000_000).unwrap();
+        let (tokens, dust) = CurveCalculator::tokens_for_base_amount(1_000, budget, &params).unwrap();
+        assert!(estimate.abs_diff(tokens) <= 1);
+
+        let cost = CurveCalculator::calculate_buy_price(1_000, tokens, &params).unwrap();
+        assert_eq!(cost + dust, budget);
+        assert!(CurveCalculator::calculate_buy_price(1_000, tokens + 1, &params).unwrap() > budget);
+    }
+
+    #[test]
+    fn test_tokens_to_sell_for_exact_base() {
+        for curve_type in [CurveType::Linear, CurveType::Sigmoid, CurveType::ConstantProduct] {
+            let params = shaped_params(curve_type);
+            let target = 123_456_789u128;
+
+            let tokens = CurveCalculator::tokens_to_sell_for_base_amount(500_000, target, &params, 0).unwrap();
+            let payout = CurveCalculator::calculate_sell_price(500_000, tokens, &params).unwrap();
+            let short = CurveCalculator::calculate_sell_price(500_000, tokens - 1, &params).unwrap();
+            assert!(payout >= target, "{:?} underpaid", curve_type);
+            assert!(short < target, "{:?} burned an extra token", curve_type);
+        }
+
+        // More than the whole supply is worth
+        let params = shaped_params(CurveType::Linear);
+        assert!(CurveCalculator::tokens_to_sell_for_base_amount(1_000, u128::MAX / 2, &params, 0).is_err());
+    }
+
+    #[test]
+    fn test_sell_spread_decay() {
+        let spread = SellSpread {
+            initial_bps: 2_000,
+            final_bps: 100,
+            decay_blocks: 1_000,
+            destination: SpreadDestination::Burn,
+        };
+        assert!(spread.validate().is_ok());
+
+        assert_eq!(spread.bps_at(0), 2_000);
+        assert_eq!(spread.bps_at(500), 1_050);
+        assert_eq!(spread.bps_at(1_000), 100);
+        assert_eq!(spread.bps_at(50_000), 100);
+
+        // Payout plus spread always equals the curve proceeds
+        let params = CurveParams { sell_spread: spread, ..shaped_params(CurveType::Linear) };
+        let proceeds = CurveCalculator::calculate_sell_proceeds(500_000, 10_000, &params).unwrap();
+        for blocks in [0u64, 250, 999, 1_000] {
+            let (payout, withheld) = CurveCalculator::sell_payout_at(500_000, 10_000, &params, blocks).unwrap();
+            assert_eq!(payout + withheld, proceeds);
+            assert_eq!(withheld, (proceeds * params.sell_spread.bps_at(blocks)).div_ceil(10_000));
+        }
+
+        // Zero spread pays the full proceeds
+        let params = CurveParams { sell_spread: SellSpread::flat(0), ..shaped_params(CurveType::Linear) };
+        assert_eq!(CurveCalculator::calculate_sell_price(500_000, 10_000, &params).unwrap(), proceeds);
+    }
+
+    #[test]
+    fn test_sell_spread_validation() {
+        assert!(SellSpread::flat(MAX_SELL_SPREAD_BPS + 1).validate().is_err());
+
+        // Spreads only decay
+        let rising = SellSpread { final_bps: 500, ..SellSpread::flat(100) };
+        assert!(rising.validate().is_err());
+
+        assert_eq!(SpreadDestination::from_u128(2).unwrap(), SpreadDestination::Fees);
+        assert!(SpreadDestination::from_u128(3).is_err());
+    }
+
+    #[test]
+    fn test_constant_product_reserves() {
+        let params = shaped_params(CurveType::ConstantProduct);
+
+        // k = 1.25B * 1.25M; price is virtual base over virtual tokens
+        assert_eq!(CurveCalculator::price_at_supply(0, &params).unwrap(), 1_000);
+        assert_eq!(CurveCalculator::price_at_supply(250_000, &params).unwrap(), 1_562);
+
+        // k / 1M - k / 1.25M
+        let cost = CurveCalculator::calculate_buy_price(0, 250_000, &params).unwrap();
+        assert_eq!(cost, 312_500_000);
+
+        // Selling it all back walks the same reserves, less the 1% spread
+        let sell = CurveCalculator::calculate_sell_price(250_000, 250_000, &params).unwrap();
+        assert_eq!(sell, 312_500_000 * 99 / 100);
+
+        // Closed-form inverse lands on the exact affordability boundary
+        let budget = 1_000_000_000u128;
+        let model = curve_models::model_for(&params);
+        let (tokens, _) = CurveCalculator::tokens_for_base_amount(100_000, budget, &params).unwrap();
+        assert_eq!(model.estimate_tokens(100_000, budget, u128::MAX).unwrap(), tokens);
+        assert!(CurveCalculator::calculate_buy_price(100_000, tokens, &params).unwrap() <= budget);
+        assert!(CurveCalculator::calculate_buy_price(100_000, tokens + 1, &params).unwrap() > budget);
+    }
+
+    #[test]
+    fn test_constant_product_sub_unit_price() {
+        // pump.fun scale: 30 BTC of virtual sats against 1.073B six-decimal tokens
+        let params = CurveParams {
+            curve_type: CurveType::ConstantProduct,
+            max_supply: 800_000_000_000_000,
+            virtual_base_reserve: 3_000_000_000,
+            virtual_token_reserve: 1_073_000_000_000_000,
+            ..CurveParams::default()
+        };
+
+        // Tokens cost a fraction of a sat, which whole units truncate away
+        let supply = params.max_supply;
+        assert_eq!(CurveCalculator::price_at_supply(supply, &params).unwrap(), 0);
+        assert!(!CurveCalculator::price_at_supply_q64(0, &params).is_zero());
+
+        // Market cap is supply * B / T to within the Q64 rounding
+        let token_reserve = params.virtual_token_reserve - supply;
+        let base_reserve = fixed_point::mul_div(3_000_000_000, params.virtual_token_reserve, token_reserve).unwrap();
+        let exact = fixed_point::mul_div(supply, base_reserve, token_reserve).unwrap();
+        let market_cap = GraduationMetrics::at(supply, 0).market_cap(&params);
+        assert!(market_cap > 30_000_000_000);
+        assert!(exact - market_cap <= 1);
+    }
+
+    #[test]
+    fn test_reserve_ratio_solvency() {
+        // w = 50%, S0 = 1M, R0 = 1.25B: R(S) = R0 * (S / S0)^2
+        let params = CurveParams {
+            sell_spread: SellSpread::flat(0),
+            ..shaped_params(CurveType::ReserveRatio)
+        };
+
+        assert_eq!(CurveCalculator::price_at_supply(0, &params).unwrap(), 2_500);
+        assert_eq!(CurveCalculator::price_at_supply(1_000_000, &params).unwrap(), 5_000);
+
+        // Doubling the supply quadruples the reserve
+        let cost = CurveCalculator::calculate_buy_price(0, 1_000_000, &params).unwrap();
+        assert!(cost.abs_diff(3_750_000_000) <= 1);
+
+        // No sell spread: the sale formula returns the reserve delta
+        let proceeds = CurveCalculator::calculate_sell_price(1_000_000, 1_000_000, &params).unwrap();
+        assert!(proceeds <= cost && cost - proceeds <= 1);
+
+        // Reserve stays a fixed fraction of market cap along the curve
+        for supply in [100_000u128, 370_000, 815_000] {
+            let reserve = 1_250_000_000 + CurveCalculator::calculate_buy_price(0, supply, &params).unwrap();
+            let price = CurveCalculator::price_at_supply(supply, &params).unwrap();
+            let market_cap = price * (1_000_000 + supply);
+            let ratio_ppm = reserve * 1_000_000 / market_cap;
+            assert!(ratio_ppm.abs_diff(500_000) <= 1_000, "ratio {} at {}", ratio_ppm, supply);
+        }
+    }
+
+    #[test]
+    fn test_shape_validation() {
+        let mut params = shaped_params(CurveType::Linear);
+        params.max_price = params.base_price;
+        assert!(curve_models::validate(&params).is_err());
+
+        let mut params = shaped_params(CurveType::PiecewiseLinear);
+        params.breakpoints = vec![(600_000, 61_000), (100_000, 11_000)];
+        assert!(curve_models::validate(&params).is_err());
+
+        let mut params = shaped_params(CurveType::PiecewiseLinear);
+        params.breakpoints = vec![(100_000, 500)];
+        assert!(curve_models::validate(&params).is_err());
+
+        let mut params = shaped_params(CurveType::Sigmoid);
+        params.growth_rate = 0;
+        assert!(curve_models::validate(&params).is_err());
+
+        let mut params = shaped_params(CurveType::ConstantProduct);
+        params.virtual_token_reserve = params.max_supply;
+        assert!(curve_models::validate(&params).is_err());
+
+        let mut params = shaped_params(CurveType::ReserveRatio);
+        params.connector_weight = 1_000_001;
+        assert!(curve_models::validate(&params).is_err());
+
+        // Exponential launches must price their whole supply
+        let params = CurveParams { growth_rate: 100_000_000, ..CurveParams::default() };
+        assert!(curve_models::validate(&params).is_err());
+
+        assert!(curve_models::validate(&CurveParams::default()).is_ok());
+    }
+}
@@ -1,7 +1,11 @@
 //! Factory tests for token creation and management
 
 use super::*;
-use crate::{BondingCurveFactory, TokenInfo, BaseToken, constants::*};
+use crate::{BondingCurveFactory, TokenInfo, BaseToken, CurveType, constants::*};
+use crate::amm_adapter::AmmBackend;
+use crate::graduation::{CriteriaMode, GraduationCriteria};
+use crate::journal::JournaledPointer;
+use metashrew_support::index_pointer::KeyValuePointer;
 use alkanes_support::parcel::AlkaneTransfer;
 
 #[cfg(test)]
@@ -10,7 +14,7 @@ mod factory_creation_tests {
 
     #[test]
     fn test_create_token_with_busd() {
-        let mut context = create_test_context();
+        let mut context = launch_context();
         
         // Add factory fee payment
         context.incoming_alkanes.push(AlkaneTransfer {
@@ -38,7 +42,7 @@ mod factory_creation_tests {
 
     #[test]
     fn test_create_token_with_frbtc() {
-        let mut context = create_test_context();
+        let mut context = launch_context();
         
         // Add factory fee payment in frBTC
         context.incoming_alkanes.push(AlkaneTransfer {
@@ -58,7 +62,7 @@ mod factory_creation_tests {
 
     #[test]
     fn test_create_token_insufficient_fee() {
-        let mut context = create_test_context();
+        let mut context = launch_context();
         
         // Add insufficient fee
         context.incoming_alkanes.push(AlkaneTransfer {
@@ -74,8 +78,27 @@ mod factory_creation_tests {
     }
 
     #[test]
-    fn test_create_token_with_custom_parameters() {
+    fn test_create_token_needs_treasury() {
         let mut context = create_test_context();
+        context.incoming_alkanes.push(AlkaneTransfer {
+            id: BaseToken::BUSD.alkane_id(),
+            value: FACTORY_DEPLOYMENT_FEE,
+        });
+        
+        // The factory never claims protocol fees, so a curve launched without
+        // a treasury could never pay them out
+        let result = BondingCurveFactory::create_token(&context, create_test_token_params());
+        assert!(result.unwrap_err().to_string().contains("no treasury"));
+        assert!(BondingCurveFactory::set_treasury(&AlkaneId::new(0, 0)).is_err());
+        
+        BondingCurveFactory::set_treasury(&AlkaneId::new(2, 500)).unwrap();
+        assert_eq!(BondingCurveFactory::get_treasury(), Some(AlkaneId::new(2, 500)));
+        assert!(BondingCurveFactory::create_token(&context, create_test_token_params()).is_ok());
+    }
+
+    #[test]
+    fn test_create_token_with_custom_parameters() {
+        let mut context = launch_context();
         
         context.incoming_alkanes.push(AlkaneTransfer {
             id: BaseToken::BUSD.alkane_id(),
@@ -87,7 +110,6 @@ mod factory_creation_tests {
         params.growth_rate = Some(300);             // 3% growth
         params.graduation_threshold = Some(10_000_000_000); // $100k
         params.max_supply = Some(500_000_000);      // 500M tokens
-        params.lp_distribution_strategy = 1;        // Community rewards
         
         let result = BondingCurveFactory::create_token(&context, params);
         assert!(result.is_ok());
@@ -97,9 +119,129 @@ mod factory_creation_tests {
         // In production, we'd query the token contract to verify params
     }
 
+    #[test]
+    fn test_create_token_with_curve_shape() {
+        let mut context = launch_context();
+        
+        context.incoming_alkanes.push(AlkaneTransfer {
+            id: BaseToken::BUSD.alkane_id(),
+            value: FACTORY_DEPLOYMENT_FEE,
+        });
+        
+        // Polynomial curves need a price ceiling above the base price
+        let mut params = create_test_token_params();
+        params.curve_type = CurveType::Quadratic;
+        params.growth_rate = None;
+        
+        let result = BondingCurveFactory::create_token(&context, params.clone());
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("Max price"));
+        
+        params.max_price = Some(400_000_000);
+        let result = BondingCurveFactory::create_token(&context, params);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_create_token_reserves_lp_allocation() {
+        let mut context = launch_context();
+        
+        context.incoming_alkanes.push(AlkaneTransfer {
+            id: BaseToken::BUSD.alkane_id(),
+            value: FACTORY_DEPLOYMENT_FEE,
+        });
+        
+        // The whole supply cannot be held back for the pool
+        let mut params = create_test_token_params();
+        params.lp_reserve_bps = Some(10_000);
+        let result = BondingCurveFactory::create_token(&context, params);
+        assert!(result.unwrap_err().to_string().contains("LP reserve"));
+
+        // Nor can the pool go without a reserve
+        let mut params = create_test_token_params();
+        params.lp_reserve_bps = Some(0);
+        let result = BondingCurveFactory::create_token(&context, params);
+        assert!(result.unwrap_err().to_string().contains("LP reserve"));
+
+        // 20% is reserved by default: the curve sells 800M of 1B
+        let result = BondingCurveFactory::create_token(&context, create_test_token_params()).unwrap();
+        let token_info: TokenInfo = serde_json::from_slice(&result.data).unwrap();
+        let (block, tx) = token_info.token_id.split_once(':').unwrap();
+        let deployed = JournaledPointer::from_keyword(&format!("/deployed/{}/{}", block, tx)).get();
+        let deployment: serde_json::Value = serde_json::from_slice(&deployed).unwrap();
+        assert_eq!(deployment["curve_params"]["max_supply"].as_u64(), Some(800_000_000));
+        assert_eq!(deployment["curve_params"]["lp_reserve_supply"].as_u64(), Some(200_000_000));
+        assert_eq!(deployment["curve_params"]["growth_steps"].as_u64(), Some(DEFAULT_GROWTH_STEPS as u64));
+
+        // 25% of 1B is reserved for the pool, the curve sells the rest
+        let mut params = create_test_token_params();
+        params.lp_reserve_bps = Some(2_500);
+        let result = BondingCurveFactory::create_token(&context, params).unwrap();
+        let token_info: TokenInfo = serde_json::from_slice(&result.data).unwrap();
+
+        let (block, tx) = token_info.token_id.split_once(':').unwrap();
+        let deployed = JournaledPointer::from_keyword(&format!("/deployed/{}/{}", block, tx)).get();
+        let deployment: serde_json::Value = serde_json::from_slice(&deployed).unwrap();
+        assert_eq!(deployment["curve_params"]["max_supply"].as_u64(), Some(750_000_000));
+        assert_eq!(deployment["curve_params"]["lp_reserve_supply"].as_u64(), Some(250_000_000));
+    }
+
+    #[test]
+    fn test_create_token_with_amm_backend() {
+        let mut context = launch_context();
+
+        context.incoming_alkanes.push(AlkaneTransfer {
+            id: BaseToken::BUSD.alkane_id(),
+            value: FACTORY_DEPLOYMENT_FEE,
+        });
+
+        // Block 0 holds no deployed factory
+        let mut params = create_test_token_params();
+        params.amm_backend = Some(AmmBackend { factory: Some((0, 5)), ..AmmBackend::default() });
+        assert!(BondingCurveFactory::create_token(&context, params).is_err());
+
+        // The launch graduates to an Oyl-compatible AMM at 4:70000
+        let mut params = create_test_token_params();
+        params.amm_backend = Some(AmmBackend::from_args(0, 4, 70_000).unwrap());
+        let result = BondingCurveFactory::create_token(&context, params).unwrap();
+        let token_info: TokenInfo = serde_json::from_slice(&result.data).unwrap();
+
+        let (block, tx) = token_info.token_id.split_once(':').unwrap();
+        let deployed = JournaledPointer::from_keyword(&format!("/deployed/{}/{}", block, tx)).get();
+        let deployment: serde_json::Value = serde_json::from_slice(&deployed).unwrap();
+        assert_eq!(deployment["curve_params"]["amm_backend"]["factory"], serde_json::json!([4, 70_000]));
+    }
+
+    #[test]
+    fn test_create_token_with_referrer() {
+        let mut context = launch_context();
+
+        context.incoming_alkanes.push(AlkaneTransfer {
+            id: BaseToken::BUSD.alkane_id(),
+            value: FACTORY_DEPLOYMENT_FEE,
+        });
+
+        // Malformed referrers are rejected at launch, not when fees are paid
+        for referrer in ["2", "2:7:1", "two:7", "2:-7", "0:0", ""] {
+            let mut params = create_test_token_params();
+            params.referrer = Some(referrer.to_string());
+            assert!(BondingCurveFactory::create_token(&context, params).is_err(), "{:?} accepted", referrer);
+        }
+
+        let mut params = create_test_token_params();
+        params.referrer = Some("02:7".to_string());
+        let result = BondingCurveFactory::create_token(&context, params).unwrap();
+        let token_info: TokenInfo = serde_json::from_slice(&result.data).unwrap();
+
+        let (block, tx) = token_info.token_id.split_once(':').unwrap();
+        let deployed = JournaledPointer::from_keyword(&format!("/deployed/{}/{}", block, tx)).get();
+        let deployment: serde_json::Value = serde_json::from_slice(&deployed).unwrap();
+        assert_eq!(deployment["referrer"], "2:7");
+    }
+
     #[test]
     fn test_create_multiple_tokens() {
-        let mut context = create_test_context();
+        let mut context = launch_context();
         
         // Create 3 tokens
         for i in 0..3 {
@@ -129,7 +271,7 @@ mod factory_creation_tests {
 
     #[test]
     fn test_invalid_token_parameters() {
-        let mut context = create_test_context();
+        let mut context = launch_context();
         
         context.incoming_alkanes.push(AlkaneTransfer {
             id: BaseToken::BUSD.alkane_id(),
@@ -166,11 +308,30 @@ mod factory_creation_tests {
         
         let result = BondingCurveFactory::create_token(&context, params);
         assert!(result.is_err());
+        
+        // LP strategies that pay out shares are not implemented yet
+        let mut params = create_test_token_params();
+        params.lp_distribution_strategy = 1;
+        
+        let result = BondingCurveFactory::create_token(&context, params);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("not supported"));
+        
+        // Test graduation criteria without conditions
+        let mut params = create_test_token_params();
+        params.graduation_criteria = Some(GraduationCriteria {
+            mode: CriteriaMode::All,
+            conditions: Vec::new(),
+        });
+        
+        let result = BondingCurveFactory::create_token(&context, params);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("at least one condition"));
     }
 
     #[test]
     fn test_creator_token_tracking() {
-        let mut context = create_test_context();
+        let mut context = launch_context();
         let creator = context.myself.clone();
         
         // Create 2 tokens
@@ -205,7 +366,7 @@ mod factory_creation_tests {
 
     #[test]
     fn test_fee_collection() {
-        let mut context = create_test_context();
+        let mut context = launch_context();
         
         // Create tokens with different base currencies
         for base_token in [BaseToken::BUSD, BaseToken::FrBtc] {
@@ -0,0 +1,275 @@
+//! End-to-end graduation tests
+//!
+//! Graduation runs against the mock Oyl contract deployed on the alkanes
+//! test indexer. The mock is built from `mocks/oyl-mock` by build.rs and
+//! deployed at the mainnet factory proxy id, so the curve calls it exactly
+//! as it would call Oyl. The alkanes each call carries are delivered to the
+//! mock, which refuses deposits that do not match what they were sent with.
+
+use super::*;
+use crate::amm_adapter::{AmmAdapter, OylAdapter};
+use crate::amm_integration::{AMMIntegration, AlkaneCaller};
+use crate::bonding_curve::{CurveCalculator, GraduationState};
+use crate::graduation::GraduationMetrics;
+use crate::journal::Journal;
+use crate::tests::std::oyl_mock_build;
+use crate::CurveType;
+use alkanes::indexer::index_block;
+use alkanes::tests::helpers::{self as alkane_helpers, clear};
+use alkanes::utils::{balance_pointer, credit_balances, debit_balances, pipe_storagemap_to};
+use alkanes::view;
+use alkanes_support::cellpack::Cellpack;
+use alkanes_support::parcel::{AlkaneTransfer, AlkaneTransferParcel};
+use alkanes_support::response::CallResponse;
+use anyhow::{anyhow, Result};
+use metashrew_core::index_pointer::{AtomicPointer, IndexPointer};
+use metashrew_support::index_pointer::KeyValuePointer;
+use protorune::message::MessageContextParcel;
+use protorune_support::rune_transfer::RuneTransfer;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const DEPLOY_HEIGHT: u32 = 840_000;
+const FUEL: u64 = 100_000_000;
+const OYL_FACTORY: AlkaneId = AlkaneId { block: 4, tx: 65522 };
+
+/// Oyl opcodes the mock answers, as in mocks/oyl-mock
+const MOCK_CREATE_NEW_POOL: u128 = 1;
+const MOCK_ADD_LIQUIDITY: u128 = 1;
+const MOCK_POOL_DETAILS: u128 = 999;
+const MOCK_MINIMUM_LIQUIDITY: u128 = 1_000;
+
+/// Forwards the curve's calls to contracts on the test indexer
+///
+/// Each call is simulated with its outgoing alkanes as the callee's
+/// incoming ones. Its effects are then committed as the indexer commits a
+/// call's: the callee's storage writes, and its balances credited with what
+/// it was sent and debited with what it returned.
+struct IndexerCaller;
+
+impl IndexerCaller {
+    /// Clear the indexer and deploy the mock Oyl factory
+    fn deploy() -> Result<Self> {
+        clear();
+        let block = alkane_helpers::init_with_multiple_cellpacks_with_tx(
+            vec![oyl_mock_build::get_bytes()],
+            vec![Cellpack {
+                target: AlkaneId::new(3, OYL_FACTORY.tx),
+                inputs: vec![0],
+            }],
+        );
+        index_block(&block, DEPLOY_HEIGHT)?;
+        Ok(Self)
+    }
+
+    /// Reserve a, reserve b and LP supply from the pool's details
+    fn reserves(&self) -> Result<(u128, u128, u128)> {
+        let data = view::call_view(&OYL_FACTORY, &vec![MOCK_POOL_DETAILS], FUEL)?;
+        let field = |i: usize| -> Result<u128> {
+            Ok(u128::from_le_bytes(data[i * 16..(i + 1) * 16].try_into()?))
+        };
+        Ok((field(4)?, field(5)?, field(6)?))
+    }
+
+    /// Balance of `token` the pool holds on the indexer
+    fn pool_balance(&self, token: &AlkaneId) -> u128 {
+        balance_pointer(&mut AtomicPointer::default(), &OYL_FACTORY, token).get_value::<u128>()
+    }
+}
+
+impl AlkaneCaller for IndexerCaller {
+    fn call_alkane(&self, cellpack: &Cellpack, outgoing: &AlkaneTransferParcel) -> Result<CallResponse> {
+        let runes: Vec<RuneTransfer> = outgoing.0
+            .iter()
+            .map(|transfer| RuneTransfer { id: transfer.id.clone().into(), value: transfer.value })
+            .collect();
+        let parcel = MessageContextParcel {
+            calldata: cellpack.encipher(),
+            height: (DEPLOY_HEIGHT + 1) as u64,
+            runes: runes.clone(),
+            ..MessageContextParcel::default()
+        };
+        let (response, _) = view::simulate_parcel(&parcel, FUEL)?;
+
+        let target: Vec<u8> = cellpack.target.clone().into();
+        let mut atomic = AtomicPointer::default();
+        credit_balances(&mut atomic, &cellpack.target, &runes)?;
+        debit_balances(&mut atomic, &cellpack.target, &response.alkanes)?;
+        pipe_storagemap_to(
+            &response.storage,
+            &mut atomic.derive(&IndexPointer::from_keyword("/alkanes/").select(&target).keyword("/storage/")),
+        );
+        atomic.commit();
+
+        Ok(CallResponse {
+            alkanes: response.alkanes,
+            storage: response.storage,
+            data: response.data,
+        })
+    }
+}
+
+/// Parcel of `(id, amount)` transfers
+fn parcel(transfers: &[(&AlkaneId, u128)]) -> AlkaneTransferParcel {
+    AlkaneTransferParcel(
+        transfers
+            .iter()
+            .map(|(id, value)| AlkaneTransfer { id: (*id).clone(), value: *value })
+            .collect(),
+    )
+}
+
+/// Run `test` against a linear curve priced at 100 base per token and
+/// holding `base_reserves`, discarding the curve's writes afterwards
+fn with_curve(base_reserves: u128, test: impl FnOnce()) {
+    let params = CurveParams {
+        curve_type: CurveType::Linear,
+        base_price: 100,
+        max_price: 200,
+        graduation_threshold: 20_000,
+        ..CurveParams::default()
+    };
+    let result: Result<()> = Journal::transact(|| {
+        CurveCalculator::set_curve_params(&params)?;
+        CurveCalculator::set_base_reserves(base_reserves);
+        test();
+        Err(anyhow!("discard test writes"))
+    });
+    assert!(result.is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_graduation_creates_and_seeds_oyl_pool() -> Result<()> {
+    let oyl = IndexerCaller::deploy()?;
+
+    with_curve(1_000_000, || {
+        let metrics = GraduationMetrics::at(1_000_000, 1_000_000);
+        AMMIntegration::attempt_graduation(&oyl, &create_test_context(), &metrics).unwrap().unwrap();
+
+        assert_eq!(CurveCalculator::graduation_state(), GraduationState::Graduated);
+        assert_eq!(AMMIntegration::get_amm_pool_address(), Some(OYL_FACTORY));
+    });
+
+    // The pool holds every base token the curve raised, opening at the
+    // curve's price of 100
+    let (token_reserve, base_reserve, lp_supply) = oyl.reserves()?;
+    assert_eq!((token_reserve, base_reserve), (10_000, 1_000_000));
+
+    // And both sides really reached it, not just its bookkeeping
+    assert_eq!(oyl.pool_balance(&create_test_context().myself), 10_000);
+    assert_eq!(oyl.pool_balance(&BaseToken::BUSD.alkane_id()), 1_000_000);
+
+    // LP supply is the integer sqrt of the seeded product
+    let product = token_reserve * base_reserve;
+    assert!(lp_supply * lp_supply <= product);
+    assert!((lp_supply + 1) * (lp_supply + 1) > product);
+    assert!(lp_supply > MOCK_MINIMUM_LIQUIDITY);
+    Ok(())
+}
+
+#[wasm_bindgen_test]
+fn test_existing_pool_fails_graduation() -> Result<()> {
+    let oyl = IndexerCaller::deploy()?;
+    let base = BaseToken::BUSD.alkane_id();
+    let myself = create_test_context().myself;
+
+    // Someone else seeds the pair first
+    oyl.call_alkane(
+        &Cellpack {
+            target: OYL_FACTORY,
+            inputs: vec![MOCK_CREATE_NEW_POOL, myself.block, myself.tx, base.block, base.tx, 5_000, 5_000],
+        },
+        &parcel(&[(&myself, 5_000), (&base, 5_000)]),
+    )?;
+
+    with_curve(1_000_000, || {
+        let metrics = GraduationMetrics::at(1_000_000, 1_000_000);
+        assert!(AMMIntegration::attempt_graduation(&oyl, &create_test_context(), &metrics).unwrap().is_err());
+
+        assert_eq!(CurveCalculator::graduation_state(), GraduationState::Failed);
+        let reason = CurveCalculator::graduation_failure().unwrap();
+        assert!(reason.contains("Oyl factory failed to create pool"));
+        assert_eq!(AMMIntegration::get_amm_pool_address(), None);
+    });
+
+    // The failed attempt left the existing pool alone
+    assert_eq!(oyl.reserves()?, (5_000, 5_000, 5_000));
+    assert_eq!(oyl.pool_balance(&base), 5_000);
+    Ok(())
+}
+
+#[wasm_bindgen_test]
+fn test_pool_refuses_deposits_it_was_not_sent() -> Result<()> {
+    let oyl = IndexerCaller::deploy()?;
+    let base = BaseToken::BUSD.alkane_id();
+    let myself = create_test_context().myself;
+    let create = Cellpack {
+        target: OYL_FACTORY,
+        inputs: vec![MOCK_CREATE_NEW_POOL, myself.block, myself.tx, base.block, base.tx, 10_000, 1_000_000],
+    };
+
+    // Claiming a seed without sending it, or sending less than claimed
+    assert!(oyl.call_alkane(&create, &AlkaneTransferParcel::default()).is_err());
+    assert!(oyl.call_alkane(&create, &parcel(&[(&myself, 10_000), (&base, 999_999)])).is_err());
+    assert!(oyl.reserves().is_err());
+
+    // Liquidity must bring both tokens
+    oyl.call_alkane(&create, &parcel(&[(&myself, 10_000), (&base, 1_000_000)]))?;
+    let add = Cellpack { target: OYL_FACTORY, inputs: vec![MOCK_ADD_LIQUIDITY] };
+    assert!(oyl.call_alkane(&add, &parcel(&[(&base, 100_000)])).is_err());
+    assert_eq!(oyl.reserves()?, (10_000, 1_000_000, 100_000));
+    Ok(())
+}
+
+#[wasm_bindgen_test]
+fn test_oyl_adapter_round_trip() -> Result<()> {
+    let oyl = IndexerCaller::deploy()?;
+    let adapter = OylAdapter::new(&oyl, OYL_FACTORY);
+    let token = create_test_context().myself;
+    let base = BaseToken::BUSD.alkane_id();
+
+    // sqrt(10k * 1M) = 100k LP, less the locked minimum
+    let (pool, lp_tokens) = adapter.create_pool(
+        AlkaneTransfer { id: token.clone(), value: 10_000 },
+        AlkaneTransfer { id: base.clone(), value: 1_000_000 },
+    )?;
+    assert_eq!(pool, OYL_FACTORY);
+    assert_eq!(lp_tokens, 100_000 - MOCK_MINIMUM_LIQUIDITY);
+    assert_eq!(adapter.get_reserves(&pool, &token, &base)?, (10_000, 1_000_000));
+
+    // A tenth more of each side mints a tenth more LP
+    let added = adapter.add_liquidity(
+        &pool,
+        AlkaneTransfer { id: token.clone(), value: 1_000 },
+        AlkaneTransfer { id: base.clone(), value: 100_000 },
+    )?;
+    assert_eq!(added, 10_000);
+    assert_eq!(adapter.get_reserves(&pool, &token, &base)?, (11_000, 1_100_000));
+    assert_eq!(oyl.pool_balance(&base), 1_100_000);
+
+    // Burning a tenth of the 110k LP supply withdraws a tenth of each side
+    assert_eq!(adapter.burn_lp(&pool, 11_000, &token, &base)?, (1_100, 110_000));
+    assert_eq!(oyl.reserves()?, (9_900, 990_000, 99_000));
+    assert_eq!(oyl.pool_balance(&token), 9_900);
+    assert_eq!(oyl.pool_balance(&base), 990_000);
+    Ok(())
+}
+
+#[wasm_bindgen_test]
+fn test_oyl_adapter_reserves_of_sorted_pair() -> Result<()> {
+    let oyl = IndexerCaller::deploy()?;
+    let adapter = OylAdapter::new(&oyl, OYL_FACTORY);
+    // Above BUSD's 2:56801, so the pool stores BUSD as token a
+    let token = AlkaneId::new(40, 1);
+    let base = BaseToken::BUSD.alkane_id();
+
+    let (pool, _) = adapter.create_pool(
+        AlkaneTransfer { id: token.clone(), value: 10_000 },
+        AlkaneTransfer { id: base.clone(), value: 1_000_000 },
+    )?;
+    let (reserve_a, reserve_b, _) = oyl.reserves()?;
+    assert_eq!((reserve_a, reserve_b), (1_000_000, 10_000));
+
+    // Reserves are matched to the ids asked for, not the pool's order
+    assert_eq!(adapter.get_reserves(&pool, &token, &base)?, (10_000, 1_000_000));
+    Ok(())
+}
@@ -12,10 +12,20 @@ pub mod bonding_curve_tests;
 pub mod amm_integration_tests;
 pub mod security_tests;
 pub mod performance_tests;
+pub mod trade_integration_tests;
 
-use crate::{BaseToken, TokenLaunchParams, CurveParams};
+// Indexer-backed tests need the wasm builds written by build.rs
+#[cfg(target_arch = "wasm32")]
+pub mod std;
+#[cfg(target_arch = "wasm32")]
+pub mod graduation_e2e_tests;
+
+use crate::{BaseToken, TokenLaunchParams, CurveParams, CurveType};
+use crate::bonding_curve::CurveCalculator;
+use crate::journal::Journal;
 use alkanes_support::context::Context;
 use alkanes_support::id::AlkaneId;
+use anyhow::{anyhow, Result};
 
 /// Create a test context for unit tests
 pub fn create_test_context() -> Context {
@@ -27,6 +37,12 @@ pub fn create_test_context() -> Context {
     }
 }
 
+/// Test context for factory launches, with the factory's treasury set
+pub fn launch_context() -> Context {
+    crate::BondingCurveFactory::set_treasury(&AlkaneId::new(2, 500)).unwrap();
+    create_test_context()
+}
+
 /// Create default test token parameters
 pub fn create_test_token_params() -> TokenLaunchParams {
     TokenLaunchParams {
@@ -40,9 +56,38 @@ pub fn create_test_token_params() -> TokenLaunchParams {
         max_supply: Some(1_000_000_000),
         base_token: BaseToken::BUSD,
         lp_distribution_strategy: 0,
+        ..TokenLaunchParams::default()
+    }
+}
+
+/// Linear curve from 1_000 to 101_000 base per token over 1M tokens
+///
+/// Tests needing other prices, limits or thresholds override fields with
+/// struct update syntax.
+pub fn linear_params() -> CurveParams {
+    CurveParams {
+        curve_type: CurveType::Linear,
+        base_price: 1_000,
+        max_price: 101_000,
+        max_supply: 1_000_000,
+        graduation_threshold: 10_000_000_000,
+        lp_reserve_supply: 250_000,
+        ..CurveParams::default()
     }
 }
 
+/// Run `test` against a curve with `params` and `base_reserves`,
+/// discarding every write afterwards so shared storage is left alone
+pub fn with_curve(params: CurveParams, base_reserves: u128, test: impl FnOnce()) {
+    let result: Result<()> = Journal::transact(|| {
+        CurveCalculator::set_curve_params(&params)?;
+        CurveCalculator::set_base_reserves(base_reserves);
+        test();
+        Err(anyhow!("discard test writes"))
+    });
+    assert!(result.is_err());
+}
+
 /// Convert string to u128 for token names
 pub fn string_to_u128(s: &str) -> u128 {
     let mut bytes = [0u8; 16];
@@ -1,6 +1,11 @@
 //! Performance and gas optimization tests
 
 use super::*;
+use crate::bonding_curve::{CurveCalculator, MAX_PRICE, MAX_SEARCH_PROBES};
+use crate::curve_models::{self, BondingCurveModel};
+use crate::fixed_point::{ONE, U256};
+use crate::trade::{CurveState, TradePlan};
+use crate::CurveType;
 
 #[cfg(test)]
 mod performance_tests {
@@ -14,8 +19,107 @@ mod performance_tests {
 
     #[test]
     fn test_large_trade_performance() {
-        // Test performance with large buy/sell orders
-        assert!(true); // Placeholder for trading performance
+        // Pricing at 1B supply must not cost O(supply)
+        let params = CurveParams {
+            base_price: 1_000,
+            growth_rate: 1,
+            graduation_threshold: u128::MAX,
+            base_token: BaseToken::BUSD,
+            max_supply: 2_000_000_000,
+            ..CurveParams::default()
+        };
+
+        // 1B is 50 whole steps of 1% of max supply and a partial one: six
+        // squaring steps and one exp2
+        let price = CurveCalculator::price_at_supply(1_000_000_007, &params).unwrap();
+        assert_eq!(price, 1_005);
+
+        // Far past max supply the price saturates instead of failing
+        assert_eq!(CurveCalculator::price_at_supply(u128::MAX, &params).unwrap(), MAX_PRICE);
+
+        for supply in [0u128, 50_000, 100_000] {
+            let cost = CurveCalculator::calculate_buy_price(supply, 100_000, &params).unwrap();
+            assert!(cost > 0);
+        }
+    }
+
+    #[test]
+    fn test_buy_at_billion_supply() {
+        // Steepness scales with max supply, so a sigmoid over 2B tokens
+        // prices a buy at its 1B midpoint like any other
+        let params = CurveParams {
+            curve_type: CurveType::Sigmoid,
+            base_price: 1_000,
+            max_price: 1_000_000,
+            growth_rate: 10,
+            midpoint_supply: 1_000_000_000,
+            max_supply: 2_000_000_000,
+            graduation_threshold: u128::MAX,
+            base_token: BaseToken::BUSD,
+            ..CurveParams::default()
+        };
+        curve_models::validate(&params).unwrap();
+
+        let state = CurveState { supply: 1_000_000_000, base_reserves: 1, ..CurveState::default() };
+        let base_amount = 1_000_000_000_000u128;
+        let plan = TradePlan::buy_with_base(&state, &params, base_amount).unwrap();
+        assert!(plan.tokens > 0);
+        assert!(plan.price_before < plan.price_after && plan.price_after < U256::mul_u128(params.max_price, ONE));
+        assert_eq!(plan.trader_base + plan.dust, base_amount);
+
+        // The same search the buy ran, from the model's estimate and from a
+        // useless one, stays under the published probe bound
+        let model = curve_models::model_for(&params);
+        let budget = params.fees.budget_before_fee(base_amount).unwrap();
+        let remaining = params.max_supply - state.supply;
+        let estimate = model.estimate_tokens(state.supply, budget, remaining).unwrap();
+        for start in [estimate, 0, remaining] {
+            let (tokens, probes) = curve_models::settle_with_probes(start, remaining, |amount| {
+                model.cost_to_buy(state.supply, amount).map(|cost| cost <= budget).unwrap_or(false)
+            });
+            assert_eq!(tokens, plan.tokens);
+            assert!(probes <= MAX_SEARCH_PROBES, "{} probes", probes);
+        }
+    }
+
+    #[test]
+    fn test_settle_probe_bound() {
+        // Worst cases for the bare search: the answer at the far end of the
+        // range from the estimate
+        for (estimate, answer) in [(0, u128::MAX - 1), (u128::MAX, 1), (1, u128::MAX / 3), (u128::MAX / 2, 0)] {
+            let (found, probes) = curve_models::settle_with_probes(estimate, u128::MAX, |amount| amount <= answer);
+            assert_eq!(found, answer);
+            assert!(probes <= MAX_SEARCH_PROBES, "{} probes", probes);
+        }
+
+        // Real costs on a steep and a nearly flat curve, from useless estimates
+        let steep = CurveParams {
+            base_price: 1_000,
+            growth_rate: 1_000,
+            max_supply: 2_000_000_000,
+            ..CurveParams::default()
+        };
+        let flat = CurveParams {
+            curve_type: CurveType::Linear,
+            base_price: 1,
+            max_price: 2,
+            max_supply: 1u128 << 62,
+            ..CurveParams::default()
+        };
+        let budget = 1_000_000_000_000u128;
+
+        for params in [steep, flat] {
+            let model = curve_models::model_for(&params);
+            let (expected, _) = model.tokens_for_base(0, budget, params.max_supply).unwrap();
+
+            for estimate in [0, params.max_supply] {
+                let (tokens, probes) = curve_models::settle_with_probes(estimate, params.max_supply, |amount| {
+                    model.cost_to_buy(0, amount).map(|cost| cost <= budget).unwrap_or(false)
+                });
+                assert_eq!(tokens, expected);
+                assert!(probes <= MAX_SEARCH_PROBES, "{} probes", probes);
+            }
+        }
     }
 
     #[test]
@@ -2,27 +2,95 @@
 
 use super::*;
 use crate::{bonding_curve::CurveCalculator, constants::*};
-use alkanes_support::parcel::AlkaneTransfer;
+use crate::amm_integration::AlkaneCaller;
+use crate::bonding_curve::GraduationState;
+use crate::trade::{CurveState, TradePlan, TradeSide};
+use crate::fixed_point::U256;
+use crate::graduation::GraduationMetrics;
+use crate::guard::ReentrancyGuard;
+use crate::{BondingCurve, BondingCurveMessage};
+use alkanes_support::cellpack::Cellpack;
+use alkanes_support::response::CallResponse;
+use anyhow::{anyhow, Result};
+use alkanes_support::parcel::{AlkaneTransfer, AlkaneTransferParcel};
+use std::cell::RefCell;
 
 #[cfg(test)]
 mod security_edge_case_tests {
     use super::*;
 
+    /// Pool that calls back into the curve from inside the curve's external
+    /// call, records how each attempt ended, then refuses to open
+    struct MaliciousPool {
+        context: Context,
+        attempts: RefCell<Vec<Result<CallResponse>>>,
+    }
+
+    impl MaliciousPool {
+        /// Try to sell into the curve before graduation finishes
+        fn sell_back(&self) -> Result<CallResponse> {
+            BondingCurve::default().handle_message_via(self, &self.context, BondingCurveMessage::SellTokens {
+                token_amount: 1_000_000,
+                min_base_out: 0,
+                quoted_base_out: 0,
+            })
+        }
+
+        /// Same attack through a buy
+        fn buy_again(&self) -> Result<CallResponse> {
+            BondingCurve::default().handle_message_via(
+                self,
+                &self.context,
+                BondingCurveMessage::BuyTokens { min_tokens_out: 0, quoted_tokens_out: 0 },
+            )
+        }
+    }
+
+    impl AlkaneCaller for MaliciousPool {
+        fn call_alkane(&self, _cellpack: &Cellpack, _outgoing: &AlkaneTransferParcel) -> Result<CallResponse> {
+            let sold = self.sell_back();
+            self.attempts.borrow_mut().push(sold);
+            let bought = self.buy_again();
+            self.attempts.borrow_mut().push(bought);
+            Err(anyhow!("pool refused"))
+        }
+    }
+
     #[test]
     fn test_reentrancy_protection() {
-        // Test that reentrancy is prevented during buy/sell operations
-        let mut context = create_test_context();
-        
-        // Add payment
-        context.incoming_alkanes.push(AlkaneTransfer {
-            id: BaseToken::BUSD.alkane_id(),
-            value: 10_000_000_000, // 100 BUSD
+        // Linear curve at about 100 base per token that graduates at 20k
+        let params = CurveParams { base_price: 100, max_price: 200, graduation_threshold: 20_000, ..linear_params() };
+        let context = Context {
+            incoming_alkanes: AlkaneTransferParcel(vec![AlkaneTransfer {
+                id: BaseToken::BUSD.alkane_id(),
+                value: 5_000_000,
+            }]),
+            ..create_test_context()
+        };
+        let plan = TradePlan::buy_with_base(&CurveState::default(), &params, 5_000_000).unwrap();
+        assert!(plan.graduates);
+
+        let pool = MaliciousPool { context: context.clone(), attempts: RefCell::new(Vec::new()) };
+        with_curve(params, 0, || {
+            let buy = BondingCurveMessage::BuyTokens { min_tokens_out: plan.tokens, quoted_tokens_out: plan.tokens };
+            BondingCurve::default().handle_message_via(&pool, &context, buy).unwrap();
+
+            // Both callbacks ran inside the graduating buy's pool call and
+            // were turned away by the buy's lock
+            let attempts = pool.attempts.borrow();
+            assert_eq!(attempts.len(), 2);
+            for attempt in attempts.iter() {
+                let err = attempt.as_ref().unwrap_err();
+                assert!(err.to_string().contains("Reentrant call rejected: buy"));
+            }
+
+            // The pool never opened, so the buy stands with graduation failed
+            assert_eq!(CurveCalculator::graduation_state(), GraduationState::Failed);
+            assert_eq!(CurveCalculator::get_base_reserves(), plan.reserves_after);
         });
-        
-        // Simulate reentrancy attempt
-        // In production, this would test actual reentrancy guards
-        // For now, we verify the pattern exists in the code
-        assert!(true); // Placeholder - actual implementation would test locks
+
+        // Returning from the buy, even through the failed attempt, freed the lock
+        assert!(!ReentrancyGuard::is_locked());
     }
 
     #[test]
@@ -33,6 +101,7 @@ mod security_edge_case_tests {
             graduation_threshold: u128::MAX,
             base_token: BaseToken::BUSD,
             max_supply: u128::MAX,
+            ..CurveParams::default()
         };
 
         // Should not panic on overflow
@@ -94,8 +163,10 @@ mod security_edge_case_tests {
         let min_acceptable = expected_tokens * (10000 - max_slippage) / 10000;
         
         assert_eq!(min_acceptable, 950); // 5% slippage = 950 minimum tokens
-        
-        // In production, the buy function would reject if actual < minimum
+
+        // The launch's tolerance gives the same suggested bound
+        let limits = CurveParams::default().limits;
+        assert_eq!(limits.slippage_floor(expected_tokens).unwrap(), min_acceptable);
     }
 
     #[test]
@@ -127,9 +198,29 @@ mod security_edge_case_tests {
         let max_allowed = remaining * MAX_BUY_PERCENTAGE / 10000;
         
         assert!(max_allowed < whale_attempt);
-        
-        // In production, this would be enforced in buy function
         assert_eq!(MAX_BUY_PERCENTAGE, 1000); // 10% limit
+
+        // Trade plans over the cap are rejected before they execute
+        let limits = &params.limits;
+        assert_eq!(limits.max_buy_tokens(current_supply, params.max_supply).unwrap(), max_allowed);
+
+        let state = CurveState { supply: current_supply, ..CurveState::default() };
+        let whale = TradePlan {
+            side: TradeSide::Buy,
+            tokens: whale_attempt,
+            curve_amount: 0,
+            trader_base: MIN_BUY_AMOUNT,
+            dust: 0,
+            fee: Default::default(),
+            spread: 0,
+            price_before: U256::ZERO,
+            price_after: U256::ZERO,
+            supply_after: current_supply + whale_attempt,
+            reserves_after: 0,
+            graduates: false,
+        };
+        assert!(limits.check(&whale, &state, params.max_supply).is_err());
+        assert!(limits.check(&TradePlan { tokens: max_allowed, ..whale }, &state, params.max_supply).is_ok());
     }
 
     #[test]
@@ -140,6 +231,7 @@ mod security_edge_case_tests {
             graduation_threshold: 100_000_000, // Low for testing
             base_token: BaseToken::BUSD,
             max_supply: 1_000_000_000,
+            ..CurveParams::default()
         };
         
         // Try to manipulate graduation by inflating market cap
@@ -148,8 +240,7 @@ mod security_edge_case_tests {
         
         // Should not graduate based on inflated market cap alone
         let should_graduate = CurveCalculator::check_graduation_criteria(
-            manipulated_supply,
-            reserves,
+            &GraduationMetrics::at(manipulated_supply, reserves),
             &params
         );
         
@@ -178,7 +269,7 @@ mod security_edge_case_tests {
 
     #[test]
     fn test_factory_fee_bypass_attempt() {
-        let mut context = create_test_context();
+        let mut context = launch_context();
         
         // Try to create token without paying fee
         let params = create_test_token_params();
@@ -221,6 +312,7 @@ mod security_edge_case_tests {
             graduation_threshold: 100_000_000,
             base_token: BaseToken::BUSD,
             max_supply: 1_000_000_000,
+            ..CurveParams::default()
         };
         
         // Token is close to graduation
@@ -229,8 +321,10 @@ mod security_edge_case_tests {
         
         // Check if close to graduation
         let close_to_grad = CurveCalculator::check_graduation_criteria(
-            current_supply + 5000, // Small buy would trigger
-            reserves + 5_000_000,
+            &GraduationMetrics::at(
+                current_supply + 5000, // Small buy would trigger
+                reserves + 5_000_000,
+            ),
             &params
         );
         
@@ -242,21 +336,16 @@ mod security_edge_case_tests {
 
     #[test]
     fn test_lp_distribution_validation() {
-        // Test all LP distribution strategies are valid
-        let strategies = vec![0, 1, 2, 3];
-        
-        for strategy in strategies {
-            let mut params = create_test_token_params();
-            params.lp_distribution_strategy = strategy;
-            
-            // All strategies should be valid
-            assert!(strategy <= 3);
+        use crate::amm_integration::LPDistributionStrategy;
+
+        // Only burn-all is carried out; the payout strategies are refused
+        assert!(LPDistributionStrategy::from_u128(0).unwrap().ensure_supported().is_ok());
+        for strategy in 1..=3 {
+            let strategy = LPDistributionStrategy::from_u128(strategy).unwrap();
+            assert!(strategy.ensure_supported().is_err());
         }
         
         // Test invalid strategy
-        let mut params = create_test_token_params();
-        params.lp_distribution_strategy = 99; // Invalid
-        
-        // In production, this should be validated
+        assert!(LPDistributionStrategy::from_u128(99).is_err());
     }
 } 
\ No newline at end of file
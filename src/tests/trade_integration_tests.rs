@@ -0,0 +1,203 @@
+//! Trade handler tests: what a trade takes from and returns to the caller
+
+use super::*;
+use crate::bonding_curve::CurveCalculator;
+use crate::fees::{FeeEngine, FeeRecipient, FeeSplit};
+use crate::trade::{CurveState, TradePlan, TradeLimits};
+use crate::{BondingCurve, BondingCurveMessage};
+use alkanes_support::parcel::{AlkaneTransfer, AlkaneTransferParcel};
+use alkanes_support::response::CallResponse;
+use metashrew_support::index_pointer::KeyValuePointer;
+
+#[cfg(test)]
+mod trade_integration_tests {
+    use super::*;
+
+    fn curve_id() -> AlkaneId {
+        AlkaneId::new(2, 1000)
+    }
+
+    fn unrelated_id() -> AlkaneId {
+        AlkaneId::new(4, 7)
+    }
+
+    /// Context of a call to the curve carrying `incoming`
+    fn call_with(incoming: Vec<AlkaneTransfer>) -> Context {
+        Context {
+            myself: curve_id(),
+            caller: AlkaneId::new(2, 1),
+            incoming_alkanes: AlkaneTransferParcel(incoming),
+            ..Context::default()
+        }
+    }
+
+    fn amount_of(response: &CallResponse, id: &AlkaneId) -> u128 {
+        response.alkanes.0
+            .iter()
+            .filter(|transfer| &transfer.id == id)
+            .map(|transfer| transfer.value)
+            .sum()
+    }
+
+    /// The shared linear curve, selling up to the whole circulating supply
+    fn linear_params() -> CurveParams {
+        CurveParams {
+            limits: TradeLimits { max_sell_bps: 10_000, ..TradeLimits::default() },
+            ..super::linear_params()
+        }
+    }
+
+    #[test]
+    fn test_sell_without_tokens_is_rejected() {
+        // The naive exploit: call SellTokens without sending any curve tokens
+        let context = call_with(Vec::new());
+        let err = BondingCurve::take_sold_tokens(&context, 1_000_000).unwrap_err();
+        assert!(err.to_string().contains("No token input"));
+
+        // Sending some other alkane instead does not count either
+        let context = call_with(vec![AlkaneTransfer { id: BaseToken::BUSD.alkane_id(), value: 1_000_000 }]);
+        assert!(BondingCurve::take_sold_tokens(&context, 1_000_000).is_err());
+    }
+
+    #[test]
+    fn test_sell_more_than_sent_is_rejected() {
+        let context = call_with(vec![AlkaneTransfer { id: curve_id(), value: 10 }]);
+        let err = BondingCurve::take_sold_tokens(&context, 1_000_000).unwrap_err();
+        assert!(err.to_string().contains("selling 1000000, received 10"));
+    }
+
+    #[test]
+    fn test_sell_consumes_tokens_and_returns_surplus() {
+        // Inputs split over several transfers add up
+        let context = call_with(vec![
+            AlkaneTransfer { id: curve_id(), value: 600 },
+            AlkaneTransfer { id: curve_id(), value: 500 },
+        ]);
+
+        let response = BondingCurve::take_sold_tokens(&context, 1_000).unwrap().into_response();
+        assert_eq!(amount_of(&response, &curve_id()), 100);
+
+        // Selling exactly what was sent leaves nothing to return
+        let response = BondingCurve::take_sold_tokens(&context, 1_100).unwrap().into_response();
+        assert_eq!(amount_of(&response, &curve_id()), 0);
+    }
+
+    #[test]
+    fn test_multi_asset_sell_forwards_other_alkanes() {
+        let params = linear_params();
+        let base_id = params.base_token.alkane_id();
+        let context = call_with(vec![
+            AlkaneTransfer { id: unrelated_id(), value: 5 },
+            AlkaneTransfer { id: curve_id(), value: 20_000 },
+            AlkaneTransfer { id: base_id.clone(), value: 777 },
+        ]);
+
+        let reserves = CurveCalculator::calculate_buy_price(0, 100_000, &params).unwrap();
+        let state = CurveState { supply: 100_000, base_reserves: reserves, ..CurveState::default() };
+        let plan = TradePlan::sell_exact(&state, &params, 15_000).unwrap();
+
+        with_curve(params.clone(), reserves, || {
+            CurveCalculator::total_supply_pointer().set_value::<u128>(100_000);
+            let message = BondingCurveMessage::SellTokens {
+                token_amount: 15_000,
+                min_base_out: plan.trader_base,
+                quoted_base_out: plan.trader_base,
+            };
+            let response = BondingCurve::default().handle_message(&context, message).unwrap();
+
+            assert_eq!(amount_of(&response, &unrelated_id()), 5);
+            assert_eq!(amount_of(&response, &curve_id()), 5_000);
+            assert_eq!(amount_of(&response, &base_id), 777 + plan.trader_base);
+            assert_eq!(CurveCalculator::sold_supply(), 85_000);
+            assert_eq!(CurveCalculator::get_base_reserves(), plan.reserves_after);
+        });
+    }
+
+    #[test]
+    fn test_multi_asset_buy_refunds_dust_only() {
+        let params = linear_params();
+        let base_id = params.base_token.alkane_id();
+        let context = call_with(vec![
+            AlkaneTransfer { id: base_id.clone(), value: 40_000_000 },
+            AlkaneTransfer { id: unrelated_id(), value: 5 },
+            AlkaneTransfer { id: base_id.clone(), value: 10_000_000 },
+        ]);
+
+        let plan = TradePlan::buy_with_base(&CurveState::default(), &params, 50_000_000).unwrap();
+
+        with_curve(params.clone(), 0, || {
+            let message = BondingCurveMessage::BuyTokens { min_tokens_out: plan.tokens, quoted_tokens_out: plan.tokens };
+            let response = BondingCurve::default().handle_message(&context, message).unwrap();
+
+            // Base paid for the tokens is not echoed back; only the dust is
+            assert_eq!(amount_of(&response, &base_id), plan.dust);
+            assert_eq!(amount_of(&response, &base_id) + plan.trader_base, 50_000_000);
+            assert_eq!(amount_of(&response, &curve_id()), plan.tokens);
+            assert_eq!(amount_of(&response, &unrelated_id()), 5);
+            assert_eq!(CurveCalculator::get_base_reserves(), plan.curve_amount);
+        });
+    }
+
+    #[test]
+    fn test_loose_slippage_bounds_are_rejected() {
+        let params = linear_params();
+        let base_id = params.base_token.alkane_id();
+        let context = call_with(vec![AlkaneTransfer { id: base_id, value: 50_000_000 }]);
+        let plan = TradePlan::buy_with_base(&CurveState::default(), &params, 50_000_000).unwrap();
+        let buy = |min_tokens_out, quoted_tokens_out| BondingCurveMessage::BuyTokens { min_tokens_out, quoted_tokens_out };
+
+        // The bound is measured against the caller's quote, not the fill
+        let quoted = plan.tokens - plan.tokens / 10;
+        let floor = params.limits.slippage_floor(quoted).unwrap();
+
+        with_curve(params.clone(), 0, || {
+            let err = BondingCurve::default().handle_message(&context, buy(floor - 1, quoted)).unwrap_err();
+            assert!(err.to_string().contains("Slippage bound too loose"));
+
+            // A fill better than quoted is not held against the trader
+            BondingCurve::default().handle_message(&context, buy(floor, quoted)).unwrap();
+        });
+
+        // Asking for no protection is the caller's choice
+        with_curve(params.clone(), 0, || {
+            BondingCurve::default().handle_message(&context, buy(0, plan.tokens)).unwrap();
+        });
+    }
+
+    #[test]
+    fn test_fee_claim_pays_only_registered_claimant() {
+        // Fee storage is shared, so the writes are discarded afterwards
+        with_curve(linear_params(), 0, || {
+            let creator = AlkaneId::new(2, 1);
+            let base_id = BaseToken::BUSD.alkane_id();
+            FeeEngine::set_recipient(FeeRecipient::Creator, &creator).unwrap();
+
+            // Wallets all call as 0:0, so it can never be a claimant
+            assert!(FeeEngine::set_recipient(FeeRecipient::Creator, &AlkaneId::new(0, 0)).is_err());
+            FeeEngine::accrue(&FeeSplit { protocol: 0, creator: 3_000, referrer: 0 }).unwrap();
+
+            // Sending the claimant's alkane is not enough, the call must come from it
+            let mut context = call_with(vec![AlkaneTransfer { id: creator.clone(), value: 1 }]);
+            context.caller = AlkaneId::new(2, 99);
+            assert!(FeeEngine::claim(&context, FeeRecipient::Creator, base_id.clone()).is_err());
+            assert_eq!(FeeEngine::get_accrued(FeeRecipient::Creator), 3_000);
+
+            let response = FeeEngine::claim(&call_with(Vec::new()), FeeRecipient::Creator, base_id.clone()).unwrap();
+            assert_eq!(amount_of(&response, &base_id), 3_000);
+            assert_eq!(FeeEngine::get_accrued(FeeRecipient::Creator), 0);
+        });
+    }
+
+    #[test]
+    fn test_curve_initializes_once() {
+        let result: Result<()> = Journal::transact(|| {
+            BondingCurve::observe_curve_initialization().unwrap();
+
+            // A second Initialize would re-register the fee recipients
+            let err = BondingCurve::observe_curve_initialization().unwrap_err();
+            assert!(err.to_string().contains("already initialized"));
+            Err(anyhow!("discard test writes"))
+        });
+        assert!(result.is_err());
+    }
+}
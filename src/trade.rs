@@ -0,0 +1,718 @@
+//! Trade Planning
+//!
+//! Every trade is first worked out as a `TradePlan` from the curve state,
+//! without touching storage. The trade handlers execute the plan and the
+//! quote opcodes serialize it, so a quote is exactly what the trade would do
+//! at the same state.
+
+use crate::bonding_curve::{CurveCalculator, SpreadDestination};
+use crate::constants::{MAX_BUY_PERCENTAGE, MAX_SELL_PERCENTAGE, MAX_SLIPPAGE_BPS, MIN_BUY_AMOUNT};
+use crate::fees::FeeSplit;
+use crate::fixed_point::{self, BPS, ONE, U256};
+use crate::graduation::GraduationMetrics;
+use crate::CurveParams;
+use alkanes_support::utils::overflow_error;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// Layout version of the binary quote returned by the quote opcodes
+pub const QUOTE_VERSION: u128 = 2;
+
+/// Number of little-endian u128 fields in a version 2 quote
+pub const QUOTE_FIELDS: usize = 20;
+
+/// Per-trade limits, fixed at launch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradeLimits {
+    pub min_buy_amount: u128,   // Smallest base amount a buy may spend, fee included
+    pub max_buy_bps: u128,      // Largest buy as a share of the remaining supply
+    pub max_sell_bps: u128,     // Largest sell as a share of the circulating supply
+    pub max_slippage_bps: u128, // Loosest slippage bound a trade accepts, relative to the caller's quote
+}
+
+impl Default for TradeLimits {
+    fn default() -> Self {
+        Self {
+            min_buy_amount: MIN_BUY_AMOUNT,
+            max_buy_bps: MAX_BUY_PERCENTAGE,
+            max_sell_bps: MAX_SELL_PERCENTAGE,
+            max_slippage_bps: MAX_SLIPPAGE_BPS,
+        }
+    }
+}
+
+impl TradeLimits {
+    pub fn validate(&self) -> Result<()> {
+        if self.max_buy_bps == 0 || self.max_buy_bps > BPS {
+            return Err(anyhow!("Max buy must be between 1 and {} bps", BPS));
+        }
+        if self.max_sell_bps == 0 || self.max_sell_bps > BPS {
+            return Err(anyhow!("Max sell must be between 1 and {} bps", BPS));
+        }
+        if self.max_slippage_bps > BPS {
+            return Err(anyhow!("Max slippage cannot exceed {} bps", BPS));
+        }
+        Ok(())
+    }
+
+    /// Most tokens one buy can take at `supply`; at least one token while
+    /// any remain, so the last few tokens can still be bought
+    pub fn max_buy_tokens(&self, supply: u128, max_supply: u128) -> Result<u128> {
+        let remaining = max_supply.saturating_sub(supply);
+        Ok(fixed_point::mul_div(remaining, self.max_buy_bps, BPS)?.max(remaining.min(1)))
+    }
+
+    /// Most tokens one sell can burn at `supply`; at least one token while
+    /// any circulate, so a small supply never rounds the cap down to nothing
+    pub fn max_sell_tokens(&self, supply: u128) -> Result<u128> {
+        Ok(fixed_point::mul_div(supply, self.max_sell_bps, BPS)?.max(supply.min(1)))
+    }
+
+    /// Reject a plan that breaks the size limits
+    pub fn check(&self, plan: &TradePlan, state: &CurveState, max_supply: u128) -> Result<()> {
+        match plan.side {
+            TradeSide::Buy => {
+                if plan.trader_base < self.min_buy_amount {
+                    return Err(anyhow!("Buy of {} base is below the minimum of {}",
+                        plan.trader_base, self.min_buy_amount));
+                }
+                let cap = self.max_buy_tokens(state.supply, max_supply)?;
+                if plan.tokens > cap {
+                    return Err(anyhow!("Buy of {} tokens exceeds the limit of {} ({} bps of remaining supply)",
+                        plan.tokens, cap, self.max_buy_bps));
+                }
+            },
+            TradeSide::Sell => {
+                let cap = self.max_sell_tokens(state.supply)?;
+                if plan.tokens > cap {
+                    return Err(anyhow!("Sell of {} tokens exceeds the limit of {} ({} bps of circulating supply)",
+                        plan.tokens, cap, self.max_sell_bps));
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Minimum-out bound `max_slippage_bps` below a quoted `outcome`
+    pub fn slippage_floor(&self, outcome: u128) -> Result<u128> {
+        fixed_point::mul_div(outcome, BPS - self.max_slippage_bps, BPS)
+    }
+
+    /// Maximum-in bound `max_slippage_bps` above a quoted `outcome`
+    pub fn slippage_ceiling(&self, outcome: u128) -> Result<u128> {
+        fixed_point::mul_div_up(outcome, BPS + self.max_slippage_bps, BPS)
+    }
+
+    /// Reject a minimum-out bound looser than `slippage_floor` of the
+    /// caller's `quoted` outcome. A zero bound asks for no protection and a
+    /// zero quote leaves nothing to measure the bound against
+    pub fn check_min_out(&self, min_out: u128, quoted: u128) -> Result<()> {
+        if min_out == 0 || quoted == 0 {
+            return Ok(());
+        }
+        let floor = self.slippage_floor(quoted)?;
+        if min_out < floor {
+            return Err(anyhow!("Slippage bound too loose: minimum of {} is more than {} bps under the quoted {}, expected at least {}",
+                min_out, self.max_slippage_bps, quoted, floor));
+        }
+        Ok(())
+    }
+
+    /// Reject a maximum-in bound looser than `slippage_ceiling` of the
+    /// caller's `quoted` outcome; a zero quote leaves it unchecked
+    pub fn check_max_in(&self, max_in: u128, quoted: u128) -> Result<()> {
+        if quoted == 0 {
+            return Ok(());
+        }
+        let ceiling = self.slippage_ceiling(quoted)?;
+        if max_in > ceiling {
+            return Err(anyhow!("Slippage bound too loose: maximum of {} is more than {} bps over the quoted {}, expected at most {}",
+                max_in, self.max_slippage_bps, quoted, ceiling));
+        }
+        Ok(())
+    }
+}
+
+/// Curve state a trade is planned against
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CurveState {
+    pub supply: u128,
+    pub base_reserves: u128,
+    pub blocks_since_launch: u64,
+    pub has_referrer: bool,
+    pub buys: u128,
+}
+
+/// Direction of a trade
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeSide {
+    Buy = 0,
+    Sell = 1,
+}
+
+/// Outcome of a trade against a given `CurveState`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradePlan {
+    pub side: TradeSide,
+    pub tokens: u128,         // Tokens minted (buy) or burned (sell)
+    pub curve_amount: u128,   // Area under the curve: buy cost or sell proceeds before spread
+    pub trader_base: u128,    // Base the trader pays (buy, fee included) or receives (sell)
+    pub dust: u128,           // Base sent with a buy beyond its cost and fee, refunded
+    pub fee: FeeSplit,        // Trading fee by recipient
+    pub spread: u128,         // Sell spread withheld from the payout
+    pub price_before: U256,   // Marginal price before the trade, Q64.64
+    pub price_after: U256,    // Marginal price after the trade, Q64.64
+    pub supply_after: u128,
+    pub reserves_after: u128,
+    pub graduates: bool,      // Whether the trade meets the graduation criteria
+}
+
+impl TradePlan {
+    /// Spend up to `base_amount`: the fee comes off the top and the rest buys
+    /// as many tokens as it covers
+    ///
+    /// Near `max_supply` the buy fills only the remaining supply. Whatever the
+    /// tokens and fee do not use is refunded as dust; only the exact cost
+    /// enters the reserves.
+    pub fn buy_with_base(state: &CurveState, params: &CurveParams, base_amount: u128) -> Result<Self> {
+        let budget = params.fees.budget_before_fee(base_amount)?;
+        let (mut tokens, unspent) = CurveCalculator::tokens_for_base_amount(state.supply, budget, params)?;
+        if tokens == 0 {
+            return Err(anyhow!("Insufficient base amount to buy any tokens"));
+        }
+
+        // A budget past the per-trade cap buys the cap and gets the rest back
+        let mut cost = budget - unspent;
+        let cap = params.limits.max_buy_tokens(state.supply, params.max_supply)?;
+        if tokens > cap {
+            tokens = cap;
+            cost = CurveCalculator::calculate_buy_price(state.supply, cap, params)?;
+        }
+        let fee = params.fees.split(params.fees.fee_on(cost)?, state.has_referrer)?;
+        let dust = base_amount - cost - fee.total();
+        let reserves_after = overflow_error(state.base_reserves.checked_add(cost))?;
+
+        Self::finish(state, params, TradePlan {
+            side: TradeSide::Buy,
+            tokens,
+            curve_amount: cost,
+            trader_base: cost + fee.total(),
+            dust,
+            fee,
+            spread: 0,
+            price_before: U256::ZERO,
+            price_after: U256::ZERO,
+            supply_after: state.supply + tokens,
+            reserves_after,
+            graduates: false,
+        })
+    }
+
+    /// Buy exactly `tokens`, paying their cost plus the fee
+    pub fn buy_exact(state: &CurveState, params: &CurveParams, tokens: u128) -> Result<Self> {
+        if tokens == 0 {
+            return Err(anyhow!("Token amount must be positive"));
+        }
+
+        let cost = CurveCalculator::calculate_buy_price(state.supply, tokens, params)?;
+        let fee = params.fees.split(params.fees.fee_on(cost)?, state.has_referrer)?;
+        let trader_base = overflow_error(cost.checked_add(fee.total()))?;
+        let reserves_after = overflow_error(state.base_reserves.checked_add(cost))?;
+
+        Self::finish(state, params, TradePlan {
+            side: TradeSide::Buy,
+            tokens,
+            curve_amount: cost,
+            trader_base,
+            dust: 0,
+            fee,
+            spread: 0,
+            price_before: U256::ZERO,
+            price_after: U256::ZERO,
+            supply_after: state.supply + tokens,
+            reserves_after,
+            graduates: false,
+        })
+    }
+
+    /// Sell exactly `tokens`; the payout is net of spread and fee
+    pub fn sell_exact(state: &CurveState, params: &CurveParams, tokens: u128) -> Result<Self> {
+        if tokens == 0 {
+            return Err(anyhow!("Token amount must be positive"));
+        }
+
+        let (payout, spread) = CurveCalculator::sell_payout_at(
+            state.supply, tokens, params, state.blocks_since_launch
+        )?;
+        let fee = params.fees.split(params.fees.fee_on(payout)?, state.has_referrer)?;
+
+        Self::finish(state, params, TradePlan {
+            side: TradeSide::Sell,
+            tokens,
+            curve_amount: payout + spread,
+            trader_base: payout - fee.total(),
+            dust: 0,
+            fee,
+            spread,
+            price_before: U256::ZERO,
+            price_after: U256::ZERO,
+            supply_after: state.supply - tokens,
+            reserves_after: Self::released_reserves(state, params, payout, spread)?,
+            graduates: false,
+        })
+    }
+
+    /// Sell as few tokens as needed to receive exactly `base_amount`
+    ///
+    /// The tokens are sold for enough to cover the fee too; any payout above
+    /// that from rounding stays in reserves.
+    pub fn sell_for_base(state: &CurveState, params: &CurveParams, base_amount: u128) -> Result<Self> {
+        if base_amount == 0 {
+            return Err(anyhow!("Base amount must be positive"));
+        }
+
+        let gross = params.fees.gross_for_net(base_amount)?;
+        let tokens = CurveCalculator::tokens_to_sell_for_base_amount(
+            state.supply, gross, params, state.blocks_since_launch
+        )?;
+        let (payout, spread) = CurveCalculator::sell_payout_at(
+            state.supply, tokens, params, state.blocks_since_launch
+        )?;
+        let fee = params.fees.split(params.fees.fee_on(gross)?, state.has_referrer)?;
+
+        Self::finish(state, params, TradePlan {
+            side: TradeSide::Sell,
+            tokens,
+            curve_amount: payout + spread,
+            trader_base: base_amount,
+            dust: 0,
+            fee,
+            spread,
+            price_before: U256::ZERO,
+            price_after: U256::ZERO,
+            supply_after: state.supply - tokens,
+            reserves_after: Self::released_reserves(state, params, base_amount + fee.total(), spread)?,
+            graduates: false,
+        })
+    }
+
+    /// Reserves left after paying out `released` and, unless it stays in
+    /// reserves, the spread
+    fn released_reserves(state: &CurveState, params: &CurveParams, released: u128, spread: u128) -> Result<u128> {
+        let spread_released = match params.sell_spread.destination {
+            SpreadDestination::Reserves => 0,
+            SpreadDestination::Burn | SpreadDestination::Fees => spread,
+        };
+
+        state.base_reserves
+            .checked_sub(overflow_error(released.checked_add(spread_released))?)
+            .ok_or_else(|| anyhow!("Insufficient reserves for sell"))
+    }
+
+    /// Check the trade limits, then fill in the marginal prices and the
+    /// graduation flag
+    fn finish(state: &CurveState, params: &CurveParams, mut plan: TradePlan) -> Result<Self> {
+        params.limits.check(&plan, state, params.max_supply)?;
+
+        plan.price_before = CurveCalculator::price_at_supply_q64(state.supply, params);
+        plan.price_after = CurveCalculator::price_at_supply_q64(plan.supply_after, params);
+        plan.graduates = plan.side == TradeSide::Buy
+            && CurveCalculator::check_graduation_criteria(&plan.graduation_metrics(state), params);
+        Ok(plan)
+    }
+
+    /// Graduation metrics once the trade has executed, a buy counting itself
+    pub fn graduation_metrics(&self, state: &CurveState) -> GraduationMetrics {
+        let new_buy = u128::from(self.side == TradeSide::Buy);
+        GraduationMetrics {
+            supply: self.supply_after,
+            base_reserves: self.reserves_after,
+            blocks_since_launch: state.blocks_since_launch,
+            buys: state.buys.saturating_add(new_buy),
+        }
+    }
+
+    /// Base paid or received per token in Q64.64, fees and spread included
+    /// (rounded down)
+    pub fn average_price(&self) -> U256 {
+        per_token_q64(self.trader_base, self.tokens)
+    }
+
+    /// Distance of the curve's average price from the marginal price before
+    /// the trade, in basis points
+    pub fn price_impact_bps(&self) -> Result<u128> {
+        if self.price_before.is_zero() || self.tokens == 0 {
+            return Ok(0);
+        }
+
+        let average = per_token_q64(self.curve_amount, self.tokens);
+        let (high, low) = if average > self.price_before {
+            (average, self.price_before)
+        } else {
+            (self.price_before, average)
+        };
+        let distance = high.checked_sub(low).unwrap_or_default();
+
+        // An impact too large for Q64.64 saturates rather than failing the quote
+        let ratio = fixed_point::ratio_q64(distance, self.price_before).unwrap_or(u128::MAX);
+        fixed_point::mul_div(ratio, BPS, ONE)
+    }
+
+    /// Encode as a version 2 quote: `QUOTE_FIELDS` little-endian u128s
+    ///
+    /// Prices are 256-bit Q64.64 values spread over two fields, low word
+    /// first, so sub-unit prices keep their fraction.
+    ///
+    /// | # | Field |
+    /// |---|-------|
+    /// | 0 | version (`QUOTE_VERSION`) |
+    /// | 1 | side (0 = buy, 1 = sell) |
+    /// | 2 | tokens |
+    /// | 3 | base paid (buy) or received (sell) |
+    /// | 4 | dust |
+    /// | 5-6 | average price |
+    /// | 7-8 | marginal price before |
+    /// | 9-10 | marginal price after |
+    /// | 11 | price impact in bps |
+    /// | 12 | total fee |
+    /// | 13 | protocol fee |
+    /// | 14 | creator fee |
+    /// | 15 | referrer fee |
+    /// | 16 | sell spread |
+    /// | 17 | supply after |
+    /// | 18 | base reserves after |
+    /// | 19 | triggers graduation (0 or 1) |
+    pub fn to_quote(&self) -> Result<Vec<u8>> {
+        let average = self.average_price();
+        let fields: [u128; QUOTE_FIELDS] = [
+            QUOTE_VERSION,
+            self.side as u128,
+            self.tokens,
+            self.trader_base,
+            self.dust,
+            average.lo,
+            average.hi,
+            self.price_before.lo,
+            self.price_before.hi,
+            self.price_after.lo,
+            self.price_after.hi,
+            self.price_impact_bps()?,
+            self.fee.total(),
+            self.fee.protocol,
+            self.fee.creator,
+            self.fee.referrer,
+            self.spread,
+            self.supply_after,
+            self.reserves_after,
+            self.graduates as u128,
+        ];
+
+        Ok(fields.iter().flat_map(|field| field.to_le_bytes()).collect())
+    }
+}
+
+/// Q64.64 base per token, rounded down; zero tokens count as one
+fn per_token_q64(amount: u128, tokens: u128) -> U256 {
+    U256::mul_u128(amount, ONE)
+        .div_rem_u128(tokens.max(1))
+        .map_or(U256::ZERO, |(quotient, _)| quotient)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bonding_curve::SellSpread;
+
+    /// The shared linear curve with every size limit lifted
+    fn linear_params() -> CurveParams {
+        CurveParams {
+            limits: TradeLimits {
+                min_buy_amount: 0,
+                max_buy_bps: BPS,
+                max_sell_bps: BPS,
+                ..TradeLimits::default()
+            },
+            ..crate::tests::linear_params()
+        }
+    }
+
+    fn state(supply: u128, base_reserves: u128) -> CurveState {
+        CurveState { supply, base_reserves, ..CurveState::default() }
+    }
+
+    fn field(quote: &[u8], index: usize) -> u128 {
+        u128::from_le_bytes(quote[index * 16..(index + 1) * 16].try_into().unwrap())
+    }
+
+    /// Q64.64 price stored low word first at `index`
+    fn price(quote: &[u8], index: usize) -> U256 {
+        U256 { lo: field(quote, index), hi: field(quote, index + 1) }
+    }
+
+    #[test]
+    fn test_buy_exact_plan() {
+        let params = linear_params();
+        let plan = TradePlan::buy_exact(&state(100_000, 500_000_000), &params, 10_000).unwrap();
+
+        let cost = CurveCalculator::calculate_buy_price(100_000, 10_000, &params).unwrap();
+        assert_eq!(plan.curve_amount, cost);
+        assert_eq!(plan.fee.total(), params.fees.fee_on(cost).unwrap());
+        assert_eq!(plan.trader_base, cost + plan.fee.total());
+        assert_eq!(plan.supply_after, 110_000);
+        assert_eq!(plan.reserves_after, 500_000_000 + cost);
+        assert_eq!(plan.price_before, U256::mul_u128(11_000, ONE));
+        assert_eq!(plan.price_after, U256::mul_u128(12_000, ONE));
+        assert!(!plan.graduates);
+    }
+
+    #[test]
+    fn test_buy_with_base_accounts_for_everything() {
+        let params = linear_params();
+        let plan = TradePlan::buy_with_base(&state(0, 0), &params, 123_456_789).unwrap();
+
+        assert_eq!(plan.curve_amount + plan.fee.total() + plan.dust, 123_456_789);
+
+        // The same tokens bought exactly cost what the spend-all buy paid
+        let exact = TradePlan::buy_exact(&state(0, 0), &params, plan.tokens).unwrap();
+        assert_eq!(exact.curve_amount, plan.curve_amount);
+        assert_eq!(exact.fee, plan.fee);
+    }
+
+    #[test]
+    fn test_buy_with_base_partial_fill_at_max_supply() {
+        let params = linear_params();
+        let plan = TradePlan::buy_with_base(&state(999_990, 7_000), &params, 10_000_000_000).unwrap();
+
+        // Only the last 10 tokens are left to buy
+        let cost = CurveCalculator::calculate_buy_price(999_990, 10, &params).unwrap();
+        assert_eq!(plan.tokens, 10);
+        assert_eq!(plan.curve_amount, cost);
+        assert_eq!(plan.dust, 10_000_000_000 - cost - plan.fee.total());
+        assert_eq!(plan.reserves_after, 7_000 + cost);
+        assert_eq!(plan.supply_after, 1_000_000);
+    }
+
+    #[test]
+    fn test_buy_with_base_quote_reports_dust() {
+        let params = linear_params();
+        let plan = TradePlan::buy_with_base(&state(100_000, 0), &params, 50_000_000).unwrap();
+
+        // Same inverse as the curve, on the budget left after the fee
+        let budget = params.fees.budget_before_fee(50_000_000).unwrap();
+        let (tokens, unspent) = CurveCalculator::tokens_for_base_amount(100_000, budget, &params).unwrap();
+        assert_eq!(plan.tokens, tokens);
+        assert!(plan.dust >= unspent);
+
+        let quote = plan.to_quote().unwrap();
+        assert_eq!(field(&quote, 2), tokens);
+        assert_eq!(field(&quote, 3), 50_000_000 - plan.dust);
+        assert_eq!(field(&quote, 4), plan.dust);
+        assert_eq!(field(&quote, 12), plan.fee.total());
+        assert_eq!(field(&quote, 11), plan.price_impact_bps().unwrap());
+
+        // Too little to buy a single token
+        assert!(TradePlan::buy_with_base(&state(100_000, 0), &params, 100).is_err());
+    }
+
+    #[test]
+    fn test_sell_plan_releases_payout_fee_and_spread() {
+        let params = CurveParams {
+            sell_spread: SellSpread {
+                destination: SpreadDestination::Burn,
+                ..SellSpread::flat(200)
+            },
+            ..linear_params()
+        };
+        let reserves = CurveCalculator::calculate_buy_price(0, 200_000, &params).unwrap();
+        let plan = TradePlan::sell_exact(&state(200_000, reserves), &params, 50_000).unwrap();
+
+        let proceeds = CurveCalculator::calculate_sell_proceeds(200_000, 50_000, &params).unwrap();
+        assert_eq!(plan.curve_amount, proceeds);
+        assert_eq!(plan.trader_base + plan.fee.total() + plan.spread, proceeds);
+        assert_eq!(plan.reserves_after, reserves - proceeds);
+        assert_eq!(plan.supply_after, 150_000);
+        assert!(plan.price_after < plan.price_before);
+        assert!(!plan.graduates);
+
+        // Kept in reserves, the spread still backs the remaining supply
+        let params = CurveParams {
+            sell_spread: SellSpread::flat(200),
+            ..linear_params()
+        };
+        let kept = TradePlan::sell_exact(&state(200_000, reserves), &params, 50_000).unwrap();
+        assert_eq!(kept.reserves_after, reserves - kept.trader_base - kept.fee.total());
+    }
+
+    #[test]
+    fn test_sell_for_base_pays_exact_amount() {
+        let params = linear_params();
+        let reserves = CurveCalculator::calculate_buy_price(0, 200_000, &params).unwrap();
+        let plan = TradePlan::sell_for_base(&state(200_000, reserves), &params, 100_000_000).unwrap();
+
+        assert_eq!(plan.trader_base, 100_000_000);
+        let fewer = TradePlan::sell_exact(&state(200_000, reserves), &params, plan.tokens - 1).unwrap();
+        assert!(fewer.trader_base < 100_000_000);
+    }
+
+    #[test]
+    fn test_sell_rejects_insufficient_reserves() {
+        let params = linear_params();
+        assert!(TradePlan::sell_exact(&state(200_000, 1_000), &params, 50_000).is_err());
+    }
+
+    #[test]
+    fn test_price_impact_grows_with_size() {
+        let params = linear_params();
+        let small = TradePlan::buy_exact(&state(100_000, 0), &params, 1_000).unwrap();
+        let large = TradePlan::buy_exact(&state(100_000, 0), &params, 100_000).unwrap();
+
+        // Average of 11_000 and 12_000 on a linear curve is 4.5% above 11_000
+        let medium = TradePlan::buy_exact(&state(100_000, 0), &params, 10_000).unwrap();
+        assert_eq!(medium.price_impact_bps().unwrap(), 454);
+
+        assert!(small.price_impact_bps().unwrap() < medium.price_impact_bps().unwrap());
+        assert!(medium.price_impact_bps().unwrap() < large.price_impact_bps().unwrap());
+    }
+
+    #[test]
+    fn test_graduation_flag() {
+        let params = linear_params();
+        let plan = TradePlan::buy_exact(&state(0, 0), &params, 400_000).unwrap();
+        assert!(plan.graduates);
+    }
+
+    #[test]
+    fn test_graduation_counts_the_buy() {
+        use crate::graduation::{CriteriaMode, GraduationCondition, GraduationCriteria};
+
+        let params = CurveParams {
+            graduation_criteria: Some(GraduationCriteria {
+                mode: CriteriaMode::All,
+                conditions: vec![GraduationCondition::Buys(3), GraduationCondition::MinAge(10)],
+            }),
+            ..linear_params()
+        };
+        let aged = CurveState { buys: 2, blocks_since_launch: 10, ..state(0, 0) };
+
+        // This is the third buy
+        assert!(TradePlan::buy_exact(&aged, &params, 1_000).unwrap().graduates);
+        assert!(!TradePlan::buy_exact(&CurveState { buys: 1, ..aged }, &params, 1_000).unwrap().graduates);
+        assert!(!TradePlan::buy_exact(&CurveState { blocks_since_launch: 9, ..aged }, &params, 1_000).unwrap().graduates);
+    }
+
+    #[test]
+    fn test_trade_limits() {
+        let params = CurveParams {
+            limits: TradeLimits::default(),
+            ..linear_params()
+        };
+        assert!(params.limits.validate().is_ok());
+
+        // 10% of the 900_000 remaining tokens
+        assert!(TradePlan::buy_exact(&state(100_000, 0), &params, 90_000).is_ok());
+        let err = TradePlan::buy_exact(&state(100_000, 0), &params, 90_001).unwrap_err();
+        assert!(err.to_string().contains("exceeds the limit of 90000"));
+
+        // 20% of the 100_000 circulating tokens
+        let reserves = CurveCalculator::calculate_buy_price(0, 100_000, &params).unwrap();
+        assert!(TradePlan::sell_exact(&state(100_000, reserves), &params, 20_000).is_ok());
+        assert!(TradePlan::sell_exact(&state(100_000, reserves), &params, 20_001).is_err());
+
+        // Dust buys
+        assert!(TradePlan::buy_exact(&state(0, 0), &params, 1).is_err());
+        assert!(TradePlan::buy_with_base(&state(0, 0), &params, MIN_BUY_AMOUNT - 1).is_err());
+        assert!(TradePlan::buy_with_base(&state(100_000, 0), &params, MIN_BUY_AMOUNT * 10).is_ok());
+
+        // Spending past the buy cap fills the cap and returns the rest
+        let cost = CurveCalculator::calculate_buy_price(100_000, 90_000, &params).unwrap();
+        let capped = TradePlan::buy_with_base(&state(100_000, 0), &params, cost * 2).unwrap();
+        assert_eq!(capped.tokens, 90_000);
+        assert_eq!(capped.curve_amount, cost);
+        assert_eq!(capped.dust, cost * 2 - capped.trader_base);
+
+        // The last few tokens stay buyable one at a time
+        assert_eq!(params.limits.max_buy_tokens(999_995, 1_000_000).unwrap(), 1);
+        assert_eq!(params.limits.max_buy_tokens(1_000_000, 1_000_000).unwrap(), 0);
+
+        // A supply too small for the share to reach a token may still sell one
+        assert_eq!(params.limits.max_sell_tokens(4).unwrap(), 1);
+        assert_eq!(params.limits.max_sell_tokens(0).unwrap(), 0);
+
+        let invalid = TradeLimits { max_sell_bps: 0, ..TradeLimits::default() };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_slippage_bounds() {
+        let limits = TradeLimits::default(); // 5%
+
+        assert_eq!(limits.slippage_floor(1_000).unwrap(), 950);
+        assert_eq!(limits.slippage_ceiling(1_000).unwrap(), 1_050);
+        assert_eq!(limits.slippage_ceiling(999).unwrap(), 1_049);
+
+        // Bounds at the tolerance of the quote pass, looser ones are refused
+        assert!(limits.check_min_out(950, 1_000).is_ok());
+        assert!(limits.check_min_out(949, 1_000).is_err());
+        assert!(limits.check_max_in(1_050, 1_000).is_ok());
+        assert!(limits.check_max_in(1_051, 1_000).is_err());
+        assert!(limits.check_max_in(u128::MAX, 1_000).is_err());
+
+        // No bound, or no quote to hold it against, is left to the caller
+        assert!(limits.check_min_out(0, 1_000).is_ok());
+        assert!(limits.check_min_out(1, 0).is_ok());
+        assert!(limits.check_max_in(u128::MAX, 0).is_ok());
+
+        // A zero tolerance takes only exact bounds
+        let strict = TradeLimits { max_slippage_bps: 0, ..limits };
+        assert!(strict.check_min_out(1_000, 1_000).is_ok());
+        assert!(strict.check_min_out(999, 1_000).is_err());
+    }
+
+    #[test]
+    fn test_quote_layout() {
+        let params = linear_params();
+        let plan = TradePlan::buy_exact(&state(100_000, 500_000_000), &params, 10_000).unwrap();
+        let quote = plan.to_quote().unwrap();
+
+        assert_eq!(quote.len(), QUOTE_FIELDS * 16);
+        assert_eq!(field(&quote, 0), QUOTE_VERSION);
+        assert_eq!(field(&quote, 1), TradeSide::Buy as u128);
+        assert_eq!(field(&quote, 2), 10_000);
+        assert_eq!(field(&quote, 3), plan.trader_base);
+        assert_eq!(price(&quote, 5), plan.average_price());
+        assert_eq!(price(&quote, 5).shr(64).lo, plan.trader_base / 10_000);
+        assert_eq!(price(&quote, 7), U256::mul_u128(11_000, ONE));
+        assert_eq!(price(&quote, 9), U256::mul_u128(12_000, ONE));
+        assert_eq!(field(&quote, 11), 454);
+        assert_eq!(field(&quote, 12), plan.fee.total());
+        assert_eq!(field(&quote, 13) + field(&quote, 14) + field(&quote, 15), plan.fee.total());
+        assert_eq!(field(&quote, 17), 110_000);
+        assert_eq!(field(&quote, 18), plan.reserves_after);
+        assert_eq!(field(&quote, 19), 0);
+    }
+
+    #[test]
+    fn test_quote_keeps_sub_unit_prices() {
+        // One base unit buys 100 tokens, a price the whole-unit view rounds to zero
+        let plan = TradePlan {
+            side: TradeSide::Buy,
+            tokens: 100,
+            curve_amount: 1,
+            trader_base: 1,
+            dust: 0,
+            fee: FeeSplit::default(),
+            spread: 0,
+            price_before: U256::from_u128(ONE / 200),
+            price_after: U256::from_u128(ONE / 100),
+            supply_after: 100,
+            reserves_after: 1,
+            graduates: false,
+        };
+        let quote = plan.to_quote().unwrap();
+
+        assert_eq!(price(&quote, 5), U256::from_u128(ONE / 100));
+        assert_eq!(price(&quote, 7), U256::from_u128(ONE / 200));
+        // An average of 0.01 is 100% above a marginal 0.005
+        assert_eq!(field(&quote, 11), BPS);
+    }
+}